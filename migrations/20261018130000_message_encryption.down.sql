@@ -0,0 +1,3 @@
+ALTER TABLE transmission
+  DROP COLUMN IF EXISTS message_data_key,
+  DROP COLUMN IF EXISTS message_key_id;
@@ -0,0 +1,3 @@
+ALTER TABLE transmission
+  ADD COLUMN IF NOT EXISTS message_key_id TEXT NULL,
+  ADD COLUMN IF NOT EXISTS message_data_key TEXT NULL;
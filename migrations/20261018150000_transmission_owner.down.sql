@@ -0,0 +1,2 @@
+ALTER TABLE transmission
+  DROP COLUMN IF EXISTS owner;
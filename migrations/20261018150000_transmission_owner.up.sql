@@ -0,0 +1,2 @@
+ALTER TABLE transmission
+  ADD COLUMN IF NOT EXISTS owner TEXT NULL;
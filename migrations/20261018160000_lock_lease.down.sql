@@ -0,0 +1,10 @@
+ALTER TABLE transmission
+  ADD COLUMN IF NOT EXISTS is_locked BOOLEAN NOT NULL DEFAULT false;
+
+UPDATE transmission
+SET is_locked = locked_until IS NOT NULL;
+
+ALTER TABLE transmission
+  ALTER COLUMN is_locked DROP DEFAULT,
+  DROP COLUMN IF EXISTS locked_until,
+  DROP COLUMN IF EXISTS locked_by;
@@ -0,0 +1,11 @@
+ALTER TABLE transmission
+  ADD COLUMN IF NOT EXISTS locked_by TEXT NULL,
+  ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ NULL;
+
+-- Locks taken before leases existed never expire, so they become leases that expired already.
+UPDATE transmission
+SET locked_until = now()
+WHERE is_locked = true;
+
+ALTER TABLE transmission
+  DROP COLUMN IF EXISTS is_locked;
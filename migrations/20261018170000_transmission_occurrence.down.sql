@@ -0,0 +1,20 @@
+DROP INDEX IF EXISTS transmission_next_idx;
+
+ALTER TABLE transmission
+  DROP CONSTRAINT transmission_pkey,
+  ADD PRIMARY KEY (id, transmission_count);
+
+-- Past occurrences become superseded rows again, of which the message equals the current state.
+INSERT INTO transmission (
+  id, inserted_at, schedule, next, transmission_count, message, delivered_destinations,
+  message_key_id, message_data_key, message_compression, owner
+)
+SELECT transmission.id, occurrence.transmitted_at - interval '1 microsecond',
+  transmission.schedule, occurrence.scheduled_at, occurrence.transmission_count,
+  transmission.message, '{}', transmission.message_key_id, transmission.message_data_key,
+  transmission.message_compression, transmission.owner
+FROM transmission_occurrence occurrence
+JOIN transmission ON transmission.id = occurrence.transmission_id
+ON CONFLICT DO NOTHING;
+
+DROP TABLE IF EXISTS transmission_occurrence;
@@ -0,0 +1,35 @@
+CREATE TABLE IF NOT EXISTS transmission_occurrence (
+  transmission_id UUID NOT NULL,
+  transmission_count INTEGER NOT NULL,
+  scheduled_at TIMESTAMPTZ NULL,
+  transmitted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  PRIMARY KEY (transmission_id, transmission_count)
+);
+
+-- Each row that was superseded by a later occurrence of its transmission was transmitted when its
+-- successor was inserted.
+INSERT INTO transmission_occurrence (
+  transmission_id, transmission_count, scheduled_at, transmitted_at
+)
+SELECT id, transmission_count, next, transmitted_at
+FROM (
+  SELECT id, transmission_count, next,
+    LEAD(inserted_at) OVER (PARTITION BY id ORDER BY transmission_count) AS transmitted_at
+  FROM transmission
+) occurrences
+WHERE transmitted_at IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+-- Only the current state of each transmission remains.
+DELETE FROM transmission
+USING transmission successor
+WHERE successor.id = transmission.id
+  AND successor.transmission_count > transmission.transmission_count;
+
+ALTER TABLE transmission
+  DROP CONSTRAINT transmission_pkey,
+  ADD PRIMARY KEY (id);
+
+CREATE INDEX IF NOT EXISTS transmission_next_idx
+  ON transmission (next)
+  WHERE next IS NOT NULL;
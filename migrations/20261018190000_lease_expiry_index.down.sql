@@ -0,0 +1 @@
+DROP INDEX IF EXISTS transmission_locked_until_idx;
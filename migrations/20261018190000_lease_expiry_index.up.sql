@@ -0,0 +1,4 @@
+-- The expiry of leases is looked up to determine when the next transmission is due.
+CREATE INDEX IF NOT EXISTS transmission_locked_until_idx
+  ON transmission (locked_until)
+  WHERE locked_until IS NOT NULL;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS transmission_occurrence;
+DROP INDEX IF EXISTS transmission_next_idx;
+DROP TABLE IF EXISTS transmission;
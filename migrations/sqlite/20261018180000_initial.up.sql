@@ -0,0 +1,26 @@
+-- Timestamps are stored as RFC 3339 text in UTC, which sorts chronologically.
+CREATE TABLE IF NOT EXISTS transmission (
+  id BLOB NOT NULL PRIMARY KEY,
+  inserted_at TEXT NOT NULL,
+  schedule TEXT NOT NULL,
+  next TEXT NULL,
+  transmission_count INTEGER NOT NULL,
+  message TEXT NOT NULL,
+  -- delivered_destinations is a JSON array of the indices of delivered fan out destinations.
+  delivered_destinations TEXT NOT NULL DEFAULT '[]',
+  owner TEXT NULL,
+  locked_by TEXT NULL,
+  locked_until TEXT NULL
+);
+
+CREATE INDEX IF NOT EXISTS transmission_next_idx
+  ON transmission (next)
+  WHERE next IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS transmission_occurrence (
+  transmission_id BLOB NOT NULL,
+  transmission_count INTEGER NOT NULL,
+  scheduled_at TEXT NULL,
+  transmitted_at TEXT NOT NULL,
+  PRIMARY KEY (transmission_id, transmission_count)
+);
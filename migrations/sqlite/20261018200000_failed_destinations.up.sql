@@ -0,0 +1,3 @@
+-- failed_destinations is a JSON array of the indices of permanently failed fan out destinations.
+ALTER TABLE transmission
+  ADD COLUMN failed_destinations TEXT NOT NULL DEFAULT '[]';
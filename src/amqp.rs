@@ -0,0 +1,44 @@
+use lapin::options::ConfirmSelectOptions;
+use lapin::uri::{AMQPAuthority, AMQPUri, AMQPUserInfo};
+use lapin::{Channel, Connection, ConnectionProperties};
+use log::info;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub vhost: String,
+    pub username: String,
+    pub password: String,
+}
+
+// connect_to_amqp returns a channel in confirm mode, such that the broker acknowledges each
+// publication.
+pub async fn connect_to_amqp(config: Config) -> Result<Channel, Box<dyn std::error::Error>> {
+    info!(
+        "Connecting to amqp://{}:{}/{}",
+        config.host, config.port, config.vhost
+    );
+
+    let uri = AMQPUri {
+        authority: AMQPAuthority {
+            userinfo: AMQPUserInfo {
+                username: config.username,
+                password: config.password,
+            },
+            host: config.host,
+            port: config.port,
+        },
+        vhost: config.vhost,
+        ..Default::default()
+    };
+
+    let connection = Connection::connect_uri(uri, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel
+        .confirm_select(ConfirmSelectOptions::default())
+        .await?;
+
+    Ok(channel)
+}
@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{DecodingKey, Validation};
+use log::debug;
+use ring::digest::{digest, SHA256};
+use serde::Deserialize;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::model::Principal;
+use crate::tls;
+
+// API_KEY_HEADER is the metadata key that carries a static API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+const AUTHORIZATION_HEADER: &str = "authorization";
+const BEARER_PREFIX: &str = "Bearer ";
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub api_keys: ApiKeys,
+    // jwt verifies bearer tokens, if set.
+    pub jwt: Option<JwtConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtConfig {
+    // jwks_path is the path to a JSON Web Key Set file, which holds the keys that tokens may be
+    // signed with.
+    pub jwks_path: String,
+    // issuer and audience are required to match the iss and aud claims of tokens, if set.
+    #[serde(default)]
+    pub issuer: Option<String>,
+    #[serde(default)]
+    pub audience: Option<String>,
+    // principal_claim names the claim that identifies the principal, which defaults to sub.
+    #[serde(default = "default_principal_claim")]
+    pub principal_claim: String,
+}
+
+fn default_principal_claim() -> String {
+    String::from("sub")
+}
+
+// ApiKeys maps the SHA-256 digests of static API keys to the name of the principal they
+// authenticate, such that the keys themselves are not kept in memory.
+#[derive(Clone, Default)]
+pub struct ApiKeys {
+    principals: BTreeMap<Vec<u8>, String>,
+}
+
+// The keys are secret, so only the principals are printed.
+impl fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiKeys")
+            .field(
+                "principals",
+                &self.principals.values().collect::<Vec<&String>>(),
+            )
+            .finish()
+    }
+}
+
+impl ApiKeys {
+    // parse reads keys formatted as `<principal>:<key>`, separated by newlines or commas.
+    pub fn parse(keys: &str) -> Result<ApiKeys, Box<dyn Error>> {
+        let mut principals = BTreeMap::new();
+
+        for entry in keys
+            .split(['\n', ','])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (principal, key) = entry
+                .split_once(':')
+                .ok_or("api key must be formatted as '<principal>:<key>'")?;
+            let (principal, key) = (principal.trim(), key.trim());
+            if principal.is_empty() {
+                return Err("api key principal cannot be empty".into());
+            }
+            if key.is_empty() {
+                return Err(format!("api key of principal '{principal}' cannot be empty").into());
+            }
+
+            if principals
+                .insert(hash(key), principal.to_string())
+                .is_some()
+            {
+                return Err(format!("api key of principal '{principal}' is not unique").into());
+            }
+        }
+
+        if principals.is_empty() {
+            return Err("at least one api key is required".into());
+        }
+
+        Ok(ApiKeys { principals })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.principals.is_empty()
+    }
+
+    fn principal(&self, key: &str) -> Option<&String> {
+        self.principals.get(&hash(key))
+    }
+}
+
+fn hash(key: &str) -> Vec<u8> {
+    digest(&SHA256, key.as_bytes()).as_ref().to_vec()
+}
+
+// Authenticator identifies the caller of a request by its API key or bearer token, and attaches
+// the authenticated Principal to the request. Requests without valid credentials are rejected.
+#[derive(Clone)]
+pub struct Authenticator {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    api_keys: ApiKeys,
+    jwt: Option<JwtVerifier>,
+}
+
+struct JwtVerifier {
+    jwks: JwkSet,
+    config: JwtConfig,
+}
+
+impl Authenticator {
+    // new reads the JWKS file, if JWTs are configured.
+    pub fn new(config: Config) -> Result<Authenticator, Box<dyn Error>> {
+        let jwt = match config.jwt {
+            Some(jwt_config) => {
+                let contents = std::fs::read_to_string(&jwt_config.jwks_path)
+                    .map_err(|err| format!("reading jwks from {}: {err}", jwt_config.jwks_path))?;
+                let jwks: JwkSet = serde_json::from_str(&contents)
+                    .map_err(|err| format!("parsing jwks from {}: {err}", jwt_config.jwks_path))?;
+                if jwks.keys.is_empty() {
+                    return Err(format!("jwks {} contains no keys", jwt_config.jwks_path).into());
+                }
+
+                Some(JwtVerifier {
+                    jwks,
+                    config: jwt_config,
+                })
+            }
+            None => None,
+        };
+
+        Ok(Authenticator {
+            inner: Arc::new(Inner {
+                api_keys: config.api_keys,
+                jwt,
+            }),
+        })
+    }
+
+    // principal identifies the caller by the credentials in the metadata of its request, or else
+    // by the certificate it presented for mutual TLS.
+    fn principal(&self, request: &Request<()>) -> Result<Principal, String> {
+        let metadata = request.metadata();
+        if let Some(api_key) = metadata.get(API_KEY_HEADER) {
+            let principal = api_key
+                .to_str()
+                .ok()
+                .and_then(|api_key| self.inner.api_keys.principal(api_key));
+
+            return match principal {
+                Some(name) => Ok(Principal { name: name.clone() }),
+                None => Err(String::from("invalid api key")),
+            };
+        }
+
+        if let Some(authorization) = metadata.get(AUTHORIZATION_HEADER) {
+            let token = authorization
+                .to_str()
+                .ok()
+                .and_then(|authorization| authorization.strip_prefix(BEARER_PREFIX))
+                .ok_or("authorization must be a bearer token")?;
+
+            return match &self.inner.jwt {
+                Some(verifier) => verifier.verify(token.trim()).map_err(|err| {
+                    debug!("rejected bearer token: {err}");
+
+                    String::from("invalid bearer token")
+                }),
+                None => Err(String::from("bearer tokens are not accepted")),
+            };
+        }
+
+        // The certificates are only present if the client ca verified them during the handshake.
+        if let Some(certificates) = request.peer_certs() {
+            return certificates
+                .first()
+                .and_then(|certificate| tls::principal(certificate.get_ref()))
+                .ok_or_else(|| String::from("client certificate has no subject"));
+        }
+
+        Err(format!(
+            "either {API_KEY_HEADER} or {AUTHORIZATION_HEADER} is required"
+        ))
+    }
+}
+
+// Authenticator intercepts requests, to reject those without valid credentials and attach the
+// Principal to the others.
+impl Interceptor for Authenticator {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let principal = self.principal(&request).map_err(Status::unauthenticated)?;
+        debug!("authenticated principal '{}'", principal.name);
+
+        request.extensions_mut().insert(principal);
+
+        Ok(request)
+    }
+}
+
+impl JwtVerifier {
+    // verify asserts the token is signed by the key of the JWKS that it names by its kid, or by
+    // the only key if it names none, and returns the principal named by its claims.
+    fn verify(&self, token: &str) -> Result<Principal, Box<dyn Error>> {
+        let header = jsonwebtoken::decode_header(token)?;
+        let jwk = match &header.kid {
+            Some(kid) => self
+                .jwks
+                .find(kid)
+                .ok_or_else(|| format!("unknown key id '{kid}'"))?,
+            None if self.jwks.keys.len() == 1 => &self.jwks.keys[0],
+            None => return Err("token must name its key id".into()),
+        };
+
+        // The key must be used with the algorithm it is published for, if any, rather than
+        // whichever algorithm the token claims.
+        if let Some(key_algorithm) = jwk.common.key_algorithm {
+            if key_algorithm.to_string() != format!("{:?}", header.alg) {
+                return Err(format!(
+                    "token algorithm {:?} does not match key algorithm {key_algorithm}",
+                    header.alg
+                )
+                .into());
+            }
+        }
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(issuer) = &self.config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = jsonwebtoken::decode::<serde_json::Map<String, serde_json::Value>>(
+            token,
+            &DecodingKey::from_jwk(jwk)?,
+            &validation,
+        )?
+        .claims;
+
+        match claims
+            .get(&self.config.principal_claim)
+            .and_then(|principal| principal.as_str())
+        {
+            Some(name) if !name.is_empty() => Ok(Principal {
+                name: name.to_string(),
+            }),
+            _ => Err(format!("claim '{}' is required", self.config.principal_claim).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::{EncodingKey, Header};
+    use tonic::metadata::MetadataValue;
+
+    const SECRET: &[u8] = b"an arbitrary secret of sufficient length";
+
+    fn token(kid: Option<&str>, claims: serde_json::Value) -> String {
+        let header = Header {
+            kid: kid.map(String::from),
+            ..Header::new(jsonwebtoken::Algorithm::HS256)
+        };
+
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(SECRET))
+            .expect("token should encode")
+    }
+
+    fn authenticator() -> Authenticator {
+        let jwks_path = std::env::temp_dir().join(format!("jwks_{}.json", uuid::Uuid::new_v4()));
+        let jwks = serde_json::json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "2026-10",
+                "alg": "HS256",
+                "k": URL_SAFE_NO_PAD.encode(SECRET),
+            }]
+        });
+        std::fs::write(&jwks_path, jwks.to_string()).expect("jwks should be written");
+
+        let authenticator = Authenticator::new(Config {
+            api_keys: ApiKeys::parse("orders:first-key,\nbilling:second-key")
+                .expect("api keys should parse"),
+            jwt: Some(JwtConfig {
+                jwks_path: jwks_path.to_string_lossy().to_string(),
+                issuer: Some(String::from("https://auth.example.com")),
+                audience: Some(String::from("transmit")),
+                principal_claim: default_principal_claim(),
+            }),
+        })
+        .expect("authenticator should be constructed");
+
+        std::fs::remove_file(jwks_path).expect("jwks should be removed");
+
+        authenticator
+    }
+
+    #[test]
+    fn test_authenticate() {
+        struct TestCase {
+            name: String,
+            metadata: Vec<(&'static str, String)>,
+            expected_principal: Option<String>,
+        }
+
+        let expires_at = chrono::Utc::now().timestamp() + 60;
+        let valid_claims = serde_json::json!({
+            "sub": "shipping",
+            "iss": "https://auth.example.com",
+            "aud": "transmit",
+            "exp": expires_at,
+        });
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("no credentials"),
+                metadata: vec![],
+                expected_principal: None,
+            },
+            TestCase {
+                name: String::from("valid api key"),
+                metadata: vec![(API_KEY_HEADER, String::from("second-key"))],
+                expected_principal: Some(String::from("billing")),
+            },
+            TestCase {
+                name: String::from("invalid api key"),
+                metadata: vec![(API_KEY_HEADER, String::from("third-key"))],
+                expected_principal: None,
+            },
+            TestCase {
+                name: String::from("valid bearer token"),
+                metadata: vec![(
+                    AUTHORIZATION_HEADER,
+                    format!("Bearer {}", token(Some("2026-10"), valid_claims.clone())),
+                )],
+                expected_principal: Some(String::from("shipping")),
+            },
+            TestCase {
+                name: String::from("token without bearer prefix"),
+                metadata: vec![(
+                    AUTHORIZATION_HEADER,
+                    token(Some("2026-10"), valid_claims.clone()),
+                )],
+                expected_principal: None,
+            },
+            TestCase {
+                name: String::from("token of unknown key"),
+                metadata: vec![(
+                    AUTHORIZATION_HEADER,
+                    format!("Bearer {}", token(Some("2025-01"), valid_claims.clone())),
+                )],
+                expected_principal: None,
+            },
+            TestCase {
+                name: String::from("expired token"),
+                metadata: vec![(
+                    AUTHORIZATION_HEADER,
+                    format!(
+                        "Bearer {}",
+                        token(
+                            Some("2026-10"),
+                            serde_json::json!({
+                                "sub": "shipping",
+                                "iss": "https://auth.example.com",
+                                "aud": "transmit",
+                                "exp": expires_at - 3600,
+                            })
+                        )
+                    ),
+                )],
+                expected_principal: None,
+            },
+            TestCase {
+                name: String::from("token of other audience"),
+                metadata: vec![(
+                    AUTHORIZATION_HEADER,
+                    format!(
+                        "Bearer {}",
+                        token(
+                            Some("2026-10"),
+                            serde_json::json!({
+                                "sub": "shipping",
+                                "iss": "https://auth.example.com",
+                                "aud": "other",
+                                "exp": expires_at,
+                            })
+                        )
+                    ),
+                )],
+                expected_principal: None,
+            },
+            TestCase {
+                name: String::from("token without principal claim"),
+                metadata: vec![(
+                    AUTHORIZATION_HEADER,
+                    format!(
+                        "Bearer {}",
+                        token(
+                            Some("2026-10"),
+                            serde_json::json!({
+                                "iss": "https://auth.example.com",
+                                "aud": "transmit",
+                                "exp": expires_at,
+                            })
+                        )
+                    ),
+                )],
+                expected_principal: None,
+            },
+        ];
+
+        let mut authenticator = authenticator();
+        for test_case in test_cases {
+            let mut request = Request::new(());
+            for (key, value) in test_case.metadata {
+                request.metadata_mut().insert(
+                    key,
+                    MetadataValue::try_from(value).expect("metadata value should be valid"),
+                );
+            }
+
+            let result = authenticator.call(request);
+            match (result, test_case.expected_principal) {
+                (Ok(request), Some(expected_principal)) => assert_eq!(
+                    request.extensions().get::<Principal>(),
+                    Some(&Principal {
+                        name: expected_principal
+                    }),
+                    "test case failed: {}",
+                    test_case.name
+                ),
+                (Err(status), None) => assert_eq!(
+                    status.code(),
+                    tonic::Code::Unauthenticated,
+                    "test case failed: {}",
+                    test_case.name
+                ),
+                (result, _) => panic!(
+                    "test case failed: {}: unexpected result {:?}",
+                    test_case.name,
+                    result.map(|request| request.extensions().get::<Principal>().cloned())
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_api_keys() {
+        assert!(ApiKeys::parse("orders:first-key").is_ok());
+        assert!(ApiKeys::parse("").is_err());
+        assert!(ApiKeys::parse("first-key").is_err());
+        assert!(ApiKeys::parse(":first-key").is_err());
+        assert!(ApiKeys::parse("orders:").is_err());
+        assert!(ApiKeys::parse("orders:first-key,billing:first-key").is_err());
+    }
+}
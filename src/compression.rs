@@ -0,0 +1,100 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub algorithm: Algorithm,
+    // threshold is the size in bytes from which messages are compressed, since compressing small
+    // messages costs more than it saves.
+    pub threshold: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Algorithm {
+    Zstd,
+    Gzip,
+}
+
+// The algorithm is stored alongside each compressed message, such that messages remain readable
+// after the configured algorithm changes.
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::Zstd => write!(f, "zstd"),
+            Algorithm::Gzip => write!(f, "gzip"),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(algorithm: &str) -> Result<Algorithm, String> {
+        match algorithm {
+            "zstd" => Ok(Algorithm::Zstd),
+            "gzip" => Ok(Algorithm::Gzip),
+            _ => Err(format!("unknown compression algorithm '{algorithm}'")),
+        }
+    }
+}
+
+impl Algorithm {
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        match self {
+            Algorithm::Zstd => Ok(zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+            Algorithm::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        match self {
+            Algorithm::Zstd => Ok(zstd::decode_all(data)?),
+            Algorithm::Gzip => {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_decompress() {
+        let data = br#"{"NatsEvent":{"subject":"ORDER.created","payload":"#.repeat(100);
+
+        for algorithm in [Algorithm::Zstd, Algorithm::Gzip] {
+            let compressed = algorithm
+                .compress(&data)
+                .expect("compression should succeed");
+            assert!(
+                compressed.len() < data.len(),
+                "repetitive data should compress with {algorithm}"
+            );
+
+            let decompressed = algorithm
+                .decompress(&compressed)
+                .expect("decompression should succeed");
+            assert_eq!(decompressed, data, "round trip failed with {algorithm}");
+
+            assert_eq!(
+                Algorithm::from_str(&algorithm.to_string()),
+                Ok(algorithm),
+                "algorithm name should parse"
+            );
+        }
+    }
+}
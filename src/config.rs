@@ -1,21 +1,43 @@
 use core::time;
+use std::collections::BTreeMap;
 
 use serde::Deserialize;
 
+use crate::amqp;
+use crate::auth;
 use crate::grpc;
+use crate::kafka;
 use crate::metrics;
+use crate::mqtt;
 use crate::nats;
 use crate::postgres;
+use crate::redis;
+use crate::sink;
+use crate::smtp;
+use crate::wal;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub automigrate: bool,
     pub log_level: log::Level,
+    // clock_cycle_interval is the minimum duration between polls of the repository. Between polls,
+    // the scheduler sleeps until the next transmission is due.
     pub clock_cycle_interval: time::Duration,
+    // exec_allowlist contains the paths of the commands that Exec messages may run.
+    pub exec_allowlist: Vec<String>,
+    // max_payload_size is the size in bytes above which messages are rejected when scheduled. If
+    // unset, the scheduler's default applies.
+    pub max_payload_size: Option<usize>,
+    // json_schemas contains the JSON Schemas by name, which payloads may refer to.
+    pub json_schemas: BTreeMap<String, serde_json::Value>,
+    // nats_subject_acl restricts the subjects that callers may schedule NatsEvent messages onto.
+    pub nats_subject_acl: nats::SubjectAcl,
+    // authentication authenticates the callers of the transport. If unset, callers are anonymous.
+    pub authentication: Option<auth::Config>,
     pub metrics: Metrics,
     pub repository: Repository,
     pub reset_state: bool,
-    pub transmitter: Transmitter,
+    pub transmitters: Vec<Transmitter>,
     pub transport: Transport,
 }
 
@@ -27,11 +49,35 @@ pub enum Transport {
 #[derive(Debug, Clone, Deserialize)]
 pub enum Transmitter {
     Nats(nats::Config),
+    Mqtt(mqtt::Config),
+    Amqp(amqp::Config),
+    Redis(redis::Config),
+    Kafka(kafka::Config),
+    // Grpc calls arbitrary endpoints, as named by each message.
+    Grpc,
+    // Exec runs the commands of the exec allowlist on the host.
+    Exec,
+    Smtp(smtp::Config),
+    // File appends messages without a configured transmitter as JSON lines to a file.
+    File(sink::Config),
+    // Stdout prints messages without a configured transmitter as JSON lines.
+    Stdout,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Deserialize)]
 pub enum Repository {
     Postgres(postgres::Config),
+    // Sqlite stores transmissions in the database file at path, which is created if it does not
+    // exist. Its lease_duration defaults to DEFAULT_LEASE_DURATION, like that of Postgres.
+    Sqlite {
+        path: String,
+        #[serde(default)]
+        lease_duration: Option<time::Duration>,
+    },
+    // Wal stores transmissions in a log file, with periodic snapshots, for environments without a
+    // database.
+    Wal(wal::Config),
     InMemory,
 }
 
@@ -45,6 +91,59 @@ pub fn validate(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
         return Err("clock cycle interval cannot be zero".into());
     }
 
+    if config.transmitters.is_empty() {
+        return Err("at least one transmitter must be configured".into());
+    }
+
+    if config.max_payload_size == Some(0) {
+        return Err("max payload size cannot be zero".into());
+    }
+
+    config.nats_subject_acl.validate()?;
+
+    if let Some(authentication) = &config.authentication {
+        if authentication.api_keys.is_empty() && authentication.jwt.is_none() {
+            return Err("authentication requires api keys, jwt verification or both".into());
+        }
+    }
+
+    if let Some(command) = config
+        .exec_allowlist
+        .iter()
+        .find(|command| !std::path::Path::new(command).is_absolute())
+    {
+        return Err(format!("allowlisted command '{command}' must be an absolute path").into());
+    }
+
+    match &config.repository {
+        Repository::Postgres(postgres_config) => postgres_config.validate()?,
+        Repository::Sqlite { path, .. } if path.is_empty() => {
+            return Err("sqlite database path cannot be empty".into())
+        }
+        Repository::Sqlite {
+            lease_duration: Some(time::Duration::ZERO),
+            ..
+        } => return Err("sqlite lease duration cannot be zero".into()),
+        Repository::Wal(wal_config) => wal_config.validate()?,
+        Repository::Sqlite { .. } | Repository::InMemory => (),
+    }
+
+    for transmitter in &config.transmitters {
+        if let Transmitter::Nats(nats_config) = transmitter {
+            nats_config.validate()?;
+        }
+    }
+
+    // Messages are routed to a transmitter by their variant, so each may be configured once.
+    for (index, transmitter) in config.transmitters.iter().enumerate() {
+        if config.transmitters[..index]
+            .iter()
+            .any(|other| std::mem::discriminant(other) == std::mem::discriminant(transmitter))
+        {
+            return Err("each transmitter can be configured at most once".into());
+        }
+    }
+
     Ok(())
 }
 
@@ -57,17 +156,77 @@ mod tests {
             automigrate: true,
             log_level: log::Level::Info,
             clock_cycle_interval: time::Duration::from_millis(100),
+            exec_allowlist: vec![String::from("/usr/local/bin/backup")],
+            max_payload_size: Some(1024 * 1024),
+            json_schemas: BTreeMap::from([(
+                String::from("order.created"),
+                serde_json::json!({ "type": "object" }),
+            )]),
+            nats_subject_acl: nats::SubjectAcl {
+                deny: vec![String::from("$SYS.>")],
+                ..nats::SubjectAcl::default()
+            },
+            authentication: Some(auth::Config {
+                api_keys: auth::ApiKeys::parse("orders:secret").expect("api keys should parse"),
+                jwt: None,
+            }),
             metrics: Metrics::Prometheus(metrics::Config {
                 port: 3000,
                 endpoint: String::from("/metrics"),
             }),
             repository: Repository::InMemory,
             reset_state: true,
-            transmitter: Transmitter::Nats(nats::Config {
-                port: 3001,
-                host: String::from("127.0.0.1"),
+            transmitters: vec![
+                Transmitter::Nats(nats::Config {
+                    port: 3001,
+                    host: String::from("127.0.0.1"),
+                    servers: vec![],
+                    tls: None,
+                    auth: nats::Auth::Anonymous,
+                }),
+                Transmitter::Mqtt(mqtt::Config {
+                    host: String::from("127.0.0.1"),
+                    port: 3003,
+                    client_id: String::from("transmit"),
+                    username: None,
+                    password: None,
+                }),
+                Transmitter::Amqp(amqp::Config {
+                    host: String::from("127.0.0.1"),
+                    port: 3005,
+                    vhost: String::from("/"),
+                    username: String::from("guest"),
+                    password: String::from("guest"),
+                }),
+                Transmitter::Redis(redis::Config {
+                    host: String::from("127.0.0.1"),
+                    port: 3006,
+                    db: 0,
+                    username: None,
+                    password: None,
+                }),
+                Transmitter::Kafka(kafka::Config {
+                    brokers: vec![String::from("127.0.0.1:3007")],
+                    client_id: String::from("transmit"),
+                }),
+                Transmitter::Grpc,
+                Transmitter::Exec,
+                Transmitter::Smtp(smtp::Config {
+                    host: String::from("127.0.0.1"),
+                    port: 3008,
+                    starttls: true,
+                    username: Some(String::from("transmit")),
+                    password: Some(String::from("secret")),
+                }),
+                Transmitter::File(sink::Config {
+                    path: String::from("transmissions.jsonl"),
+                }),
+                Transmitter::Stdout,
+            ],
+            transport: Transport::Grpc(grpc::Config {
+                port: 3002,
+                tls: None,
             }),
-            transport: Transport::Grpc(grpc::Config { port: 3002 }),
         }
     }
 
@@ -93,6 +252,185 @@ mod tests {
                 },
                 expected_valid: false,
             },
+            TestCase {
+                name: String::from("relative allowlisted command"),
+                config: Config {
+                    exec_allowlist: vec![String::from("backup")],
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("zero max payload size"),
+                config: Config {
+                    max_payload_size: Some(0),
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("invalid subject pattern"),
+                config: Config {
+                    nats_subject_acl: nats::SubjectAcl {
+                        allow: vec![String::from("ORDERS.>.created")],
+                        ..nats::SubjectAcl::default()
+                    },
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("anonymous callers"),
+                config: Config {
+                    authentication: None,
+                    ..config()
+                },
+                expected_valid: true,
+            },
+            TestCase {
+                name: String::from("authentication without credentials"),
+                config: Config {
+                    authentication: Some(auth::Config::default()),
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("invalid nats server"),
+                config: Config {
+                    transmitters: vec![Transmitter::Nats(nats::Config {
+                        port: 3001,
+                        host: String::from("127.0.0.1"),
+                        servers: vec![String::from("nats://127.0.0.1:port")],
+                        tls: None,
+                        auth: nats::Auth::Anonymous,
+                    })],
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("postgres pool minimum exceeds maximum"),
+                config: Config {
+                    repository: Repository::Postgres(postgres::Config {
+                        name: String::from("transmit"),
+                        host: String::from("localhost"),
+                        port: 5432,
+                        user: String::from("postgres"),
+                        password: String::from("postgres"),
+                        ssl_mode: postgres::SslMode::Require,
+                        ssl_root_cert: None,
+                        url: None,
+                        pool: postgres::PoolConfig {
+                            max_connections: Some(2),
+                            min_connections: Some(4),
+                            ..postgres::PoolConfig::default()
+                        },
+                        lease_duration: None,
+                        message_keyring: None,
+                        message_compression: None,
+                    }),
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("invalid postgres url"),
+                config: Config {
+                    repository: Repository::Postgres(postgres::Config {
+                        name: String::new(),
+                        host: String::new(),
+                        port: 5432,
+                        user: String::new(),
+                        password: String::new(),
+                        ssl_mode: postgres::SslMode::default(),
+                        ssl_root_cert: None,
+                        url: Some(String::from("postgres://postgres@localhost:port/transmit")),
+                        pool: postgres::PoolConfig::default(),
+                        lease_duration: None,
+                        message_keyring: None,
+                        message_compression: None,
+                    }),
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("sqlite repository"),
+                config: Config {
+                    repository: Repository::Sqlite {
+                        path: String::from("transmit.db"),
+                        lease_duration: Some(time::Duration::from_secs(60)),
+                    },
+                    ..config()
+                },
+                expected_valid: true,
+            },
+            TestCase {
+                name: String::from("empty sqlite path"),
+                config: Config {
+                    repository: Repository::Sqlite {
+                        path: String::new(),
+                        lease_duration: None,
+                    },
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("zero sqlite lease duration"),
+                config: Config {
+                    repository: Repository::Sqlite {
+                        path: String::from("transmit.db"),
+                        lease_duration: Some(time::Duration::ZERO),
+                    },
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("zero wal fsync interval"),
+                config: Config {
+                    repository: Repository::Wal(wal::Config {
+                        directory: String::from("/var/lib/transmit"),
+                        fsync: wal::FsyncPolicy::Interval(time::Duration::ZERO),
+                        snapshot_threshold: wal::DEFAULT_SNAPSHOT_THRESHOLD,
+                    }),
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("no transmitters"),
+                config: Config {
+                    transmitters: vec![],
+                    ..config()
+                },
+                expected_valid: false,
+            },
+            TestCase {
+                name: String::from("duplicate transmitter"),
+                config: Config {
+                    transmitters: vec![
+                        Transmitter::Nats(nats::Config {
+                            port: 3001,
+                            host: String::from("127.0.0.1"),
+                            servers: vec![],
+                            tls: None,
+                            auth: nats::Auth::Anonymous,
+                        }),
+                        Transmitter::Nats(nats::Config {
+                            port: 3004,
+                            host: String::from("127.0.0.1"),
+                            servers: vec![],
+                            tls: None,
+                            auth: nats::Auth::Anonymous,
+                        }),
+                    ],
+                    ..config()
+                },
+                expected_valid: false,
+            },
         ];
 
         for test_case in test_cases {
@@ -7,12 +7,21 @@ use chrono::prelude::*;
 use mockall::{automock, mock, predicate::*};
 use uuid::Uuid;
 
-use crate::model::{Message, MetricEvent, Schedule, ScheduleError, Transmission};
+use crate::model::{
+    Message, MetricEvent, Occurrence, Principal, Schedule, ScheduleError, Transmission,
+};
 
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait Scheduler: Send + Sync {
-    async fn schedule(&self, schedule: Schedule, message: Message) -> Result<Uuid, ScheduleError>;
+    // schedule stores the transmission on behalf of the principal, if the caller was
+    // authenticated.
+    async fn schedule(
+        &self,
+        schedule: Schedule,
+        message: Message,
+        principal: Option<Principal>,
+    ) -> Result<Uuid, ScheduleError>;
 }
 
 #[cfg_attr(test, automock)]
@@ -29,12 +38,26 @@ pub trait Repository: Send + Sync {
     ) -> Result<Vec<Transmission>, Box<dyn Error>>;
     async fn save(&self, schedule: &Transmission) -> Result<(), Box<dyn Error + Send + Sync>>;
     async fn reschedule(&self, transmission_id: &Uuid) -> Result<(), Box<dyn Error + Send + Sync>>;
+    // renew_lease extends the lease on a polled transmission before it is transmitted, and returns
+    // whether the lease was still held. A transmission of which the lease was reclaimed by another
+    // poller must not be transmitted, since the other poller transmits it.
+    async fn renew_lease(
+        &self,
+        transmission_id: &Uuid,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>>;
+    // next_due returns the earliest time at which a poll may return a transmission, i.e. the next
+    // occurrence of a transmission that is not leased, or the expiry of a lease, if any.
+    async fn next_due(&self) -> Result<Option<DateTime<Utc>>, Box<dyn Error + Send + Sync>>;
 }
 
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait Transmitter: Send + Sync {
-    async fn transmit(&self, message: Message) -> Result<(), Box<dyn Error + Send + Sync>>;
+    async fn transmit(
+        &self,
+        message: Message,
+        occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 }
 
 #[cfg_attr(test, automock)]
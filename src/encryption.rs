@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+const KEY_LEN: usize = 32;
+
+// Envelope is an encrypted message. The message is encrypted with a data key of its own, which is
+// in turn encrypted with the key encryption key of the keyring that is identified by key_id.
+// Both the ciphertext and the data key are base64 encoded and prefixed with their nonce.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    pub key_id: String,
+    pub data_key: String,
+    pub ciphertext: String,
+}
+
+// Keyring holds the key encryption keys by their id. New envelopes are sealed with the current
+// key, while the others are kept to open envelopes that were not yet re-encrypted.
+#[derive(Clone)]
+pub struct Keyring {
+    current_key_id: String,
+    keys: BTreeMap<String, [u8; KEY_LEN]>,
+}
+
+// The keys are secret, so only their ids are printed.
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("current_key_id", &self.current_key_id)
+            .field("key_ids", &self.keys.keys().collect::<Vec<&String>>())
+            .finish()
+    }
+}
+
+impl Keyring {
+    // parse reads keys formatted as `<key id>:<base64 encoded 256-bit key>`, separated by newlines
+    // or commas. The first key is the current key.
+    pub fn parse(keys: &str) -> Result<Keyring, Box<dyn Error>> {
+        let mut current_key_id = None;
+        let mut parsed_keys = BTreeMap::new();
+
+        for entry in keys
+            .split(['\n', ','])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (key_id, key) = entry
+                .split_once(':')
+                .ok_or("encryption key must be formatted as '<key id>:<base64 key>'")?;
+            let key_id = key_id.trim();
+            if key_id.is_empty() {
+                return Err("encryption key id cannot be empty".into());
+            }
+
+            let key: [u8; KEY_LEN] = STANDARD
+                .decode(key.trim())
+                .map_err(|err| format!("encryption key '{key_id}' is not valid base64: {err}"))?
+                .try_into()
+                .map_err(|_| format!("encryption key '{key_id}' must be {KEY_LEN} bytes"))?;
+
+            if parsed_keys.insert(key_id.to_string(), key).is_some() {
+                return Err(format!("encryption key '{key_id}' is listed more than once").into());
+            }
+            current_key_id.get_or_insert(key_id.to_string());
+        }
+
+        match current_key_id {
+            Some(current_key_id) => Ok(Keyring {
+                current_key_id,
+                keys: parsed_keys,
+            }),
+            None => Err("at least one encryption key is required".into()),
+        }
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        &self.current_key_id
+    }
+
+    // seal encrypts the plaintext with a new data key, wrapped by the current key. The associated
+    // data is authenticated, but not stored, such that the envelope can only be opened with the
+    // same associated data, e.g. the id of the row it is stored in.
+    pub fn seal(
+        &self,
+        plaintext: &[u8],
+        associated_data: &[u8],
+    ) -> Result<Envelope, Box<dyn Error + Send + Sync>> {
+        let random = SystemRandom::new();
+        let mut data_key = [0u8; KEY_LEN];
+        random
+            .fill(&mut data_key)
+            .map_err(|_| "failed to generate data key")?;
+
+        let ciphertext = seal_with(&data_key, plaintext, associated_data)?;
+
+        Ok(Envelope {
+            key_id: self.current_key_id.clone(),
+            data_key: self.wrap(&data_key)?,
+            ciphertext,
+        })
+    }
+
+    // open decrypts the envelope, provided the keyring holds the key it was sealed with.
+    pub fn open(
+        &self,
+        envelope: &Envelope,
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let data_key = self.unwrap(envelope)?;
+
+        open_with(&data_key, &envelope.ciphertext, associated_data)
+    }
+
+    // rewrap wraps the data key of the envelope with the current key, leaving the ciphertext as
+    // it is. It suffices to rotate keys, since the key that was replaced no longer protects the
+    // message.
+    pub fn rewrap(&self, envelope: &Envelope) -> Result<Envelope, Box<dyn Error + Send + Sync>> {
+        let data_key = self.unwrap(envelope)?;
+
+        Ok(Envelope {
+            key_id: self.current_key_id.clone(),
+            data_key: self.wrap(&data_key)?,
+            ciphertext: envelope.ciphertext.clone(),
+        })
+    }
+
+    fn wrap(&self, data_key: &[u8; KEY_LEN]) -> Result<String, Box<dyn Error + Send + Sync>> {
+        // The current key is always present, since it is the first key that was parsed.
+        let key = &self.keys[&self.current_key_id];
+
+        seal_with(key, data_key, self.current_key_id.as_bytes())
+    }
+
+    fn unwrap(&self, envelope: &Envelope) -> Result<[u8; KEY_LEN], Box<dyn Error + Send + Sync>> {
+        let key = self
+            .keys
+            .get(&envelope.key_id)
+            .ok_or_else(|| format!("encryption key '{}' is not configured", envelope.key_id))?;
+
+        let data_key = open_with(key, &envelope.data_key, envelope.key_id.as_bytes())?;
+
+        data_key
+            .try_into()
+            .map_err(|_| "data key has an invalid length".into())
+    }
+}
+
+// seal_with encrypts the plaintext with the key and a random nonce, and returns the nonce and
+// ciphertext, base64 encoded.
+fn seal_with(
+    key: &[u8; KEY_LEN],
+    plaintext: &[u8],
+    associated_data: &[u8],
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| "failed to generate nonce")?;
+
+    let mut in_out = plaintext.to_vec();
+    less_safe_key(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(associated_data),
+            &mut in_out,
+        )
+        .map_err(|_| "failed to encrypt")?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.append(&mut in_out);
+
+    Ok(STANDARD.encode(sealed))
+}
+
+// open_with decrypts the output of seal_with.
+fn open_with(
+    key: &[u8; KEY_LEN],
+    sealed: &str,
+    associated_data: &[u8],
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let sealed = STANDARD.decode(sealed)?;
+    if sealed.len() < NONCE_LEN {
+        return Err("ciphertext is too short to contain a nonce".into());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = less_safe_key(key)?
+        .open_in_place(
+            Nonce::try_assume_unique_for_key(nonce).map_err(|_| "invalid nonce")?,
+            Aad::from(associated_data),
+            &mut in_out,
+        )
+        .map_err(|_| "failed to decrypt; the ciphertext or its associated data was altered")?;
+
+    Ok(plaintext.to_vec())
+}
+
+fn less_safe_key(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, Box<dyn Error + Send + Sync>> {
+    let unbound_key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| "invalid key")?;
+
+    Ok(LessSafeKey::new(unbound_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        STANDARD.encode([byte; KEY_LEN])
+    }
+
+    #[test]
+    fn test_parse() {
+        struct TestCase {
+            name: String,
+            keys: String,
+            expected_current_key_id: Option<String>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("single key"),
+                keys: format!("2026-10:{}", key(1)),
+                expected_current_key_id: Some(String::from("2026-10")),
+            },
+            TestCase {
+                name: String::from("first of several keys"),
+                keys: format!("2026-10:{}\n2025-01:{}\n", key(1), key(2)),
+                expected_current_key_id: Some(String::from("2026-10")),
+            },
+            TestCase {
+                name: String::from("comma separated"),
+                keys: format!("2026-10:{}, 2025-01:{}", key(1), key(2)),
+                expected_current_key_id: Some(String::from("2026-10")),
+            },
+            TestCase {
+                name: String::from("empty"),
+                keys: String::from("\n"),
+                expected_current_key_id: None,
+            },
+            TestCase {
+                name: String::from("missing key id"),
+                keys: key(1),
+                expected_current_key_id: None,
+            },
+            TestCase {
+                name: String::from("short key"),
+                keys: format!("2026-10:{}", STANDARD.encode([1; 16])),
+                expected_current_key_id: None,
+            },
+            TestCase {
+                name: String::from("duplicate key id"),
+                keys: format!("2026-10:{},2026-10:{}", key(1), key(2)),
+                expected_current_key_id: None,
+            },
+        ];
+
+        for test_case in test_cases {
+            let current_key_id = Keyring::parse(&test_case.keys)
+                .ok()
+                .map(|keyring| keyring.current_key_id().to_string());
+            assert_eq!(
+                current_key_id, test_case.expected_current_key_id,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_seal_open() {
+        let keyring = Keyring::parse(&format!("2026-10:{}", key(1))).expect("keys should parse");
+        let plaintext = br#"{"NatsEvent":{"subject":"ORDER.created"}}"#;
+
+        let envelope = keyring
+            .seal(plaintext, b"row id")
+            .expect("sealing should succeed");
+        assert_eq!(envelope.key_id, "2026-10");
+        assert!(!envelope.ciphertext.contains("ORDER"));
+
+        let opened = keyring
+            .open(&envelope, b"row id")
+            .expect("opening should succeed");
+        assert_eq!(opened, plaintext);
+
+        assert!(
+            keyring.open(&envelope, b"other row id").is_err(),
+            "envelope should not open with other associated data"
+        );
+
+        let altered_envelope = Envelope {
+            key_id: String::from("unknown"),
+            ..envelope
+        };
+        assert!(
+            keyring.open(&altered_envelope, b"row id").is_err(),
+            "envelope should not open with an unknown key"
+        );
+    }
+
+    #[test]
+    fn test_rewrap_after_rotation() {
+        let old_keyring =
+            Keyring::parse(&format!("2025-01:{}", key(1))).expect("keys should parse");
+        let rotated_keyring = Keyring::parse(&format!("2026-10:{}\n2025-01:{}", key(2), key(1)))
+            .expect("keys should parse");
+        let new_keyring =
+            Keyring::parse(&format!("2026-10:{}", key(2))).expect("keys should parse");
+        let plaintext = b"payload";
+
+        let envelope = old_keyring
+            .seal(plaintext, b"row id")
+            .expect("sealing should succeed");
+        assert!(
+            new_keyring.open(&envelope, b"row id").is_err(),
+            "the retired key should be needed to open the envelope"
+        );
+
+        let rewrapped = rotated_keyring
+            .rewrap(&envelope)
+            .expect("rewrapping should succeed");
+        assert_eq!(rewrapped.key_id, "2026-10");
+        assert_eq!(rewrapped.ciphertext, envelope.ciphertext);
+
+        let opened = new_keyring
+            .open(&rewrapped, b"row id")
+            .expect("opening should succeed without the retired key");
+        assert_eq!(opened, plaintext);
+    }
+}
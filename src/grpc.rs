@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::future::Future;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -11,12 +12,15 @@ use chrono::prelude::*;
 use futures_util::FutureExt;
 use log::{error, info};
 use serde::Deserialize;
+use tokio::net::TcpListener;
 use tokio_stream::Stream;
 use tokio_util::sync::CancellationToken;
 use tonic::{transport::Server, Request, Response, Status};
 
+use crate::auth::{self, Authenticator};
 use crate::contract::Scheduler;
 use crate::model::*;
+use crate::tls;
 
 pub mod proto {
     tonic::include_proto!("transmit");
@@ -31,33 +35,82 @@ use self::proto::health_check_response::ServingStatus;
 #[derive(Clone, Debug, Deserialize)]
 pub struct Config {
     pub port: u16,
+    // tls serves over TLS, or mutual TLS if a client ca is configured, instead of plaintext.
+    #[serde(default)]
+    pub tls: Option<tls::Config>,
 }
 
 #[derive(Clone)]
 pub struct GrpcServer {
     config: Config,
     scheduler: Arc<dyn Scheduler + Send + Sync>,
+    // authenticator authenticates the callers of the transmit service. If unset, callers are
+    // anonymous.
+    authenticator: Option<Authenticator>,
 }
 
 impl GrpcServer {
     pub fn new(config: Config, scheduler: Arc<dyn Scheduler + Send + Sync>) -> GrpcServer {
-        GrpcServer { config, scheduler }
+        GrpcServer {
+            config,
+            scheduler,
+            authenticator: None,
+        }
+    }
+
+    pub fn with_authenticator(self, authenticator: Authenticator) -> GrpcServer {
+        GrpcServer {
+            authenticator: Some(authenticator),
+            ..self
+        }
     }
 
     pub async fn serve(&self, cancel_token: CancellationToken) -> Result<(), Box<dyn Error>> {
         let host = "0.0.0.0";
         let address = format!("{}:{}", host, self.config.port).parse()?;
-        let transmit_server = TransmitServer::new(self.clone());
         let health_server = HealthServer::new(self.clone());
 
         info!("Start listening for incoming messages at {}.", address);
 
-        let server = Server::builder()
-            .add_service(health_server)
-            .add_service(transmit_server);
+        // Clients of mutual TLS are identified by their certificates, even if no other
+        // credentials are configured.
+        let mutual_tls =
+            matches!(&self.config.tls, Some(tls_config) if tls_config.client_ca_path.is_some());
+        let authenticator = match (&self.authenticator, mutual_tls) {
+            (Some(authenticator), _) => Some(authenticator.clone()),
+            (None, true) => Some(Authenticator::new(auth::Config::default())?),
+            (None, false) => None,
+        };
+
+        // Health checks remain unauthenticated, such that orchestrators can probe the server.
+        let router = Server::builder().add_service(health_server);
+        let server = match authenticator {
+            Some(authenticator) => router.add_service(TransmitServer::with_interceptor(
+                self.clone(),
+                authenticator,
+            )),
+            None => router.add_service(TransmitServer::new(self.clone())),
+        };
 
         // Create a signal channel for graceful shutdown.
         let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let serving: Pin<Box<dyn Future<Output = Result<(), tonic::transport::Error>> + Send>> =
+            match &self.config.tls {
+                Some(tls_config) => {
+                    let acceptor = tls::Acceptor::new(tls_config.clone())?;
+                    let listener = TcpListener::bind(address).await?;
+                    info!(
+                        "Serving over tls with certificate {}.",
+                        tls_config.cert_path
+                    );
+
+                    Box::pin(server.serve_with_incoming_shutdown(
+                        tls::incoming(listener, acceptor),
+                        shutdown_receiver.map(drop),
+                    ))
+                }
+                None => Box::pin(server.serve_with_shutdown(address, shutdown_receiver.map(drop))),
+            };
 
         // Spawn a task to wait for the cancellation token or shutdown signal.
         let serve_task = tokio::spawn(async move {
@@ -67,7 +120,7 @@ impl GrpcServer {
                     let _ = shutdown_sender.send(());
                     info!("Shutting down gRPC server");
                 }
-                _ = server.serve_with_shutdown(address, shutdown_receiver.map(drop)) => {
+                _ = serving => {
                 }
             }
         });
@@ -88,6 +141,8 @@ impl proto::transmit_server::Transmit for GrpcServer {
     ) -> Result<Response<ScheduleTransmissionResponse>, Status> {
         info!("ScheduleMessage request received");
 
+        // The principal is attached to the request by the authentication layer, if any.
+        let principal = request.extensions().get::<Principal>().cloned();
         let request_data = request.into_inner();
         let schedule_proto = match request_data.schedule {
             None => return Err(Status::invalid_argument("schedule is required")),
@@ -226,16 +281,82 @@ impl proto::transmit_server::Transmit for GrpcServer {
         };
         let message = match message_proto {
             proto::schedule_transmission_request::Message::NatsEvent(event) => {
-                let subject = event.subject;
-                let payload = event.payload;
-                Message::NatsEvent(NatsEvent {
-                    subject: subject.into(),
-                    payload: payload.into(),
-                })
+                Message::NatsEvent(nats_event_from_proto(event))
+            }
+            proto::schedule_transmission_request::Message::MqttPublish(publish) => {
+                Message::MqttPublish(
+                    mqtt_publish_from_proto(publish).map_err(Status::invalid_argument)?,
+                )
+            }
+            proto::schedule_transmission_request::Message::AmqpPublish(publish) => {
+                Message::AmqpPublish(amqp_publish_from_proto(publish))
+            }
+            proto::schedule_transmission_request::Message::RedisPublish(publish) => {
+                Message::RedisPublish(redis_publish_from_proto(publish))
+            }
+            proto::schedule_transmission_request::Message::RedisStreamAdd(stream_add) => {
+                Message::RedisStreamAdd(redis_stream_add_from_proto(stream_add))
+            }
+            proto::schedule_transmission_request::Message::KafkaRecord(record) => {
+                Message::KafkaRecord(kafka_record_from_proto(record))
+            }
+            proto::schedule_transmission_request::Message::GrpcCall(call) => {
+                Message::GrpcCall(grpc_call_from_proto(call).map_err(Status::invalid_argument)?)
+            }
+            proto::schedule_transmission_request::Message::Exec(exec) => {
+                Message::Exec(exec_from_proto(exec).map_err(Status::invalid_argument)?)
+            }
+            proto::schedule_transmission_request::Message::Email(email) => {
+                Message::Email(email_from_proto(email))
+            }
+            proto::schedule_transmission_request::Message::FanOut(fan_out) => {
+                let mut destinations = vec![];
+                for destination in fan_out.destinations {
+                    let message = match destination.message {
+                        None => {
+                            return Err(Status::invalid_argument(
+                                "fan_out.destinations.message is required",
+                            ))
+                        }
+                        Some(proto::destination::Message::NatsEvent(event)) => {
+                            Message::NatsEvent(nats_event_from_proto(event))
+                        }
+                        Some(proto::destination::Message::AmqpPublish(publish)) => {
+                            Message::AmqpPublish(amqp_publish_from_proto(publish))
+                        }
+                        Some(proto::destination::Message::RedisPublish(publish)) => {
+                            Message::RedisPublish(redis_publish_from_proto(publish))
+                        }
+                        Some(proto::destination::Message::RedisStreamAdd(stream_add)) => {
+                            Message::RedisStreamAdd(redis_stream_add_from_proto(stream_add))
+                        }
+                        Some(proto::destination::Message::KafkaRecord(record)) => {
+                            Message::KafkaRecord(kafka_record_from_proto(record))
+                        }
+                        Some(proto::destination::Message::GrpcCall(call)) => Message::GrpcCall(
+                            grpc_call_from_proto(call).map_err(Status::invalid_argument)?,
+                        ),
+                        Some(proto::destination::Message::Exec(exec)) => {
+                            Message::Exec(exec_from_proto(exec).map_err(Status::invalid_argument)?)
+                        }
+                        Some(proto::destination::Message::Email(email)) => {
+                            Message::Email(email_from_proto(email))
+                        }
+                        Some(proto::destination::Message::MqttPublish(publish)) => {
+                            Message::MqttPublish(
+                                mqtt_publish_from_proto(publish)
+                                    .map_err(Status::invalid_argument)?,
+                            )
+                        }
+                    };
+                    destinations.push(message);
+                }
+
+                Message::FanOut(FanOut::new(destinations))
             }
         };
 
-        match self.scheduler.schedule(schedule, message).await {
+        match self.scheduler.schedule(schedule, message, principal).await {
             Ok(id) => {
                 info!("Scheduled message: {id}");
 
@@ -252,6 +373,69 @@ impl proto::transmit_server::Transmit for GrpcServer {
             Err(ScheduleError::NatsInvalidSubject) => Err(Status::invalid_argument(
                 "provided nats subject not allowed",
             )),
+            Err(ScheduleError::NatsSubjectNotAllowed) => Err(Status::permission_denied(
+                "provided nats subject is not permitted for the caller",
+            )),
+            Err(ScheduleError::MqttInvalidTopic) => {
+                Err(Status::invalid_argument("provided mqtt topic not allowed"))
+            }
+            Err(ScheduleError::AmqpInvalidExchange) => Err(Status::invalid_argument(
+                "provided amqp exchange not allowed",
+            )),
+            Err(ScheduleError::AmqpInvalidRoutingKey) => Err(Status::invalid_argument(
+                "provided amqp routing key not allowed",
+            )),
+            Err(ScheduleError::RedisInvalidChannel) => Err(Status::invalid_argument(
+                "provided redis channel not allowed",
+            )),
+            Err(ScheduleError::RedisInvalidStream) => Err(Status::invalid_argument(
+                "redis stream requires a name without unmatched braces and at least one field",
+            )),
+            Err(ScheduleError::KafkaInvalidTopic) => {
+                Err(Status::invalid_argument("provided kafka topic not allowed"))
+            }
+            Err(ScheduleError::KafkaInvalidHeader) => Err(Status::invalid_argument(
+                "provided kafka header key not allowed",
+            )),
+            Err(ScheduleError::GrpcInvalidEndpoint) => Err(Status::invalid_argument(
+                "grpc endpoint must be an http(s) uri",
+            )),
+            Err(ScheduleError::GrpcInvalidMethod) => Err(Status::invalid_argument(
+                "grpc method must be formatted as /package.Service/Method",
+            )),
+            Err(ScheduleError::GrpcInvalidDeadline) => {
+                Err(Status::invalid_argument("grpc deadline must be positive"))
+            }
+            Err(ScheduleError::GrpcInvalidMetadata) => Err(Status::invalid_argument(
+                "provided grpc metadata not allowed",
+            )),
+            Err(ScheduleError::ExecInvalidCommand) => Err(Status::invalid_argument(
+                "exec requires a command, a positive timeout and valid environment variables",
+            )),
+            Err(ScheduleError::ExecCommandNotAllowed) => Err(Status::permission_denied(
+                "provided exec command is not allowlisted",
+            )),
+            Err(ScheduleError::EmailInvalidAddress) => Err(Status::invalid_argument(
+                "email requires a valid sender and at least one valid recipient",
+            )),
+            Err(ScheduleError::EmailMissingBody) => Err(Status::invalid_argument(
+                "email requires a text or html body",
+            )),
+            Err(ScheduleError::FanOutInvalidDestinations) => Err(Status::invalid_argument(
+                "fan out requires at least one destination and cannot be nested",
+            )),
+            Err(err @ ScheduleError::TransportNotConfigured(_)) => {
+                Err(Status::invalid_argument(err.to_string()))
+            }
+            Err(ScheduleError::InvalidPayload(err)) => {
+                Err(Status::invalid_argument(format!("invalid payload: {err}")))
+            }
+            Err(err @ ScheduleError::PayloadTooLarge { .. }) => {
+                Err(Status::invalid_argument(err.to_string()))
+            }
+            Err(ScheduleError::InvalidTemplate(err)) => {
+                Err(Status::invalid_argument(format!("invalid template: {err}")))
+            }
             Err(err) => {
                 error!("Failed to schedule message: {err}");
 
@@ -261,6 +445,138 @@ impl proto::transmit_server::Transmit for GrpcServer {
     }
 }
 
+fn payload_format_from_proto(format: Option<proto::PayloadFormat>) -> PayloadFormat {
+    let format = format.unwrap_or_default();
+
+    PayloadFormat {
+        content_type: Some(format.content_type).filter(|value| !value.is_empty()),
+        schema: Some(format.schema).filter(|value| !value.is_empty()),
+    }
+}
+
+fn nats_event_from_proto(event: proto::NatsEvent) -> NatsEvent {
+    NatsEvent {
+        subject: event.subject.into(),
+        payload: event.payload.into(),
+        templated: event.templated,
+        format: payload_format_from_proto(event.format),
+    }
+}
+
+fn mqtt_publish_from_proto(publish: proto::MqttPublish) -> Result<MqttPublish, String> {
+    let qos = match proto::mqtt_publish::QualityOfService::try_from(publish.qos) {
+        Ok(proto::mqtt_publish::QualityOfService::AtMostOnce) => QualityOfService::AtMostOnce,
+        Ok(proto::mqtt_publish::QualityOfService::AtLeastOnce) => QualityOfService::AtLeastOnce,
+        Ok(proto::mqtt_publish::QualityOfService::ExactlyOnce) => QualityOfService::ExactlyOnce,
+        Err(_) => {
+            return Err(format!(
+                "mqtt_publish.qos has unknown value {}",
+                publish.qos
+            ))
+        }
+    };
+
+    Ok(MqttPublish {
+        format: payload_format_from_proto(publish.format),
+        ..MqttPublish::new(publish.topic, publish.payload.into(), qos, publish.retain)
+    })
+}
+
+fn amqp_publish_from_proto(publish: proto::AmqpPublish) -> AmqpPublish {
+    let properties = publish.properties.unwrap_or_default();
+
+    AmqpPublish {
+        format: payload_format_from_proto(publish.format),
+        ..AmqpPublish::new(
+            publish.exchange,
+            publish.routing_key,
+            AmqpProperties {
+                content_type: Some(properties.content_type).filter(|value| !value.is_empty()),
+                correlation_id: Some(properties.correlation_id).filter(|value| !value.is_empty()),
+                persistent: properties.persistent,
+                headers: properties.headers.into_iter().collect(),
+            },
+            publish.body.into(),
+        )
+    }
+}
+
+fn redis_publish_from_proto(publish: proto::RedisPublish) -> RedisPublish {
+    RedisPublish {
+        format: payload_format_from_proto(publish.format),
+        ..RedisPublish::new(publish.channel, publish.payload.into())
+    }
+}
+
+fn redis_stream_add_from_proto(stream_add: proto::RedisStreamAdd) -> RedisStreamAdd {
+    RedisStreamAdd::new(
+        stream_add.stream,
+        stream_add
+            .fields
+            .into_iter()
+            .map(|(field, value)| (field, value.into()))
+            .collect(),
+        stream_add.max_len,
+    )
+}
+
+fn kafka_record_from_proto(record: proto::KafkaRecord) -> KafkaRecord {
+    KafkaRecord {
+        format: payload_format_from_proto(record.format),
+        ..KafkaRecord::new(
+            record.topic,
+            record.key.map(Into::into),
+            record
+                .headers
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect(),
+            record.value.into(),
+        )
+    }
+}
+
+fn grpc_call_from_proto(call: proto::GrpcCall) -> Result<GrpcCall, String> {
+    let deadline = match call.deadline {
+        None => return Err("grpc_call.deadline is required".to_string()),
+        Some(deadline) => std::time::Duration::try_from(deadline)
+            .map_err(|err| format!("parsing grpc_call.deadline as std::time::Duration: {err}"))?,
+    };
+
+    Ok(GrpcCall::new(
+        call.endpoint,
+        call.method,
+        call.body.into(),
+        deadline,
+        call.metadata.into_iter().collect(),
+    ))
+}
+
+fn exec_from_proto(exec: proto::Exec) -> Result<Exec, String> {
+    let timeout = match exec.timeout {
+        None => return Err("exec.timeout is required".to_string()),
+        Some(timeout) => std::time::Duration::try_from(timeout)
+            .map_err(|err| format!("parsing exec.timeout as std::time::Duration: {err}"))?,
+    };
+
+    Ok(Exec::new(
+        exec.command,
+        exec.arguments,
+        exec.environment.into_iter().collect(),
+        timeout,
+    ))
+}
+
+fn email_from_proto(email: proto::Email) -> Email {
+    Email::new(
+        email.from,
+        email.to,
+        email.subject,
+        email.text_body,
+        email.html_body,
+    )
+}
+
 #[tonic::async_trait]
 impl proto::health_server::Health for GrpcServer {
     type WatchStream =
@@ -331,7 +647,9 @@ mod tests {
         let message_proto =
             proto::schedule_transmission_request::Message::NatsEvent(proto::NatsEvent {
                 subject: event_subject,
-                payload: event_payload.into(),
+                payload: event_payload.clone().into(),
+                templated: false,
+                format: None,
             });
 
         let test_cases = vec![
@@ -404,6 +722,227 @@ mod tests {
                 )),
                 expected_message: expected_message.clone(),
             },
+            TestCase {
+                name: "delayed_fan_out".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::FanOut(
+                    proto::FanOut {
+                        destinations: vec![
+                            proto::Destination {
+                                message: Some(proto::destination::Message::NatsEvent(
+                                    proto::NatsEvent {
+                                        subject: "first_subject".to_string(),
+                                        payload: event_payload.clone().into(),
+                                        templated: false,
+                                        format: None,
+                                    },
+                                )),
+                            },
+                            proto::Destination {
+                                message: Some(proto::destination::Message::NatsEvent(
+                                    proto::NatsEvent {
+                                        subject: "second_subject".to_string(),
+                                        payload: event_payload.clone().into(),
+                                        templated: false,
+                                        format: None,
+                                    },
+                                )),
+                            },
+                        ],
+                    },
+                ),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::FanOut(FanOut::new(vec![
+                    Message::NatsEvent(NatsEvent::new(
+                        "first_subject".to_string(),
+                        event_payload.clone(),
+                    )),
+                    Message::NatsEvent(NatsEvent::new(
+                        "second_subject".to_string(),
+                        event_payload.clone(),
+                    )),
+                ])),
+            },
+            TestCase {
+                name: "delayed_mqtt_publish".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::MqttPublish(
+                    proto::MqttPublish {
+                        topic: "some/topic".to_string(),
+                        payload: event_payload.clone().into(),
+                        qos: proto::mqtt_publish::QualityOfService::ExactlyOnce.into(),
+                        retain: true,
+                        format: None,
+                    },
+                ),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::MqttPublish(MqttPublish::new(
+                    "some/topic".to_string(),
+                    event_payload.clone(),
+                    QualityOfService::ExactlyOnce,
+                    true,
+                )),
+            },
+            TestCase {
+                name: "delayed_amqp_publish".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::AmqpPublish(
+                    proto::AmqpPublish {
+                        exchange: "some_exchange".to_string(),
+                        routing_key: "some.routing.key".to_string(),
+                        properties: Some(proto::AmqpProperties {
+                            content_type: "application/json".to_string(),
+                            correlation_id: String::new(),
+                            persistent: true,
+                            headers: std::collections::HashMap::from([(
+                                "origin".to_string(),
+                                "transmit".to_string(),
+                            )]),
+                        }),
+                        body: event_payload.clone().into(),
+                        format: None,
+                    },
+                ),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::AmqpPublish(AmqpPublish::new(
+                    "some_exchange".to_string(),
+                    "some.routing.key".to_string(),
+                    AmqpProperties {
+                        content_type: Some("application/json".to_string()),
+                        correlation_id: None,
+                        persistent: true,
+                        headers: std::collections::BTreeMap::from([(
+                            "origin".to_string(),
+                            "transmit".to_string(),
+                        )]),
+                    },
+                    event_payload.clone(),
+                )),
+            },
+            TestCase {
+                name: "delayed_redis_stream_add".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::RedisStreamAdd(
+                    proto::RedisStreamAdd {
+                        stream: "some_stream".to_string(),
+                        fields: std::collections::HashMap::from([(
+                            "payload".to_string(),
+                            event_payload.clone().into(),
+                        )]),
+                        max_len: Some(1000),
+                    },
+                ),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::RedisStreamAdd(RedisStreamAdd::new(
+                    "some_stream".to_string(),
+                    std::collections::BTreeMap::from([(
+                        "payload".to_string(),
+                        event_payload.clone(),
+                    )]),
+                    Some(1000),
+                )),
+            },
+            TestCase {
+                name: "delayed_kafka_record".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::KafkaRecord(
+                    proto::KafkaRecord {
+                        topic: "some_topic".to_string(),
+                        key: Some("some_key".into()),
+                        headers: std::collections::HashMap::new(),
+                        value: event_payload.clone().into(),
+                        format: Some(proto::PayloadFormat {
+                            content_type: "application/json".to_string(),
+                            schema: "order.created".to_string(),
+                        }),
+                    },
+                ),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::KafkaRecord(KafkaRecord {
+                    format: PayloadFormat {
+                        content_type: Some("application/json".to_string()),
+                        schema: Some("order.created".to_string()),
+                    },
+                    ..KafkaRecord::new(
+                        "some_topic".to_string(),
+                        Some("some_key".into()),
+                        std::collections::BTreeMap::new(),
+                        event_payload.clone(),
+                    )
+                }),
+            },
+            TestCase {
+                name: "delayed_grpc_call".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::GrpcCall(
+                    proto::GrpcCall {
+                        endpoint: "http://orders:8080".to_string(),
+                        method: "/orders.Orders/Create".to_string(),
+                        body: event_payload.clone().into(),
+                        deadline: Some(
+                            std::time::Duration::from_secs(1)
+                                .try_into()
+                                .expect("deadline is not too large to be prost duration"),
+                        ),
+                        metadata: std::collections::HashMap::new(),
+                    },
+                ),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::GrpcCall(GrpcCall::new(
+                    "http://orders:8080".to_string(),
+                    "/orders.Orders/Create".to_string(),
+                    event_payload.clone(),
+                    std::time::Duration::from_secs(1),
+                    std::collections::BTreeMap::new(),
+                )),
+            },
+            TestCase {
+                name: "delayed_email".to_string(),
+                schedule_proto: proto::schedule_transmission_request::Schedule::Delayed(
+                    proto::Delayed {
+                        transmit_at: Some(std::time::SystemTime::from(now).into()),
+                    },
+                ),
+                message_proto: proto::schedule_transmission_request::Message::Email(proto::Email {
+                    from: "transmit@example.com".to_string(),
+                    to: vec!["ops@example.com".to_string()],
+                    subject: "Reminder".to_string(),
+                    text_body: Some("Rotate the certificates.".to_string()),
+                    html_body: None,
+                }),
+                expected_schedule: Schedule::Delayed(Delayed::new(now)),
+                expected_message: Message::Email(Email::new(
+                    "transmit@example.com".to_string(),
+                    vec!["ops@example.com".to_string()],
+                    "Reminder".to_string(),
+                    Some("Rotate the certificates.".to_string()),
+                    None,
+                )),
+            },
         ];
 
         for test_case in test_cases {
@@ -413,11 +952,15 @@ mod tests {
                 .with(
                     eq(test_case.expected_schedule),
                     eq(test_case.expected_message),
+                    eq(None),
                 )
-                .return_once(move |_, _| Ok(id_clone))
+                .return_once(move |_, _, _| Ok(id_clone))
                 .once();
 
-            let config = Config { port: 8081 };
+            let config = Config {
+                port: 8081,
+                tls: None,
+            };
             let grpc_server = GrpcServer::new(config, Arc::new(scheduler));
 
             let request = ScheduleTransmissionRequest {
@@ -438,4 +981,215 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn test_schedule_invalid_payload() {
+        let mut scheduler = MockScheduler::new();
+        scheduler.expect_schedule().return_once(|_, _, _| {
+            Err(ScheduleError::InvalidPayload(
+                crate::schema::SchemaError::Violation {
+                    instance_path: "/count".to_string(),
+                    schema_path: "/properties/count/type".to_string(),
+                    reason: "\"one\" is not of type \"integer\"".to_string(),
+                },
+            ))
+        });
+
+        let grpc_server = GrpcServer::new(
+            Config {
+                port: 8081,
+                tls: None,
+            },
+            Arc::new(scheduler),
+        );
+
+        let request = ScheduleTransmissionRequest {
+            schedule: Some(proto::schedule_transmission_request::Schedule::Delayed(
+                proto::Delayed {
+                    transmit_at: Some(std::time::SystemTime::from(Utc::now()).into()),
+                },
+            )),
+            message: Some(proto::schedule_transmission_request::Message::NatsEvent(
+                proto::NatsEvent {
+                    subject: "some_subject".to_string(),
+                    payload: r#"{"count": "one"}"#.into(),
+                    templated: false,
+                    format: Some(proto::PayloadFormat {
+                        content_type: String::new(),
+                        schema: "occurrence".to_string(),
+                    }),
+                },
+            )),
+        };
+
+        let status = grpc_server
+            .schedule_transmission(tonic::Request::new(request))
+            .await
+            .expect_err("invalid payload should be rejected");
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(
+            status.message().contains("'/count'"),
+            "status should contain the error path: {}",
+            status.message()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schedule_authenticated_principal() {
+        let principal = Principal {
+            name: "orders".to_string(),
+        };
+        let expected_id = uuid::Uuid::new_v4();
+
+        let mut scheduler = MockScheduler::new();
+        scheduler
+            .expect_schedule()
+            .with(always(), always(), eq(Some(principal.clone())))
+            .return_once(move |_, _, _| Ok(expected_id))
+            .once();
+
+        let grpc_server = GrpcServer::new(
+            Config {
+                port: 8081,
+                tls: None,
+            },
+            Arc::new(scheduler),
+        );
+
+        let mut request = tonic::Request::new(ScheduleTransmissionRequest {
+            schedule: Some(proto::schedule_transmission_request::Schedule::Delayed(
+                proto::Delayed {
+                    transmit_at: Some(std::time::SystemTime::from(Utc::now()).into()),
+                },
+            )),
+            message: Some(proto::schedule_transmission_request::Message::NatsEvent(
+                proto::NatsEvent {
+                    subject: "ORDERS.created".to_string(),
+                    payload: "some_payload".into(),
+                    templated: false,
+                    format: None,
+                },
+            )),
+        });
+        // The authenticator attaches the principal before the request is handled.
+        request.extensions_mut().insert(principal);
+
+        let response = grpc_server
+            .schedule_transmission(request)
+            .await
+            .expect("transmission should be scheduled");
+
+        assert_eq!(
+            response.into_inner().transmission_id,
+            expected_id.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_schedule_mutual_tls() {
+        use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+
+        let port = 50161;
+        let fixture = |name: &str| {
+            std::fs::read_to_string(format!("./tests/tls/{name}")).expect("fixture should exist")
+        };
+        let expected_id = uuid::Uuid::new_v4();
+
+        let mut scheduler = MockScheduler::new();
+        scheduler
+            .expect_schedule()
+            .with(
+                always(),
+                always(),
+                eq(Some(Principal {
+                    name: "orders".to_string(),
+                })),
+            )
+            .return_once(move |_, _, _| Ok(expected_id))
+            .once();
+
+        let config = Config {
+            port,
+            tls: Some(tls::Config {
+                cert_path: "./tests/tls/server.pem".to_string(),
+                key_path: "./tests/tls/server.key".to_string(),
+                client_ca_path: Some("./tests/tls/ca.pem".to_string()),
+            }),
+        };
+        let grpc_server = GrpcServer::new(config, Arc::new(scheduler));
+        let token = CancellationToken::new();
+        let server_token = token.clone();
+        tokio::spawn(async move {
+            grpc_server
+                .serve(server_token)
+                .await
+                .expect("server must serve")
+        });
+
+        // Allow the server to start listening.
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let client_tls = |identity: Option<Identity>| {
+            let client_tls = ClientTlsConfig::new()
+                .domain_name("localhost")
+                .ca_certificate(Certificate::from_pem(fixture("ca.pem")));
+            match identity {
+                Some(identity) => client_tls.identity(identity),
+                None => client_tls,
+            }
+        };
+        let request = ScheduleTransmissionRequest {
+            schedule: Some(proto::schedule_transmission_request::Schedule::Delayed(
+                proto::Delayed {
+                    transmit_at: Some(std::time::SystemTime::from(Utc::now()).into()),
+                },
+            )),
+            message: Some(proto::schedule_transmission_request::Message::NatsEvent(
+                proto::NatsEvent {
+                    subject: "ORDERS.created".to_string(),
+                    payload: "some_payload".into(),
+                    templated: false,
+                    format: None,
+                },
+            )),
+        };
+
+        // Clients without a certificate are rejected, either when connecting or, with TLS 1.3, once
+        // the server verified the handshake.
+        let anonymous_channel = Channel::from_shared(format!("https://localhost:{port}"))
+            .expect("endpoint should be valid")
+            .tls_config(client_tls(None))
+            .expect("tls config should be valid")
+            .connect()
+            .await;
+        if let Ok(channel) = anonymous_channel {
+            proto::transmit_client::TransmitClient::new(channel)
+                .schedule_transmission(request.clone())
+                .await
+                .expect_err("client without certificate should be rejected");
+        }
+
+        let channel = Channel::from_shared(format!("https://localhost:{port}"))
+            .expect("endpoint should be valid")
+            .tls_config(client_tls(Some(Identity::from_pem(
+                fixture("client.pem"),
+                fixture("client.key"),
+            ))))
+            .expect("tls config should be valid")
+            .connect()
+            .await
+            .expect("client with certificate should connect");
+        let response = proto::transmit_client::TransmitClient::new(channel)
+            .schedule_transmission(request)
+            .await
+            .expect("transmission should be scheduled by the certificate's subject");
+
+        assert_eq!(
+            response.into_inner().transmission_id,
+            expected_id.to_string()
+        );
+
+        token.cancel();
+    }
 }
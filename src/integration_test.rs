@@ -15,12 +15,17 @@ mod tests {
     use crate::grpc::proto::transmit_client::TransmitClient;
     use crate::grpc::proto::ScheduleTransmissionRequest;
     use crate::metrics;
+    use crate::model::Message;
     use crate::nats;
     use crate::postgres;
+    use crate::repository_in_memory;
     use crate::repository_postgres;
     use crate::scheduler;
     use crate::scheduler::TransmissionScheduler;
+    use crate::sink;
     use crate::transmitter_nats;
+    use crate::transmitter_router;
+    use crate::transmitter_sink;
 
     // To monitor the transmitted messages, run nats via docker with:
     // `docker run -p 4222:4222 -ti nats:latest`
@@ -302,6 +307,89 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    // Runs without external services, since transmissions are written to a sink file and
+    // transmissions are kept in memory.
+    async fn schedule_interval_transmission_to_sink() {
+        let subject = "INTEGRATION.sink_transmission";
+        let grpc_port = 50056;
+        let cancel = CancellationToken::new();
+        let timestamp_now = Utc::now();
+        let sink_path =
+            std::env::temp_dir().join(format!("transmit-{}.jsonl", uuid::Uuid::new_v4()));
+
+        let first_transmission_timestamp = timestamp_now + Duration::seconds(30);
+        let interval_duration = time::Duration::from_secs(10);
+        let repetitions = 2;
+        let process_timestamps = vec![
+            timestamp_now + Duration::seconds(10),
+            timestamp_now + Duration::seconds(31),
+            timestamp_now + Duration::seconds(42),
+            timestamp_now + Duration::seconds(61),
+        ];
+
+        let mut now = MockNow::new();
+        let mut sequence_now = Sequence::new();
+
+        // Expect call for validation.
+        now.expect_now()
+            .once()
+            .in_sequence(&mut sequence_now)
+            .returning(move || timestamp_now);
+
+        for process_timestamp in process_timestamps.clone() {
+            now.expect_now()
+                .once()
+                .in_sequence(&mut sequence_now)
+                .returning(move || process_timestamp);
+        }
+
+        let scheduler = new_sink_scheduler(Arc::new(now), &sink_path).await;
+        start_server(scheduler.clone(), grpc_port, cancel).await;
+        let mut grpc_client = new_grpc_client(grpc_port).await;
+
+        // Construct the grpc request, containing a schedule and message.
+        let schedule_transmission_request = new_interval_transmission_request(
+            subject,
+            first_transmission_timestamp,
+            interval_duration,
+            repetitions,
+        );
+        let grpc_request = tonic::Request::new(schedule_transmission_request);
+
+        // Do the request.
+        let response = grpc_client
+            .schedule_transmission(grpc_request)
+            .await
+            .expect("grpc server should handle request");
+        let transmission_id = uuid::Uuid::parse_str(&response.into_inner().transmission_id)
+            .expect("response should contain uuid");
+
+        for _ in process_timestamps {
+            scheduler.process_batch().await.expect("process should run");
+        }
+
+        let contents = tokio::fs::read_to_string(&sink_path)
+            .await
+            .expect("sink file should be readable");
+        tokio::fs::remove_file(&sink_path)
+            .await
+            .expect("sink file should be removed");
+
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("line should be json"))
+            .collect();
+        assert_eq!(lines.len(), repetitions as usize);
+        for (transmission_count, line) in lines.iter().enumerate() {
+            assert_eq!(line["transmission_id"], transmission_id.to_string());
+            assert_eq!(line["transmission_count"], transmission_count);
+            let message: Message = serde_json::from_value(line["message"].clone())
+                .expect("message should deserialize");
+            assert_eq!(message.destination(), format!("nats:{subject}"));
+        }
+    }
+
     fn many_transmission_requests(
         now: DateTime<Utc>,
         subject: &str,
@@ -393,6 +481,8 @@ mod tests {
         let nats_event = grpc::proto::NatsEvent {
             subject: subject.to_string(),
             payload: "Integration test payload.".into(),
+            templated: false,
+            format: None,
         };
         let message = grpc::proto::schedule_transmission_request::Message::NatsEvent(nats_event);
         let schedule_transmission_request = grpc::proto::ScheduleTransmissionRequest {
@@ -422,6 +512,8 @@ mod tests {
         let nats_event = grpc::proto::NatsEvent {
             subject: subject.to_string(),
             payload: "Integration test payload.".into(),
+            templated: false,
+            format: None,
         };
         let message = grpc::proto::schedule_transmission_request::Message::NatsEvent(nats_event);
         let schedule_transmission_request = grpc::proto::ScheduleTransmissionRequest {
@@ -443,6 +535,8 @@ mod tests {
         let nats_event = grpc::proto::NatsEvent {
             subject: subject.to_string(),
             payload: "Integration test payload.".into(),
+            templated: false,
+            format: None,
         };
         let message = grpc::proto::schedule_transmission_request::Message::NatsEvent(nats_event);
         let schedule_transmission_request = grpc::proto::ScheduleTransmissionRequest {
@@ -514,7 +608,13 @@ mod tests {
             port: 5432,
             user: "postgres".into(),
             password: "postgres".into(),
-            ssl: false,
+            ssl_mode: postgres::SslMode::Disable,
+            ssl_root_cert: None,
+            url: None,
+            pool: postgres::PoolConfig::default(),
+            lease_duration: None,
+            message_keyring: None,
+            message_compression: None,
         };
         let connection = postgres::connect_to_test_database(postgres_config)
             .await
@@ -536,6 +636,9 @@ mod tests {
         let nats_connection = nats::connect_to_nats(nats::Config {
             port: 4222,
             host: "0.0.0.0".to_string(),
+            servers: vec![],
+            tls: None,
+            auth: nats::Auth::Anonymous,
         })
         .await
         .expect("could not connect to nats; is the server running on port 4222?");
@@ -575,12 +678,40 @@ mod tests {
         Arc::new(scheduler)
     }
 
+    // new_sink_scheduler constructs a scheduler that writes all transmissions to the sink file at
+    // the given path and keeps transmissions in memory.
+    async fn new_sink_scheduler(
+        now: Arc<dyn Now>,
+        sink_path: &std::path::Path,
+    ) -> Arc<TransmissionScheduler> {
+        let file = sink::open_file(sink::Config {
+            path: sink_path.to_string_lossy().to_string(),
+        })
+        .await
+        .expect("could not open sink file");
+        let router = transmitter_router::TransmitterRouter::new()
+            .with_sink(Arc::new(transmitter_sink::SinkWriter::new(file)));
+
+        let scheduler = scheduler::TransmissionScheduler::new(
+            time::Duration::from_micros(10),
+            Arc::new(repository_in_memory::RepositoryInMemory::new()),
+            Arc::new(router),
+            now,
+            Arc::new(metric_client()),
+        );
+
+        Arc::new(scheduler)
+    }
+
     async fn start_server(
         transmission_scheduler: Arc<TransmissionScheduler>,
         grpc_port: u16,
         cancel: CancellationToken,
     ) {
-        let grpc_config = grpc::Config { port: grpc_port };
+        let grpc_config = grpc::Config {
+            port: grpc_port,
+            tls: None,
+        };
         let grpc_server = grpc::GrpcServer::new(grpc_config, transmission_scheduler.clone());
 
         tokio::task::spawn(async move {
@@ -591,7 +722,7 @@ mod tests {
         });
 
         // Allow time for the server to initialise.
-        std::thread::sleep(std::time::Duration::from_millis(10));
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
     }
 
     async fn new_grpc_client(port: u16) -> TransmitClient<tonic::transport::Channel> {
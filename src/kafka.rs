@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use log::info;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, Producer};
+use serde::Deserialize;
+
+// DELIVERY_TIMEOUT bounds the time to have a record acknowledged, including retries. Once
+// passed, the transmission fails and is rescheduled.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+// METADATA_TIMEOUT bounds the time to reach the brokers at startup.
+const METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub brokers: Vec<String>,
+    pub client_id: String,
+}
+
+// create_producer returns an idempotent producer, which waits for all in-sync replicas to
+// acknowledge each record and prevents duplicates due to its internal retries.
+pub async fn create_producer(config: Config) -> Result<FutureProducer, Box<dyn std::error::Error>> {
+    let bootstrap_servers = config.brokers.join(",");
+
+    info!("Connecting to kafka brokers {}", bootstrap_servers);
+
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &bootstrap_servers)
+        .set("client.id", &config.client_id)
+        .set("enable.idempotence", "true")
+        .set("acks", "all")
+        .set(
+            "message.timeout.ms",
+            DELIVERY_TIMEOUT.as_millis().to_string(),
+        )
+        .create()?;
+
+    // Fetching metadata blocks, so is run on a thread that may block. It surfaces unreachable
+    // brokers at startup.
+    let metadata_producer = producer.clone();
+    tokio::task::spawn_blocking(move || {
+        metadata_producer
+            .client()
+            .fetch_metadata(None, METADATA_TIMEOUT)
+            .map(|_| ())
+    })
+    .await??;
+
+    Ok(producer)
+}
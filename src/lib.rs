@@ -1,13 +1,40 @@
+pub mod amqp;
+pub mod auth;
+pub mod compression;
 pub mod config;
 pub mod contract;
+pub mod encryption;
 pub mod grpc;
 pub mod integration_test;
+pub mod kafka;
 pub mod load_config;
 pub mod metrics;
 pub mod model;
+pub mod mqtt;
 pub mod nats;
 pub mod postgres;
+pub mod redis;
+#[cfg(test)]
+pub mod repository_conformance;
 pub mod repository_in_memory;
 pub mod repository_postgres;
+pub mod repository_sqlite;
+pub mod repository_wal;
 pub mod scheduler;
+pub mod schema;
+pub mod sink;
+pub mod smtp;
+pub mod sqlite;
+pub mod template;
+pub mod tls;
+pub mod transmitter_amqp;
+pub mod transmitter_email;
+pub mod transmitter_exec;
+pub mod transmitter_grpc;
+pub mod transmitter_kafka;
+pub mod transmitter_mqtt;
 pub mod transmitter_nats;
+pub mod transmitter_redis;
+pub mod transmitter_router;
+pub mod transmitter_sink;
+pub mod wal;
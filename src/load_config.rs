@@ -1,42 +1,229 @@
 use humantime;
+use std::collections::BTreeMap;
 use std::env;
 use std::error::Error;
 use std::fs::File;
 use std::io::Read;
 use std::str::FromStr;
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
+use crate::amqp;
+use crate::auth;
+use crate::compression;
 use crate::config;
+use crate::encryption::Keyring;
+use crate::kafka;
+use crate::mqtt;
+use crate::nats;
 use crate::postgres;
+use crate::redis;
+use crate::sink;
+use crate::smtp;
+use crate::wal;
 
-const ENV_POSTGRES_PASSWORD: &'static str = "POSTGRES_PASSWORD";
+const ENV_POSTGRES_PASSWORD: &str = "POSTGRES_PASSWORD";
+const ENV_DATABASE_URL: &str = "DATABASE_URL";
+const ENV_DATABASE_URL_FILE: &str = "DATABASE_URL_FILE";
+const ENV_MQTT_PASSWORD: &str = "MQTT_PASSWORD";
+const ENV_AMQP_PASSWORD: &str = "AMQP_PASSWORD";
+const ENV_REDIS_PASSWORD: &str = "REDIS_PASSWORD";
+const ENV_SMTP_PASSWORD: &str = "SMTP_PASSWORD";
+const ENV_MESSAGE_ENCRYPTION_KEYS: &str = "MESSAGE_ENCRYPTION_KEYS";
+const ENV_MESSAGE_ENCRYPTION_KEYS_FILE: &str = "MESSAGE_ENCRYPTION_KEYS_FILE";
+const ENV_API_KEYS: &str = "API_KEYS";
+const ENV_API_KEYS_FILE: &str = "API_KEYS_FILE";
+const ENV_NATS_CREDS: &str = "NATS_CREDS";
+const ENV_NATS_CREDS_FILE: &str = "NATS_CREDS_FILE";
+const ENV_NATS_NKEY: &str = "NATS_NKEY";
+const ENV_NATS_NKEY_FILE: &str = "NATS_NKEY_FILE";
+const ENV_NATS_PASSWORD: &str = "NATS_PASSWORD";
+const ENV_NATS_PASSWORD_FILE: &str = "NATS_PASSWORD_FILE";
 
 #[derive(Debug, Deserialize)]
 struct FileConfig {
     automigrate: bool,
     log_level: String,
     clock_cycle_interval: String,
+    #[serde(default)]
+    exec_allowlist: Vec<String>,
+    #[serde(default)]
+    max_payload_size: Option<usize>,
+    // json_schemas contains the paths of the JSON Schema files by the name they are registered as.
+    #[serde(default)]
+    json_schemas: BTreeMap<String, String>,
+    #[serde(default)]
+    nats_subject_acl: nats::SubjectAcl,
+    #[serde(default)]
+    authentication: Option<Authentication>,
     metrics: config::Metrics,
     repository: Repository,
     reset_state: bool,
-    transmitter: config::Transmitter,
+    // transmitter is superseded by transmitters, and is prepended to them, such that existing
+    // configuration files remain valid.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    transmitter: Option<Transmitter>,
+    #[serde(default)]
+    transmitters: Vec<Transmitter>,
     transport: config::Transport,
 }
 
+// Authentication holds the settings that are not secret. The api keys are read from the env.
+#[derive(Debug, Deserialize)]
+struct Authentication {
+    #[serde(default)]
+    jwt: Option<auth::JwtConfig>,
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize)]
 enum Repository {
     Postgres(Postgres),
+    Sqlite {
+        path: String,
+        #[serde(default)]
+        lease_duration: Option<String>,
+    },
+    Wal(Wal),
     InMemory,
 }
 
+// Wal mirrors wal::Config, with the fsync interval as a human readable duration.
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Config")]
+struct Wal {
+    directory: String,
+    #[serde(default)]
+    fsync: WalFsync,
+    #[serde(default)]
+    snapshot_threshold: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+enum WalFsync {
+    #[default]
+    Always,
+    Interval(String),
+    Never,
+}
+
+// Postgres holds the connection settings, of which name, host and user are only required if
+// DATABASE_URL is not set.
 #[derive(Debug, Deserialize)]
 struct Postgres {
+    #[serde(default)]
     name: String,
+    #[serde(default)]
     host: String,
+    #[serde(default = "default_postgres_port")]
     port: u16,
+    #[serde(default)]
     user: String,
-    ssl: bool,
+    // ssl is superseded by ssl_mode, with true being equivalent to Require and false to Disable.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    ssl: Option<bool>,
+    #[serde(default)]
+    ssl_mode: Option<postgres::SslMode>,
+    #[serde(default)]
+    ssl_root_cert: Option<String>,
+    #[serde(default)]
+    pool: PostgresPool,
+    #[serde(default)]
+    lease_duration: Option<String>,
+    #[serde(default)]
+    message_compression: Option<compression::Config>,
+}
+
+fn default_postgres_port() -> u16 {
+    5432
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PostgresPool {
+    #[serde(default)]
+    max_connections: Option<u32>,
+    #[serde(default)]
+    min_connections: Option<u32>,
+    #[serde(default)]
+    acquire_timeout: Option<String>,
+    #[serde(default)]
+    idle_timeout: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+enum Transmitter {
+    Nats(Nats),
+    Mqtt(Mqtt),
+    Amqp(Amqp),
+    Redis(Redis),
+    Kafka(kafka::Config),
+    Grpc,
+    Exec,
+    Smtp(Smtp),
+    File(sink::Config),
+    Stdout,
+}
+
+// Nats keeps the name of nats::Config, such that existing configuration files remain valid. The
+// other transmitters are named alike.
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Config")]
+struct Nats {
+    host: String,
+    port: u16,
+    #[serde(default)]
+    servers: Vec<String>,
+    #[serde(default)]
+    tls: Option<nats::TlsConfig>,
+    #[serde(default)]
+    auth: NatsAuth,
+}
+
+// NatsAuth selects the method that the nats client authenticates with, of which the secrets are
+// read from the env.
+#[derive(Debug, Default, Deserialize)]
+enum NatsAuth {
+    #[default]
+    Anonymous,
+    Credentials,
+    NKey,
+    UserPassword(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Config")]
+struct Mqtt {
+    host: String,
+    port: u16,
+    client_id: String,
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Config")]
+struct Amqp {
+    host: String,
+    port: u16,
+    vhost: String,
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Config")]
+struct Redis {
+    host: String,
+    port: u16,
+    db: i64,
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Config")]
+struct Smtp {
+    host: String,
+    port: u16,
+    starttls: bool,
+    username: Option<String>,
 }
 
 fn load_config_from_file(file_path: &str) -> Result<FileConfig, Box<dyn Error>> {
@@ -49,13 +236,77 @@ fn load_config_from_file(file_path: &str) -> Result<FileConfig, Box<dyn Error>>
 }
 
 struct EnvConfig {
-    postgres_password: String,
+    // postgres_password is only required if a postgres repository is configured without
+    // database_url.
+    postgres_password: Option<String>,
+    // database_url is a connection string, which takes precedence over the other postgres
+    // connection settings, either directly or in a file.
+    database_url: Option<String>,
+    // mqtt_password is optional, since brokers may permit anonymous clients.
+    mqtt_password: Option<String>,
+    // amqp_password is only required if an amqp transmitter is configured.
+    amqp_password: Option<String>,
+    // redis_password is optional, since redis may not require authentication.
+    redis_password: Option<String>,
+    // smtp_password is optional, since relays may not require authentication.
+    smtp_password: Option<String>,
+    // message_encryption_keys is optional, since messages are only encrypted if keys are given,
+    // either directly or in a file.
+    message_encryption_keys: Option<String>,
+    // api_keys is optional, since callers may authenticate with JWTs instead, either directly or
+    // in a file.
+    api_keys: Option<String>,
+    // The nats secrets are only required for the auth method that is configured.
+    nats_creds: Option<String>,
+    nats_nkey: Option<String>,
+    nats_password: Option<String>,
 }
 
 fn load_secrets_from_env() -> Result<EnvConfig, Box<dyn Error>> {
-    let postgres_password = env::var(ENV_POSTGRES_PASSWORD)?;
+    let postgres_password = env::var(ENV_POSTGRES_PASSWORD).ok();
+    let database_url = env_or_file(ENV_DATABASE_URL, ENV_DATABASE_URL_FILE)?;
+    let mqtt_password = env::var(ENV_MQTT_PASSWORD).ok();
+    let amqp_password = env::var(ENV_AMQP_PASSWORD).ok();
+    let redis_password = env::var(ENV_REDIS_PASSWORD).ok();
+    let smtp_password = env::var(ENV_SMTP_PASSWORD).ok();
+    let message_encryption_keys = env_or_file(
+        ENV_MESSAGE_ENCRYPTION_KEYS,
+        ENV_MESSAGE_ENCRYPTION_KEYS_FILE,
+    )?;
+    let api_keys = env_or_file(ENV_API_KEYS, ENV_API_KEYS_FILE)?;
+    let nats_creds = env_or_file(ENV_NATS_CREDS, ENV_NATS_CREDS_FILE)?;
+    let nats_nkey = env_or_file(ENV_NATS_NKEY, ENV_NATS_NKEY_FILE)?;
+    let nats_password = env_or_file(ENV_NATS_PASSWORD, ENV_NATS_PASSWORD_FILE)?;
 
-    Ok(EnvConfig { postgres_password })
+    Ok(EnvConfig {
+        postgres_password,
+        database_url,
+        mqtt_password,
+        amqp_password,
+        redis_password,
+        smtp_password,
+        message_encryption_keys,
+        api_keys,
+        nats_creds,
+        nats_nkey,
+        nats_password,
+    })
+}
+
+// env_or_file reads a secret from the env variable, or from the file named by the other, of which
+// at most one can be set.
+fn env_or_file(env_key: &str, env_key_file: &str) -> Result<Option<String>, Box<dyn Error>> {
+    match (env::var(env_key).ok(), env::var(env_key_file).ok()) {
+        (Some(_), Some(_)) => {
+            Err(format!("only one of {env_key} and {env_key_file} can be set").into())
+        }
+        (Some(secret), None) => Ok(Some(secret)),
+        (None, Some(file_path)) => Ok(Some(
+            std::fs::read_to_string(&file_path)
+                .map_err(|err| format!("reading {env_key_file} {file_path}: {err}"))?,
+        )),
+        (None, None) => Ok(None),
+    }
 }
 
 pub fn load_config(file_path: &str) -> Result<config::Config, Box<dyn Error>> {
@@ -80,30 +331,246 @@ fn derive_config(config: FileConfig, secrets: EnvConfig) -> Result<config::Confi
 
     let clock_cycle_interval = humantime::parse_duration(&config.clock_cycle_interval)?;
 
+    let json_schemas = config
+        .json_schemas
+        .iter()
+        .map(|(name, file_path)| {
+            let contents = std::fs::read_to_string(file_path)
+                .map_err(|err| format!("reading schema '{name}' from {file_path}: {err}"))?;
+            let schema = serde_json::from_str(&contents)
+                .map_err(|err| format!("parsing schema '{name}' from {file_path}: {err}"))?;
+
+            Ok((name.clone(), schema))
+        })
+        .collect::<Result<BTreeMap<String, serde_json::Value>, String>>()?;
+
+    let message_keyring = match &secrets.message_encryption_keys {
+        Some(keys) => Some(Keyring::parse(keys)?),
+        None => None,
+    };
+
+    let authentication = match (config.authentication, &secrets.api_keys) {
+        (Some(authentication), api_keys) => Some(auth::Config {
+            api_keys: match api_keys {
+                Some(keys) => auth::ApiKeys::parse(keys)?,
+                None => auth::ApiKeys::default(),
+            },
+            jwt: authentication.jwt,
+        }),
+        (None, Some(_)) => {
+            return Err(format!(
+                "authentication must be configured to accept the keys of {ENV_API_KEYS}"
+            )
+            .into())
+        }
+        (None, None) => None,
+    };
+
     Ok(config::Config {
         automigrate: config.automigrate,
         log_level,
         clock_cycle_interval,
+        exec_allowlist: config.exec_allowlist,
+        max_payload_size: config.max_payload_size,
+        json_schemas,
+        nats_subject_acl: config.nats_subject_acl,
+        authentication,
         metrics: config.metrics,
         repository: match config.repository {
-            Repository::Postgres(postgres_config) => {
-                config::Repository::Postgres(postgres::Config {
-                    name: postgres_config.name,
-                    host: postgres_config.host,
-                    port: postgres_config.port,
-                    user: postgres_config.user,
-                    password: secrets.postgres_password,
-                    ssl: postgres_config.ssl,
-                })
-            }
+            Repository::Postgres(postgres_config) => config::Repository::Postgres(
+                derive_postgres_config(postgres_config, &secrets, message_keyring)?,
+            ),
+            Repository::Sqlite {
+                path,
+                lease_duration,
+            } => config::Repository::Sqlite {
+                path,
+                lease_duration: lease_duration
+                    .map(|lease_duration| humantime::parse_duration(&lease_duration))
+                    .transpose()?,
+            },
+            Repository::Wal(wal_config) => config::Repository::Wal(wal::Config {
+                directory: wal_config.directory,
+                fsync: match wal_config.fsync {
+                    WalFsync::Always => wal::FsyncPolicy::Always,
+                    WalFsync::Interval(interval) => {
+                        wal::FsyncPolicy::Interval(humantime::parse_duration(&interval)?)
+                    }
+                    WalFsync::Never => wal::FsyncPolicy::Never,
+                },
+                snapshot_threshold: wal_config
+                    .snapshot_threshold
+                    .unwrap_or(wal::DEFAULT_SNAPSHOT_THRESHOLD),
+            }),
             Repository::InMemory => config::Repository::InMemory,
         },
-        transmitter: config.transmitter,
+        transmitters: config
+            .transmitter
+            .into_iter()
+            .chain(config.transmitters)
+            .map(|transmitter| match transmitter {
+                Transmitter::Nats(nats_config) => {
+                    let auth = derive_nats_auth(nats_config.auth, &secrets)?;
+
+                    Ok(config::Transmitter::Nats(nats::Config {
+                        host: nats_config.host,
+                        port: nats_config.port,
+                        servers: nats_config.servers,
+                        tls: nats_config.tls,
+                        auth,
+                    }))
+                }
+                Transmitter::Mqtt(mqtt_config) => Ok(config::Transmitter::Mqtt(mqtt::Config {
+                    host: mqtt_config.host,
+                    port: mqtt_config.port,
+                    client_id: mqtt_config.client_id,
+                    username: mqtt_config.username,
+                    password: secrets.mqtt_password.clone(),
+                })),
+                Transmitter::Amqp(amqp_config) => match &secrets.amqp_password {
+                    Some(password) => Ok(config::Transmitter::Amqp(amqp::Config {
+                        host: amqp_config.host,
+                        port: amqp_config.port,
+                        vhost: amqp_config.vhost,
+                        username: amqp_config.username,
+                        password: password.clone(),
+                    })),
+                    None => Err(format!(
+                        "{ENV_AMQP_PASSWORD} must be set to configure an amqp transmitter"
+                    )),
+                },
+                Transmitter::Redis(redis_config) => Ok(config::Transmitter::Redis(redis::Config {
+                    host: redis_config.host,
+                    port: redis_config.port,
+                    db: redis_config.db,
+                    username: redis_config.username,
+                    password: secrets.redis_password.clone(),
+                })),
+                Transmitter::Kafka(kafka_config) => Ok(config::Transmitter::Kafka(kafka_config)),
+                Transmitter::Grpc => Ok(config::Transmitter::Grpc),
+                Transmitter::Exec => Ok(config::Transmitter::Exec),
+                Transmitter::Smtp(smtp_config) => Ok(config::Transmitter::Smtp(smtp::Config {
+                    host: smtp_config.host,
+                    port: smtp_config.port,
+                    starttls: smtp_config.starttls,
+                    username: smtp_config.username,
+                    password: secrets.smtp_password.clone(),
+                })),
+                Transmitter::File(sink_config) => Ok(config::Transmitter::File(sink_config)),
+                Transmitter::Stdout => Ok(config::Transmitter::Stdout),
+            })
+            .collect::<Result<Vec<config::Transmitter>, String>>()?,
         transport: config.transport,
         reset_state: config.reset_state,
     })
 }
 
+fn derive_postgres_config(
+    config: Postgres,
+    secrets: &EnvConfig,
+    message_keyring: Option<Keyring>,
+) -> Result<postgres::Config, Box<dyn Error>> {
+    let url = secrets
+        .database_url
+        .as_ref()
+        .map(|url| url.trim().to_string());
+
+    // The connection string takes precedence over the other connection settings, which are
+    // required without it.
+    let (name, host, user, password) = match &url {
+        Some(_) => (
+            config.name,
+            config.host,
+            config.user,
+            secrets.postgres_password.clone().unwrap_or_default(),
+        ),
+        None => {
+            let password = secrets.postgres_password.clone().ok_or(format!(
+                "{ENV_POSTGRES_PASSWORD} or {ENV_DATABASE_URL} must be set to configure a postgres repository"
+            ))?;
+
+            if config.name.is_empty() || config.host.is_empty() || config.user.is_empty() {
+                return Err(format!(
+                    "postgres name, host and user are required unless {ENV_DATABASE_URL} is set"
+                )
+                .into());
+            }
+
+            (config.name, config.host, config.user, password)
+        }
+    };
+
+    let ssl_mode = match (config.ssl_mode, config.ssl) {
+        (Some(_), Some(_)) => return Err("only one of postgres ssl and ssl_mode can be set".into()),
+        (Some(ssl_mode), None) => ssl_mode,
+        (None, Some(true)) => postgres::SslMode::Require,
+        (None, Some(false)) => postgres::SslMode::Disable,
+        (None, None) => postgres::SslMode::default(),
+    };
+
+    let parse_duration = |duration: Option<String>| match duration {
+        Some(duration) => humantime::parse_duration(&duration).map(Some),
+        None => Ok(None),
+    };
+
+    Ok(postgres::Config {
+        name,
+        host,
+        port: config.port,
+        user,
+        password,
+        ssl_mode,
+        ssl_root_cert: config.ssl_root_cert,
+        url,
+        pool: postgres::PoolConfig {
+            max_connections: config.pool.max_connections,
+            min_connections: config.pool.min_connections,
+            acquire_timeout: parse_duration(config.pool.acquire_timeout)?,
+            idle_timeout: parse_duration(config.pool.idle_timeout)?,
+        },
+        lease_duration: parse_duration(config.lease_duration)?,
+        message_keyring,
+        message_compression: config.message_compression,
+    })
+}
+
+// deserialize_some deserializes a field that is set without `Some`, such that an omitted field can
+// be told apart from one that is set.
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+// derive_nats_auth pairs the configured auth method with its secret.
+fn derive_nats_auth(auth: NatsAuth, secrets: &EnvConfig) -> Result<nats::Auth, String> {
+    let missing = |env_key: &str, env_key_file: &str| {
+        format!("{env_key} or {env_key_file} must be set to authenticate with nats")
+    };
+
+    match auth {
+        NatsAuth::Anonymous => Ok(nats::Auth::Anonymous),
+        NatsAuth::Credentials => match &secrets.nats_creds {
+            Some(credentials) => Ok(nats::Auth::Credentials(credentials.clone())),
+            None => Err(missing(ENV_NATS_CREDS, ENV_NATS_CREDS_FILE)),
+        },
+        // Secrets read from files commonly end with a newline, which is not part of the secret.
+        NatsAuth::NKey => match &secrets.nats_nkey {
+            Some(seed) => Ok(nats::Auth::NKey(seed.trim().to_string())),
+            None => Err(missing(ENV_NATS_NKEY, ENV_NATS_NKEY_FILE)),
+        },
+        NatsAuth::UserPassword(user) => match &secrets.nats_password {
+            Some(password) => Ok(nats::Auth::UserPassword {
+                user,
+                password: password.trim_end_matches(['\r', '\n']).to_string(),
+            }),
+            None => Err(missing(ENV_NATS_PASSWORD, ENV_NATS_PASSWORD_FILE)),
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,6 +586,7 @@ mod tests {
         assert!(load_config(config_file).is_err());
 
         env::set_var(ENV_POSTGRES_PASSWORD, postgres_password);
+        env::set_var(ENV_AMQP_PASSWORD, "guest");
         let configuration = load_config(config_file).expect("could not load configuration");
 
         // Merely asserting the log level is enough to assert the structure of the file contents.
@@ -132,8 +600,184 @@ mod tests {
         match configuration.repository {
             config::Repository::Postgres(postgres_config) => {
                 assert_eq!(postgres_config.password, postgres_password);
+                assert_eq!(postgres_config.port, 5432);
             }
             _ => panic!("file configures for a postgres repository"),
         };
     }
+
+    #[test]
+    fn test_load_legacy_transmitter() {
+        // The transmitter key of configuration files that predate the transmitters list.
+        let file_config = r#"(
+            automigrate: true,
+            reset_state: false,
+            log_level: "debug",
+            clock_cycle_interval: "100ms",
+            metrics: Prometheus(Config(port: 9090, endpoint: "/")),
+            repository: InMemory,
+            transmitter: Nats(Config(host: "127.0.0.1", port: 4222)),
+            transport: Grpc(Config(port: 8080)),
+        )"#;
+        let file_config: FileConfig =
+            ron::de::from_str(file_config).expect("legacy config should parse");
+
+        let secrets = EnvConfig {
+            postgres_password: None,
+            database_url: None,
+            mqtt_password: None,
+            amqp_password: None,
+            redis_password: None,
+            smtp_password: None,
+            message_encryption_keys: None,
+            api_keys: None,
+            nats_creds: None,
+            nats_nkey: None,
+            nats_password: None,
+        };
+        let configuration =
+            derive_config(file_config, secrets).expect("legacy config should derive");
+
+        match configuration.transmitters.as_slice() {
+            [config::Transmitter::Nats(nats_config)] => assert_eq!(nats_config.port, 4222),
+            transmitters => panic!("expected a single nats transmitter, got {transmitters:?}"),
+        };
+    }
+
+    #[test]
+    fn test_derive_postgres_ssl_mode() {
+        struct TestCase {
+            name: String,
+            postgres: String,
+            expected_ssl_mode: Result<postgres::SslMode, ()>,
+        }
+
+        let secrets = EnvConfig {
+            postgres_password: Some(String::from("postgres")),
+            database_url: None,
+            mqtt_password: None,
+            amqp_password: None,
+            redis_password: None,
+            smtp_password: None,
+            message_encryption_keys: None,
+            api_keys: None,
+            nats_creds: None,
+            nats_nkey: None,
+            nats_password: None,
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("omitted"),
+                postgres: String::from(
+                    r#"(name: "transmit", host: "localhost", user: "postgres")"#,
+                ),
+                expected_ssl_mode: Ok(postgres::SslMode::default()),
+            },
+            TestCase {
+                name: String::from("legacy ssl enabled"),
+                postgres: String::from(
+                    r#"(name: "transmit", host: "localhost", user: "postgres", ssl: true)"#,
+                ),
+                expected_ssl_mode: Ok(postgres::SslMode::Require),
+            },
+            TestCase {
+                name: String::from("legacy ssl disabled"),
+                postgres: String::from(
+                    r#"(name: "transmit", host: "localhost", user: "postgres", ssl: false)"#,
+                ),
+                expected_ssl_mode: Ok(postgres::SslMode::Disable),
+            },
+            TestCase {
+                name: String::from("ssl mode"),
+                postgres: String::from(
+                    r#"(name: "transmit", host: "localhost", user: "postgres", ssl_mode: Some(VerifyFull))"#,
+                ),
+                expected_ssl_mode: Ok(postgres::SslMode::VerifyFull),
+            },
+            TestCase {
+                name: String::from("both legacy ssl and ssl mode"),
+                postgres: String::from(
+                    r#"(name: "transmit", host: "localhost", user: "postgres", ssl: false, ssl_mode: Some(Require))"#,
+                ),
+                expected_ssl_mode: Err(()),
+            },
+        ];
+
+        for test_case in test_cases {
+            let postgres: Postgres =
+                ron::de::from_str(&test_case.postgres).expect("postgres config should parse");
+            let ssl_mode = derive_postgres_config(postgres, &secrets, None)
+                .map(|config| config.ssl_mode)
+                .map_err(|_| ());
+            assert_eq!(
+                ssl_mode, test_case.expected_ssl_mode,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_derive_nats_auth() {
+        struct TestCase {
+            name: String,
+            auth: NatsAuth,
+            expected_auth: Result<String, ()>,
+        }
+
+        let secrets = EnvConfig {
+            postgres_password: Some(String::from("postgres")),
+            database_url: None,
+            mqtt_password: None,
+            amqp_password: None,
+            redis_password: None,
+            smtp_password: None,
+            message_encryption_keys: None,
+            api_keys: None,
+            nats_creds: None,
+            nats_nkey: Some(String::from(
+                "SUAIBDPBAUTWCWBKIO6XHQNINK5FWJW4OHLXC3HQ2KFE4PEJUA44CNHTC4\n",
+            )),
+            nats_password: Some(String::from("secret\n")),
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("anonymous"),
+                auth: NatsAuth::Anonymous,
+                expected_auth: Ok(String::from("Anonymous")),
+            },
+            TestCase {
+                name: String::from("missing credentials"),
+                auth: NatsAuth::Credentials,
+                expected_auth: Err(()),
+            },
+            TestCase {
+                name: String::from("nkey"),
+                auth: NatsAuth::NKey,
+                expected_auth: Ok(String::from("NKey")),
+            },
+            TestCase {
+                name: String::from("user and password"),
+                auth: NatsAuth::UserPassword(String::from("transmit")),
+                expected_auth: Ok(String::from(r#"UserPassword { user: "transmit", .. }"#)),
+            },
+        ];
+
+        for test_case in test_cases {
+            let auth = derive_nats_auth(test_case.auth, &secrets);
+            assert_eq!(
+                auth.map(|auth| format!("{auth:?}")).map_err(|_| ()),
+                test_case.expected_auth,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+
+        match derive_nats_auth(NatsAuth::UserPassword(String::from("transmit")), &secrets) {
+            Ok(nats::Auth::UserPassword { password, .. }) => assert_eq!(password, "secret"),
+            _ => panic!("password should be derived"),
+        }
+    }
 }
@@ -3,23 +3,44 @@ use std::process;
 use std::sync::Arc;
 
 use chrono::prelude::*;
-use log::{error, info};
+use log::{error, info, warn};
 use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 
+use transmit::amqp;
+use transmit::auth;
 use transmit::config;
 use transmit::contract;
 use transmit::grpc;
+use transmit::kafka;
 use transmit::load_config;
 use transmit::metrics;
+use transmit::mqtt;
 use transmit::nats;
 use transmit::postgres;
+use transmit::redis;
 use transmit::repository_in_memory;
 use transmit::repository_postgres;
+use transmit::repository_sqlite;
+use transmit::repository_wal;
 use transmit::scheduler;
+use transmit::schema;
+use transmit::sink;
+use transmit::smtp;
+use transmit::sqlite;
+use transmit::transmitter_amqp;
+use transmit::transmitter_email;
+use transmit::transmitter_exec;
+use transmit::transmitter_grpc;
+use transmit::transmitter_kafka;
+use transmit::transmitter_mqtt;
 use transmit::transmitter_nats;
+use transmit::transmitter_redis;
+use transmit::transmitter_router;
+use transmit::transmitter_sink;
 
-const DEFAULT_CONFIG_FILE_PATH: &'static str = "config.ron";
+const DEFAULT_CONFIG_FILE_PATH: &str = "config.ron";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -49,27 +70,174 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env::set_var(rust_log, config.log_level.as_str());
     info!("Starting application.");
 
-    // Construct transmitter.
-    let transmitter: Arc<dyn contract::Transmitter> = match config.transmitter {
-        config::Transmitter::Nats(nats_config) => {
-            let nats_client = match nats::connect_to_nats(nats_config).await {
-                Ok(client) => client,
-                Err(err) => {
-                    error!("Failed to initialise nats connection: {}", err);
-                    process::exit(1);
-                }
-            };
+    // Construct transmitters, routed to by message variant.
+    let mut router = transmitter_router::TransmitterRouter::new();
+    for transmitter_config in config.transmitters {
+        router = match transmitter_config {
+            config::Transmitter::Nats(nats_config) => {
+                let nats_client = match nats::connect_to_nats(nats_config).await {
+                    Ok(client) => client,
+                    Err(err) => {
+                        error!("Failed to initialise nats connection: {}", err);
+                        process::exit(1);
+                    }
+                };
+
+                let transmitter = transmitter_nats::NatsPublisher::new(nats_client);
+                info!("Initialised nats transmitter.");
+
+                router.with_nats(Arc::new(transmitter))
+            }
+            config::Transmitter::Mqtt(mqtt_config) => {
+                let (mqtt_client, acknowledgements) = match mqtt::connect_to_mqtt(mqtt_config).await
+                {
+                    Ok(client) => client,
+                    Err(err) => {
+                        error!("Failed to initialise mqtt connection: {}", err);
+                        process::exit(1);
+                    }
+                };
+
+                let transmitter =
+                    transmitter_mqtt::MqttPublisher::new(mqtt_client, acknowledgements);
+                info!("Initialised mqtt transmitter.");
+
+                router.with_mqtt(Arc::new(transmitter))
+            }
+            config::Transmitter::Amqp(amqp_config) => {
+                let amqp_channel = match amqp::connect_to_amqp(amqp_config).await {
+                    Ok(channel) => channel,
+                    Err(err) => {
+                        error!("Failed to initialise amqp connection: {}", err);
+                        process::exit(1);
+                    }
+                };
+
+                let transmitter = transmitter_amqp::AmqpPublisher::new(amqp_channel);
+                info!("Initialised amqp transmitter.");
+
+                router.with_amqp(Arc::new(transmitter))
+            }
+            config::Transmitter::Redis(redis_config) => {
+                let redis_connection = match redis::connect_to_redis(redis_config).await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        error!("Failed to initialise redis connection: {}", err);
+                        process::exit(1);
+                    }
+                };
+
+                let transmitter = transmitter_redis::RedisPublisher::new(redis_connection);
+                info!("Initialised redis transmitter.");
+
+                router.with_redis(Arc::new(transmitter))
+            }
+            config::Transmitter::Kafka(kafka_config) => {
+                let kafka_producer = match kafka::create_producer(kafka_config).await {
+                    Ok(producer) => producer,
+                    Err(err) => {
+                        error!("Failed to initialise kafka producer: {}", err);
+                        process::exit(1);
+                    }
+                };
+
+                let transmitter = transmitter_kafka::KafkaProducer::new(kafka_producer);
+                info!("Initialised kafka transmitter.");
+
+                router.with_kafka(Arc::new(transmitter))
+            }
+            config::Transmitter::Grpc => {
+                let transmitter = transmitter_grpc::GrpcCaller::new();
+                info!("Initialised grpc transmitter.");
+
+                router.with_grpc(Arc::new(transmitter))
+            }
+            config::Transmitter::Exec => {
+                let transmitter = transmitter_exec::ExecRunner::new(config.exec_allowlist.clone());
+                info!("Initialised exec transmitter.");
 
-            let transmitter = transmitter_nats::NatsPublisher::new(nats_client);
-            info!("Initialised nats transmitter.");
+                router.with_exec(Arc::new(transmitter))
+            }
+            config::Transmitter::Smtp(smtp_config) => {
+                let smtp_transport = match smtp::connect_to_smtp(smtp_config).await {
+                    Ok(transport) => transport,
+                    Err(err) => {
+                        error!("Failed to initialise smtp connection: {}", err);
+                        process::exit(1);
+                    }
+                };
 
-            Arc::new(transmitter)
+                let transmitter = transmitter_email::SmtpMailer::new(smtp_transport);
+                info!("Initialised smtp transmitter.");
+
+                router.with_email(Arc::new(transmitter))
+            }
+            config::Transmitter::File(sink_config) => {
+                let file = match sink::open_file(sink_config).await {
+                    Ok(file) => file,
+                    Err(err) => {
+                        error!("Failed to open sink file: {}", err);
+                        process::exit(1);
+                    }
+                };
+
+                let transmitter = transmitter_sink::SinkWriter::new(file);
+                info!("Initialised file sink transmitter.");
+
+                router.with_sink(Arc::new(transmitter))
+            }
+            config::Transmitter::Stdout => {
+                let transmitter = transmitter_sink::SinkWriter::stdout();
+                info!("Initialised stdout sink transmitter.");
+
+                router.with_sink(Arc::new(transmitter))
+            }
+        };
+    }
+    let transports = router.transports();
+    let transmitter: Arc<dyn contract::Transmitter> = Arc::new(router);
+
+    // Construct metrics client and server.
+    let metrics_client: Arc<dyn contract::Metrics> = match config.metrics {
+        config::Metrics::Prometheus(metrics_config) => {
+            let (metrics_client, metrics_server) = metrics::new(metrics_config);
+
+            // Start metrics server.
+            let _handle = tokio::spawn(async move {
+                match metrics_server.start_server().await {
+                    Ok(_) => (),
+                    Err(err) => {
+                        error!("Failed to start prometheus server: {}", err);
+                        process::exit(1);
+                    }
+                }
+            });
+
+            Arc::new(metrics_client)
         }
     };
 
+    // wakeups wakes up the scheduler when a transmission is scheduled that is due before it would
+    // wake up otherwise.
+    let wakeups = Arc::new(Notify::new());
+
+    // lease_duration is renewed by the scheduler while it transmits, such that slow transmissions
+    // are not reclaimed. The repositories without a configurable lease use the default.
+    let lease_duration = match &config.repository {
+        config::Repository::Postgres(postgres_config) => postgres_config.lease_duration,
+        config::Repository::Sqlite { lease_duration, .. } => *lease_duration,
+        config::Repository::Wal(_) | config::Repository::InMemory => None,
+    }
+    .unwrap_or(repository_postgres::DEFAULT_LEASE_DURATION);
+
     // Construct repository.
     let repository: Arc<dyn contract::Repository> = match config.repository {
         config::Repository::Postgres(postgres_config) => {
+            let message_keyring = postgres_config.message_keyring.clone();
+            let message_compression = postgres_config.message_compression.clone();
+            let lease_duration = postgres_config
+                .lease_duration
+                .unwrap_or(repository_postgres::DEFAULT_LEASE_DURATION);
             let postgres_connection = match postgres::connect_to_database(postgres_config).await {
                 Ok(client) => client,
                 Err(err) => {
@@ -78,7 +246,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
-            let repository = repository_postgres::RepositoryPostgres::new(postgres_connection);
+            let repository = repository_postgres::RepositoryPostgres::new(postgres_connection)
+                .with_lease_duration(lease_duration)
+                .with_metrics(metrics_client.clone());
+            let repository = match message_keyring.clone() {
+                Some(keyring) => {
+                    info!(
+                        "Encrypting messages with key '{}'.",
+                        keyring.current_key_id()
+                    );
+                    repository.with_keyring(keyring)
+                }
+                None => repository,
+            };
+            let repository = match message_compression {
+                Some(compression) => {
+                    info!(
+                        "Compressing messages of at least {} bytes with {}.",
+                        compression.threshold, compression.algorithm
+                    );
+                    repository.with_compression(compression)
+                }
+                None => repository,
+            };
 
             info!("Initialised postgres repository.");
 
@@ -104,33 +294,106 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 };
             }
 
-            Arc::new(repository)
+            // Transmissions may be scheduled through other replicas.
+            match repository.listen(wakeups.clone()).await {
+                Ok(_) => (),
+                Err(err) => {
+                    error!("Failed to listen for wakeups: {}", err);
+                    process::exit(1);
+                }
+            };
+
+            let repository = Arc::new(repository);
+
+            // Re-encrypt messages stored in plain text or with a rotated key in the background, such
+            // that retired keys can be removed once it completes.
+            if message_keyring.is_some() {
+                let re_encrypting_repository = repository.clone();
+                tokio::spawn(async move {
+                    match re_encrypting_repository.re_encrypt().await {
+                        Ok(re_encrypted) => {
+                            info!("Re-encrypted {} stored messages.", re_encrypted)
+                        }
+                        Err(err) => error!("Failed to re-encrypt stored messages: {}", err),
+                    }
+                });
+            }
+
+            repository
         }
-        config::Repository::InMemory => {
-            let repository = repository_in_memory::RepositoryInMemory::new();
-            info!("Initialised in-memory repository.");
+        config::Repository::Sqlite {
+            path,
+            lease_duration,
+        } => {
+            let sqlite_connection = match sqlite::connect_to_database(&path).await {
+                Ok(client) => client,
+                Err(err) => {
+                    error!("Failed to open sqlite database: {}", err);
+                    process::exit(1);
+                }
+            };
+
+            let repository = repository_sqlite::RepositorySqlite::new(sqlite_connection)
+                .with_lease_duration(
+                    lease_duration.unwrap_or(repository_postgres::DEFAULT_LEASE_DURATION),
+                )
+                .with_metrics(metrics_client.clone());
+
+            info!("Initialised sqlite repository.");
+
+            if config.automigrate {
+                info!("Running migrations.");
+
+                match repository.migrate().await {
+                    Ok(_) => (),
+                    Err(err) => {
+                        error!("Failed to run sql migrations: {}", err);
+                        process::exit(1);
+                    }
+                };
+            }
+
+            if config.reset_state {
+                match repository.clear_all().await {
+                    Ok(_) => (),
+                    Err(err) => {
+                        error!("Failed to reset repository state: {}", err);
+                        process::exit(1);
+                    }
+                };
+            }
 
             Arc::new(repository)
         }
-    };
+        config::Repository::Wal(wal_config) => {
+            let repository = match repository_wal::RepositoryWal::open(wal_config).await {
+                Ok(repository) => repository.with_metrics(metrics_client.clone()),
+                Err(err) => {
+                    error!("Failed to recover write-ahead log: {}", err);
+                    process::exit(1);
+                }
+            };
 
-    // Construct metrics client and server.
-    let metrics_client: Arc<dyn contract::Metrics> = match config.metrics {
-        config::Metrics::Prometheus(metrics_config) => {
-            let (metrics_client, metrics_server) = metrics::new(metrics_config);
+            info!("Initialised write-ahead log repository.");
 
-            // Start metrics server.
-            let _handle = tokio::spawn(async move {
-                match metrics_server.start_server().await {
+            if config.reset_state {
+                match repository.clear_all().await {
                     Ok(_) => (),
                     Err(err) => {
-                        error!("Failed to start prometheus server: {}", err);
+                        error!("Failed to reset repository state: {}", err);
                         process::exit(1);
                     }
-                }
-            });
+                };
+            }
 
-            Arc::new(metrics_client)
+            Arc::new(repository)
+        }
+        config::Repository::InMemory => {
+            let repository = repository_in_memory::RepositoryInMemory::new()
+                .with_metrics(metrics_client.clone());
+            info!("Initialised in-memory repository.");
+
+            Arc::new(repository)
         }
     };
 
@@ -138,13 +401,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now_provider = Arc::new(Utc::now);
 
     // Construct scheduler.
-    let scheduler = Arc::new(scheduler::TransmissionScheduler::new(
+    let scheduler = scheduler::TransmissionScheduler::new(
         config.clock_cycle_interval,
         repository,
         transmitter,
         now_provider,
         metrics_client,
-    ));
+    )
+    .with_lease_duration(lease_duration)
+    .with_exec_allowlist(config.exec_allowlist)
+    .with_subject_acl(config.nats_subject_acl)
+    .with_wakeups(wakeups);
+    let scheduler = match transports {
+        Some(transports) => scheduler.with_transports(transports),
+        None => scheduler,
+    };
+    let scheduler = match config.max_payload_size {
+        Some(max_payload_size) => scheduler.with_max_payload_size(max_payload_size),
+        None => scheduler,
+    };
+
+    // Register the configured schemas, which payloads may refer to once the transport serves.
+    let schemas = schema::SchemaRegistry::new();
+    for (name, json_schema) in &config.json_schemas {
+        match schemas.register(name, json_schema) {
+            Ok(()) => info!("Registered schema '{}'.", name),
+            Err(err) => {
+                error!("Failed to register schema '{}': {}", name, err);
+                process::exit(1);
+            }
+        }
+    }
+    let scheduler = Arc::new(scheduler.with_schemas(Arc::new(schemas)));
 
     // Initiate shared signal for graceful shutdown.
     let token = CancellationToken::new();
@@ -160,6 +448,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let grpc_handle = match config.transport {
         config::Transport::Grpc(grpc_config) => {
             let grpc_server = grpc::GrpcServer::new(grpc_config, scheduler);
+            let grpc_server = match config.authentication {
+                Some(authentication) => match auth::Authenticator::new(authentication) {
+                    Ok(authenticator) => {
+                        info!("Authenticating grpc callers.");
+                        grpc_server.with_authenticator(authenticator)
+                    }
+                    Err(err) => {
+                        error!("Failed to initialise authentication: {}", err);
+                        process::exit(1);
+                    }
+                },
+                None => {
+                    warn!("No authentication configured: grpc callers are anonymous.");
+                    grpc_server
+                }
+            };
 
             // Start gRPC server.
             tokio::spawn(async move {
@@ -18,6 +18,9 @@ use tokio::net::TcpListener;
 
 const METRIC_NAME: &str = "procedure";
 const METRIC_HELP_TEXT: &str = "Number of procedure calls";
+const DESTINATION_METRIC_NAME: &str = "destination";
+const DESTINATION_METRIC_HELP_TEXT: &str =
+    "Number of transmissions to fan out destinations per transport";
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
@@ -30,16 +33,27 @@ pub fn new(config: Config) -> (MetricClient, MetricServer) {
 
     let procedure_metric = Family::<ResultLabel, Counter>::default();
 
+    let destination_metric = Family::<DestinationLabel, Counter>::default();
+
     registry.register(METRIC_NAME, METRIC_HELP_TEXT, procedure_metric.clone());
+    registry.register(
+        DESTINATION_METRIC_NAME,
+        DESTINATION_METRIC_HELP_TEXT,
+        destination_metric.clone(),
+    );
 
     (
-        MetricClient { procedure_metric },
+        MetricClient {
+            procedure_metric,
+            destination_metric,
+        },
         MetricServer { config, registry },
     )
 }
 
 pub struct MetricClient {
     procedure_metric: Family<ResultLabel, Counter>,
+    destination_metric: Family<DestinationLabel, Counter>,
 }
 
 pub struct MetricServer {
@@ -111,6 +125,16 @@ fn make_handler(
 
 impl contract::Metrics for MetricClient {
     fn count(&self, metric_event: MetricEvent) {
+        if let MetricEvent::DestinationTransmitted(transport, success) = &metric_event {
+            let destination_label = DestinationLabel {
+                transport: transport.to_string(),
+                result: ResultStatus::from(*success),
+            };
+            self.destination_metric
+                .get_or_create(&destination_label)
+                .inc();
+        }
+
         let metric_label = ResultLabel::from(metric_event);
         self.procedure_metric.get_or_create(&metric_label).inc();
     }
@@ -122,6 +146,12 @@ pub struct ResultLabel {
     pub result: ResultStatus,
 }
 
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+pub struct DestinationLabel {
+    pub transport: String,
+    pub result: ResultStatus,
+}
+
 #[derive(Clone, Hash, PartialEq, Eq, EncodeLabelValue, Debug)]
 pub enum Procedure {
     Scheduled,
@@ -129,6 +159,9 @@ pub enum Procedure {
     Transmitted,
     ScheduleStateSaved,
     Rescheduled,
+    DestinationTransmitted,
+    LeaseReclaimed,
+    Quarantined,
 }
 
 impl From<MetricEvent> for ResultLabel {
@@ -154,6 +187,18 @@ impl From<MetricEvent> for ResultLabel {
                 procedure: Procedure::Rescheduled,
                 result: ResultStatus::from(success),
             },
+            MetricEvent::DestinationTransmitted(_, success) => ResultLabel {
+                procedure: Procedure::DestinationTransmitted,
+                result: ResultStatus::from(success),
+            },
+            MetricEvent::LeaseReclaimed => ResultLabel {
+                procedure: Procedure::LeaseReclaimed,
+                result: ResultStatus::Success,
+            },
+            MetricEvent::Quarantined => ResultLabel {
+                procedure: Procedure::Quarantined,
+                result: ResultStatus::Failure,
+            },
         }
     }
 }
@@ -180,6 +225,7 @@ mod tests {
     use prometheus_client::encoding::text::encode;
 
     use crate::contract::Metrics;
+    use crate::model::Transport;
 
     #[test]
     fn test_metric_increment() {
@@ -197,6 +243,33 @@ mod tests {
             "# HELP {METRIC_NAME} {METRIC_HELP_TEXT}.
 # TYPE {METRIC_NAME} counter
 {METRIC_NAME}_total{{procedure=\"Polled\",result=\"Failure\"}} 1
+# HELP {DESTINATION_METRIC_NAME} {DESTINATION_METRIC_HELP_TEXT}.
+# TYPE {DESTINATION_METRIC_NAME} counter
+# EOF
+",
+        );
+        assert_eq!(expected, buffer);
+    }
+
+    #[test]
+    fn test_destination_metric_increment() {
+        let config = Config {
+            port: 0,
+            endpoint: "/".to_string(),
+        };
+        let (prometheus_client, prometheus_server) = new(config);
+        prometheus_client.count(MetricEvent::DestinationTransmitted(Transport::Nats, true));
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &prometheus_server.registry).unwrap();
+
+        let expected = format!(
+            "# HELP {METRIC_NAME} {METRIC_HELP_TEXT}.
+# TYPE {METRIC_NAME} counter
+{METRIC_NAME}_total{{procedure=\"DestinationTransmitted\",result=\"Success\"}} 1
+# HELP {DESTINATION_METRIC_NAME} {DESTINATION_METRIC_HELP_TEXT}.
+# TYPE {DESTINATION_METRIC_NAME} counter
+{DESTINATION_METRIC_NAME}_total{{transport=\"nats\",result=\"Success\"}} 1
 # EOF
 ",
         );
@@ -235,6 +308,8 @@ mod tests {
             "# HELP {METRIC_NAME} {METRIC_HELP_TEXT}.
 # TYPE {METRIC_NAME} counter
 {METRIC_NAME}_total{{procedure=\"Scheduled\",result=\"Success\"}} 1
+# HELP {DESTINATION_METRIC_NAME} {DESTINATION_METRIC_HELP_TEXT}.
+# TYPE {DESTINATION_METRIC_NAME} counter
 # EOF
 ",
         );
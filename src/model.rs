@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt;
 use std::str::FromStr;
@@ -8,6 +9,9 @@ use chrono::prelude::*;
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::schema::SchemaError;
+use crate::template::TemplateError;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Transmission {
     pub id: Uuid,
@@ -15,6 +19,18 @@ pub struct Transmission {
     pub next: Option<DateTime<Utc>>,
     pub transmission_count: u32,
     pub message: Message,
+    // delivered_destinations contains the indices of the destinations of a FanOut message that
+    // have been transmitted to in the current occurrence, such that a retry skips them.
+    #[serde(default)]
+    pub delivered_destinations: Vec<u32>,
+    // failed_destinations contains the indices of the destinations of a FanOut message that failed
+    // permanently in the current occurrence. They are settled like delivered destinations, since
+    // a retry cannot succeed.
+    #[serde(default)]
+    pub failed_destinations: Vec<u32>,
+    // owner is the authenticated principal that scheduled the transmission, if any.
+    #[serde(default)]
+    pub owner: Option<Principal>,
 }
 
 impl Transmission {
@@ -33,6 +49,9 @@ impl Transmission {
             message,
             next,
             transmission_count: 0,
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         }
     }
 
@@ -53,19 +72,430 @@ impl Transmission {
             message: self.message.clone(),
             next: new_next,
             transmission_count: new_transmission_count,
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: self.owner.clone(),
         })
     }
+
+    // partially_transmitted records the destinations that were transmitted to or failed
+    // permanently, without transitioning to the next occurrence. It is appropriate when a FanOut
+    // message was transmitted to some, but not all of its destinations.
+    pub fn partially_transmitted(
+        &self,
+        delivered_destinations: Vec<u32>,
+        failed_destinations: Vec<u32>,
+    ) -> Transmission {
+        Transmission {
+            delivered_destinations,
+            failed_destinations,
+            ..self.clone()
+        }
+    }
+
+    // is_settled returns whether the destination of a FanOut message needs no further transmission
+    // in the current occurrence.
+    pub fn is_settled(&self, destination_index: u32) -> bool {
+        self.delivered_destinations.contains(&destination_index)
+            || self.failed_destinations.contains(&destination_index)
+    }
+}
+
+// Occurrence describes a single transmission of a message. Templated messages may refer to its
+// fields by name, e.g. `{{transmission_count}}`, and transmitters may record it alongside the
+// message.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Occurrence {
+    pub transmission_id: Uuid,
+    pub transmission_count: u32,
+    pub scheduled_at: DateTime<Utc>,
+    pub transmitted_at: DateTime<Utc>,
+}
+
+// Principal identifies the authenticated caller that schedules a transmission.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Principal {
+    pub name: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Message {
     NatsEvent(NatsEvent),
+    MqttPublish(MqttPublish),
+    AmqpPublish(AmqpPublish),
+    RedisPublish(RedisPublish),
+    RedisStreamAdd(RedisStreamAdd),
+    KafkaRecord(KafkaRecord),
+    GrpcCall(GrpcCall),
+    Exec(Exec),
+    Email(Email),
+    FanOut(FanOut),
+}
+
+// Transport is the kind of transmitter that transmits a message.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Transport {
+    Nats,
+    Mqtt,
+    Amqp,
+    Redis,
+    Kafka,
+    Grpc,
+    Exec,
+    Email,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Transport::Nats => write!(f, "nats"),
+            Transport::Mqtt => write!(f, "mqtt"),
+            Transport::Amqp => write!(f, "amqp"),
+            Transport::Redis => write!(f, "redis"),
+            Transport::Kafka => write!(f, "kafka"),
+            Transport::Grpc => write!(f, "grpc"),
+            Transport::Exec => write!(f, "exec"),
+            Transport::Email => write!(f, "email"),
+        }
+    }
+}
+
+impl Message {
+    // transport is the kind of transmitter of the message, or None for a FanOut, whose
+    // destinations each have their own.
+    pub fn transport(&self) -> Option<Transport> {
+        match self {
+            Message::NatsEvent(_) => Some(Transport::Nats),
+            Message::MqttPublish(_) => Some(Transport::Mqtt),
+            Message::AmqpPublish(_) => Some(Transport::Amqp),
+            Message::RedisPublish(_) | Message::RedisStreamAdd(_) => Some(Transport::Redis),
+            Message::KafkaRecord(_) => Some(Transport::Kafka),
+            Message::GrpcCall(_) => Some(Transport::Grpc),
+            Message::Exec(_) => Some(Transport::Exec),
+            Message::Email(_) => Some(Transport::Email),
+            Message::FanOut(_) => None,
+        }
+    }
+
+    // destination identifies where the message is transmitted to, e.g. to label metrics.
+    pub fn destination(&self) -> String {
+        match self {
+            Message::NatsEvent(nats_event) => format!("nats:{}", nats_event.subject),
+            Message::MqttPublish(mqtt_publish) => format!("mqtt:{}", mqtt_publish.topic),
+            Message::AmqpPublish(amqp_publish) => format!(
+                "amqp:{}/{}",
+                amqp_publish.exchange, amqp_publish.routing_key
+            ),
+            Message::RedisPublish(redis_publish) => format!("redis:{}", redis_publish.channel),
+            Message::RedisStreamAdd(redis_stream_add) => {
+                format!("redis_stream:{}", redis_stream_add.stream)
+            }
+            Message::KafkaRecord(kafka_record) => format!("kafka:{}", kafka_record.topic),
+            Message::GrpcCall(grpc_call) => {
+                format!("grpc:{}{}", grpc_call.endpoint, grpc_call.method)
+            }
+            Message::Exec(exec) => format!("exec:{}", exec.command),
+            Message::Email(_) => String::from("email"),
+            Message::FanOut(_) => String::from("fan_out"),
+        }
+    }
+
+    // payload_size is the number of bytes of the content the message carries, excluding where it
+    // is transmitted to, such that it can be held to the limits of the brokers.
+    pub fn payload_size(&self) -> usize {
+        match self {
+            Message::NatsEvent(nats_event) => nats_event.payload.len(),
+            Message::MqttPublish(mqtt_publish) => mqtt_publish.payload.len(),
+            Message::AmqpPublish(amqp_publish) => amqp_publish.body.len(),
+            Message::RedisPublish(redis_publish) => redis_publish.payload.len(),
+            Message::RedisStreamAdd(redis_stream_add) => redis_stream_add
+                .fields
+                .iter()
+                .map(|(key, value)| key.len() + value.len())
+                .sum(),
+            Message::KafkaRecord(kafka_record) => {
+                kafka_record.key.as_ref().map_or(0, Bytes::len)
+                    + kafka_record
+                        .headers
+                        .iter()
+                        .map(|(key, value)| key.len() + value.len())
+                        .sum::<usize>()
+                    + kafka_record.value.len()
+            }
+            Message::GrpcCall(grpc_call) => grpc_call.body.len(),
+            Message::Exec(exec) => {
+                exec.arguments.iter().map(String::len).sum::<usize>()
+                    + exec
+                        .environment
+                        .iter()
+                        .map(|(key, value)| key.len() + value.len())
+                        .sum::<usize>()
+            }
+            Message::Email(email) => {
+                email.subject.len()
+                    + email.text_body.as_ref().map_or(0, String::len)
+                    + email.html_body.as_ref().map_or(0, String::len)
+            }
+            Message::FanOut(fan_out) => {
+                fan_out.destinations.iter().map(Message::payload_size).sum()
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MqttPublish {
+    pub topic: String,
+    pub payload: Bytes,
+    pub qos: QualityOfService,
+    pub retain: bool,
+    #[serde(default)]
+    pub format: PayloadFormat,
+}
+
+impl MqttPublish {
+    pub fn new(topic: String, payload: Bytes, qos: QualityOfService, retain: bool) -> MqttPublish {
+        MqttPublish {
+            topic,
+            payload,
+            qos,
+            retain,
+            format: PayloadFormat::default(),
+        }
+    }
+}
+
+// QualityOfService is the delivery guarantee of an MQTT publication.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum QualityOfService {
+    AtMostOnce,
+    AtLeastOnce,
+    ExactlyOnce,
+}
+
+// AmqpPublish publishes the body to an AMQP 0-9-1 exchange, e.g. of RabbitMQ. The empty exchange
+// is the default exchange, which routes to the queue named by the routing key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AmqpPublish {
+    pub exchange: String,
+    pub routing_key: String,
+    pub properties: AmqpProperties,
+    pub body: Bytes,
+    // format.content_type defaults to the content type of the properties when validating.
+    #[serde(default)]
+    pub format: PayloadFormat,
+}
+
+impl AmqpPublish {
+    pub fn new(
+        exchange: String,
+        routing_key: String,
+        properties: AmqpProperties,
+        body: Bytes,
+    ) -> AmqpPublish {
+        AmqpPublish {
+            exchange,
+            routing_key,
+            properties,
+            body,
+            format: PayloadFormat::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AmqpProperties {
+    pub content_type: Option<String>,
+    pub correlation_id: Option<String>,
+    // persistent requests the broker to store the message on disk, to survive a broker restart.
+    pub persistent: bool,
+    pub headers: BTreeMap<String, String>,
+}
+
+// RedisPublish publishes the payload to the subscribers of a Redis pub/sub channel.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RedisPublish {
+    pub channel: String,
+    pub payload: Bytes,
+    #[serde(default)]
+    pub format: PayloadFormat,
+}
+
+impl RedisPublish {
+    pub fn new(channel: String, payload: Bytes) -> RedisPublish {
+        RedisPublish {
+            channel,
+            payload,
+            format: PayloadFormat::default(),
+        }
+    }
+}
+
+// RedisStreamAdd appends an entry with the given fields to a Redis stream.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RedisStreamAdd {
+    pub stream: String,
+    pub fields: BTreeMap<String, Bytes>,
+    // max_len approximately trims the stream to the given number of entries, if set.
+    pub max_len: Option<u64>,
+}
+
+impl RedisStreamAdd {
+    pub fn new(
+        stream: String,
+        fields: BTreeMap<String, Bytes>,
+        max_len: Option<u64>,
+    ) -> RedisStreamAdd {
+        RedisStreamAdd {
+            stream,
+            fields,
+            max_len,
+        }
+    }
+}
+
+// KafkaRecord produces the value to a Kafka topic. Records with the same key are assigned to the
+// same partition, which preserves their order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KafkaRecord {
+    pub topic: String,
+    pub key: Option<Bytes>,
+    pub headers: BTreeMap<String, Bytes>,
+    pub value: Bytes,
+    // format describes the value.
+    #[serde(default)]
+    pub format: PayloadFormat,
+}
+
+impl KafkaRecord {
+    pub fn new(
+        topic: String,
+        key: Option<Bytes>,
+        headers: BTreeMap<String, Bytes>,
+        value: Bytes,
+    ) -> KafkaRecord {
+        KafkaRecord {
+            topic,
+            key,
+            headers,
+            value,
+            format: PayloadFormat::default(),
+        }
+    }
+}
+
+// GrpcCall makes a unary call to a gRPC service. The body is the protobuf encoded request message,
+// such that any method can be called without knowing its schema.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GrpcCall {
+    // endpoint is the uri of the server, e.g. http://orders:8080.
+    pub endpoint: String,
+    // method is the fully qualified path of the method, e.g. /orders.Orders/Create.
+    pub method: String,
+    pub body: Bytes,
+    pub deadline: time::Duration,
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl GrpcCall {
+    pub fn new(
+        endpoint: String,
+        method: String,
+        body: Bytes,
+        deadline: time::Duration,
+        metadata: BTreeMap<String, String>,
+    ) -> GrpcCall {
+        GrpcCall {
+            endpoint,
+            method,
+            body,
+            deadline,
+            metadata,
+        }
+    }
+}
+
+// Exec runs a local command, which must be allowlisted in the configuration. A non-zero exit code
+// fails the transmission.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Exec {
+    // command is the path to the program, exactly as it is allowlisted.
+    pub command: String,
+    pub arguments: Vec<String>,
+    // environment contains the only environment variables that are set for the process.
+    pub environment: BTreeMap<String, String>,
+    // timeout is the duration after which the process is killed.
+    pub timeout: time::Duration,
+}
+
+impl Exec {
+    pub fn new(
+        command: String,
+        arguments: Vec<String>,
+        environment: BTreeMap<String, String>,
+        timeout: time::Duration,
+    ) -> Exec {
+        Exec {
+            command,
+            arguments,
+            environment,
+            timeout,
+        }
+    }
+}
+
+// Email is sent over SMTP. At least one of the text and html bodies is required; if both are
+// given, the recipient's client picks which to display.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Email {
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub text_body: Option<String>,
+    pub html_body: Option<String>,
+}
+
+impl Email {
+    pub fn new(
+        from: String,
+        to: Vec<String>,
+        subject: String,
+        text_body: Option<String>,
+        html_body: Option<String>,
+    ) -> Email {
+        Email {
+            from,
+            to,
+            subject,
+            text_body,
+            html_body,
+        }
+    }
+}
+
+// FanOut transmits the same occurrence to several destinations. Delivery to each destination is
+// tracked separately, so a failed destination is retried without repeating the others.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FanOut {
+    pub destinations: Vec<Message>,
+}
+
+impl FanOut {
+    pub fn new(destinations: Vec<Message>) -> FanOut {
+        FanOut { destinations }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct NatsEvent {
     pub subject: async_nats::Subject,
     pub payload: Bytes,
+    // templated opts in to rendering placeholders in the subject and payload at transmit time,
+    // like `{{transmission_count}}`. See the template module for the available variables.
+    #[serde(default)]
+    pub templated: bool,
+    #[serde(default)]
+    pub format: PayloadFormat,
 }
 
 impl NatsEvent {
@@ -73,10 +503,22 @@ impl NatsEvent {
         NatsEvent {
             subject: subject.into(),
             payload,
+            templated: false,
+            format: PayloadFormat::default(),
         }
     }
 }
 
+// PayloadFormat optionally describes the payload of a message, such that it can be validated when
+// the transmission is scheduled. JSON payloads must be well-formed, and conform to the JSON Schema
+// registered under the name of schema, if set.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PayloadFormat {
+    pub content_type: Option<String>,
+    pub schema: Option<String>,
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Schedule {
     Delayed(Delayed),
@@ -227,6 +669,27 @@ pub enum ScheduleError {
     AgedSchedule,
     TooShortInterval,
     NatsInvalidSubject,
+    NatsSubjectNotAllowed,
+    MqttInvalidTopic,
+    AmqpInvalidExchange,
+    AmqpInvalidRoutingKey,
+    RedisInvalidChannel,
+    RedisInvalidStream,
+    KafkaInvalidTopic,
+    KafkaInvalidHeader,
+    GrpcInvalidEndpoint,
+    GrpcInvalidMethod,
+    GrpcInvalidDeadline,
+    GrpcInvalidMetadata,
+    ExecInvalidCommand,
+    ExecCommandNotAllowed,
+    EmailInvalidAddress,
+    EmailMissingBody,
+    FanOutInvalidDestinations,
+    TransportNotConfigured(Transport),
+    InvalidPayload(SchemaError),
+    PayloadTooLarge { size: usize, max_size: usize },
+    InvalidTemplate(TemplateError),
     Other(Box<dyn Error>),
 }
 
@@ -240,6 +703,45 @@ impl fmt::Display for ScheduleError {
             ScheduleError::NatsInvalidSubject => {
                 write!(f, "subject not allowed")
             }
+            ScheduleError::NatsSubjectNotAllowed => {
+                write!(f, "subject not permitted for the caller")
+            }
+            ScheduleError::MqttInvalidTopic => write!(f, "topic not allowed"),
+            ScheduleError::AmqpInvalidExchange => write!(f, "exchange not allowed"),
+            ScheduleError::AmqpInvalidRoutingKey => write!(f, "routing key not allowed"),
+            ScheduleError::RedisInvalidChannel => write!(f, "channel not allowed"),
+            ScheduleError::RedisInvalidStream => {
+                write!(f, "stream requires a name and at least one field")
+            }
+            ScheduleError::KafkaInvalidTopic => write!(f, "topic not allowed"),
+            ScheduleError::KafkaInvalidHeader => write!(f, "header key not allowed"),
+            ScheduleError::GrpcInvalidEndpoint => write!(f, "endpoint must be an http(s) uri"),
+            ScheduleError::GrpcInvalidMethod => {
+                write!(f, "method must be formatted as /package.Service/Method")
+            }
+            ScheduleError::GrpcInvalidDeadline => write!(f, "deadline must be positive"),
+            ScheduleError::GrpcInvalidMetadata => write!(f, "metadata not allowed"),
+            ScheduleError::ExecInvalidCommand => write!(f, "invalid command"),
+            ScheduleError::ExecCommandNotAllowed => write!(f, "command not allowlisted"),
+            ScheduleError::EmailInvalidAddress => write!(f, "invalid email address"),
+            ScheduleError::EmailMissingBody => write!(f, "email requires a text or html body"),
+            ScheduleError::FanOutInvalidDestinations => {
+                write!(
+                    f,
+                    "fan out requires at least one destination and cannot be nested"
+                )
+            }
+            ScheduleError::TransportNotConfigured(transport) => {
+                write!(f, "no {} transmitter configured", transport)
+            }
+            ScheduleError::InvalidPayload(err) => write!(f, "invalid payload: {}", err),
+            ScheduleError::PayloadTooLarge { size, max_size } => {
+                write!(
+                    f,
+                    "payload of {size} bytes exceeds maximum of {max_size} bytes"
+                )
+            }
+            ScheduleError::InvalidTemplate(err) => write!(f, "invalid template: {}", err),
             ScheduleError::Other(err) => write!(f, "err: {}", err),
         }
     }
@@ -260,11 +762,124 @@ impl PartialEq for ScheduleError {
                 ScheduleError::NatsInvalidSubject => true,
                 _ => false,
             },
+            ScheduleError::NatsSubjectNotAllowed => match other {
+                ScheduleError::NatsSubjectNotAllowed => true,
+                _ => false,
+            },
+            ScheduleError::MqttInvalidTopic => match other {
+                ScheduleError::MqttInvalidTopic => true,
+                _ => false,
+            },
+            ScheduleError::AmqpInvalidExchange => match other {
+                ScheduleError::AmqpInvalidExchange => true,
+                _ => false,
+            },
+            ScheduleError::AmqpInvalidRoutingKey => match other {
+                ScheduleError::AmqpInvalidRoutingKey => true,
+                _ => false,
+            },
+            ScheduleError::RedisInvalidChannel => match other {
+                ScheduleError::RedisInvalidChannel => true,
+                _ => false,
+            },
+            ScheduleError::RedisInvalidStream => match other {
+                ScheduleError::RedisInvalidStream => true,
+                _ => false,
+            },
+            ScheduleError::KafkaInvalidTopic => match other {
+                ScheduleError::KafkaInvalidTopic => true,
+                _ => false,
+            },
+            ScheduleError::KafkaInvalidHeader => match other {
+                ScheduleError::KafkaInvalidHeader => true,
+                _ => false,
+            },
+            ScheduleError::GrpcInvalidEndpoint => match other {
+                ScheduleError::GrpcInvalidEndpoint => true,
+                _ => false,
+            },
+            ScheduleError::GrpcInvalidMethod => match other {
+                ScheduleError::GrpcInvalidMethod => true,
+                _ => false,
+            },
+            ScheduleError::GrpcInvalidDeadline => match other {
+                ScheduleError::GrpcInvalidDeadline => true,
+                _ => false,
+            },
+            ScheduleError::GrpcInvalidMetadata => match other {
+                ScheduleError::GrpcInvalidMetadata => true,
+                _ => false,
+            },
+            ScheduleError::ExecInvalidCommand => match other {
+                ScheduleError::ExecInvalidCommand => true,
+                _ => false,
+            },
+            ScheduleError::ExecCommandNotAllowed => match other {
+                ScheduleError::ExecCommandNotAllowed => true,
+                _ => false,
+            },
+            ScheduleError::EmailInvalidAddress => match other {
+                ScheduleError::EmailInvalidAddress => true,
+                _ => false,
+            },
+            ScheduleError::EmailMissingBody => match other {
+                ScheduleError::EmailMissingBody => true,
+                _ => false,
+            },
+            ScheduleError::FanOutInvalidDestinations => match other {
+                ScheduleError::FanOutInvalidDestinations => true,
+                _ => false,
+            },
+            ScheduleError::TransportNotConfigured(transport) => match other {
+                ScheduleError::TransportNotConfigured(other_transport) => {
+                    transport == other_transport
+                }
+                _ => false,
+            },
+            ScheduleError::PayloadTooLarge { size, max_size } => match other {
+                ScheduleError::PayloadTooLarge {
+                    size: other_size,
+                    max_size: other_max_size,
+                } => size == other_size && max_size == other_max_size,
+                _ => false,
+            },
+            ScheduleError::InvalidPayload(err) => match other {
+                ScheduleError::InvalidPayload(other_err) => err == other_err,
+                _ => false,
+            },
+            ScheduleError::InvalidTemplate(err) => match other {
+                ScheduleError::InvalidTemplate(other_err) => err == other_err,
+                _ => false,
+            },
             _ => false,
         }
     }
 }
 
+// PermanentTransmitError signals that a transmission cannot succeed when retried, e.g. because the
+// recipient was rejected. Transmitters return it to have the occurrence skipped, rather than
+// rescheduled.
+#[derive(Debug)]
+pub struct PermanentTransmitError {
+    reason: String,
+}
+
+impl PermanentTransmitError {
+    pub fn new(reason: impl Into<String>) -> PermanentTransmitError {
+        PermanentTransmitError {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for PermanentTransmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "permanent transmission failure: {}", self.reason)
+    }
+}
+
+impl Error for PermanentTransmitError {}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum MetricEvent {
     Scheduled(bool),
@@ -272,4 +887,11 @@ pub enum MetricEvent {
     Transmitted(bool),
     ScheduleStateSaved(bool),
     Rescheduled(bool),
+    DestinationTransmitted(Transport, bool),
+    // LeaseReclaimed counts transmissions that were polled after the lease of another poller
+    // expired, i.e. that poller presumably crashed before it finished their transmission.
+    LeaseReclaimed,
+    // Quarantined counts transmissions that were polled, but could not be read, e.g. because their
+    // encryption key was retired, so are no longer polled.
+    Quarantined,
 }
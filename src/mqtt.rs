@@ -0,0 +1,224 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{error, info};
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet};
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+// KEEP_ALIVE is the interval in which the broker expects to hear from the client.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+// REQUEST_CAPACITY bounds the number of publications that await the event loop.
+const REQUEST_CAPACITY: usize = 100;
+// RECONNECT_INTERVAL is the pause after a connection error, before the event loop reconnects.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+// Acknowledgements matches the acknowledgements of the broker to the publications that await them.
+// The event loop sends publications in the order they were queued, and assigns each a packet id
+// when it is sent, so publications must be expected in the order they are queued.
+#[derive(Default)]
+pub struct Acknowledgements {
+    state: Mutex<AcknowledgementState>,
+}
+
+#[derive(Default)]
+struct AcknowledgementState {
+    // unsent contains the publications that were queued, but not yet sent.
+    unsent: VecDeque<oneshot::Sender<()>>,
+    // inflight contains the publications that were sent, by packet id.
+    inflight: HashMap<u16, oneshot::Sender<()>>,
+    // colliding contains the publication that awaits the acknowledgement of another publication
+    // with the same packet id, before it is sent.
+    colliding: Option<(u16, oneshot::Sender<()>)>,
+}
+
+impl Acknowledgements {
+    // expect registers the publication that is queued next, and resolves when the broker
+    // acknowledged it. Publications with QoS 0 resolve when they are sent.
+    pub fn expect(&self) -> oneshot::Receiver<()> {
+        let (acknowledge, acknowledged) = oneshot::channel();
+        self.state
+            .lock()
+            .expect("mutex is poisoned")
+            .unsent
+            .push_back(acknowledge);
+
+        acknowledged
+    }
+
+    // cancel removes the publication that was expected last, if it could not be queued.
+    pub fn cancel(&self) {
+        self.state
+            .lock()
+            .expect("mutex is poisoned")
+            .unsent
+            .pop_back();
+    }
+
+    fn handle(&self, event: &Event) {
+        let mut state = self.state.lock().expect("mutex is poisoned");
+        match event {
+            Event::Outgoing(Outgoing::Publish(0)) => {
+                if let Some(acknowledge) = state.unsent.pop_front() {
+                    let _ = acknowledge.send(());
+                }
+            }
+            // Publications are sent again after a reconnect, under the packet id they were
+            // assigned before.
+            Event::Outgoing(Outgoing::Publish(pkid)) if state.inflight.contains_key(pkid) => (),
+            Event::Outgoing(Outgoing::Publish(pkid)) => {
+                if let Some(acknowledge) = state.unsent.pop_front() {
+                    state.inflight.insert(*pkid, acknowledge);
+                }
+            }
+            Event::Outgoing(Outgoing::AwaitAck(pkid)) => {
+                if let Some(acknowledge) = state.unsent.pop_front() {
+                    state.colliding = Some((*pkid, acknowledge));
+                }
+            }
+            Event::Incoming(Packet::PubAck(rumqttc::PubAck { pkid, .. }))
+            | Event::Incoming(Packet::PubComp(rumqttc::PubComp { pkid, .. })) => {
+                if let Some(acknowledge) = state.inflight.remove(pkid) {
+                    let _ = acknowledge.send(());
+                }
+                if state
+                    .colliding
+                    .as_ref()
+                    .is_some_and(|(colliding_pkid, _)| colliding_pkid == pkid)
+                {
+                    if let Some((colliding_pkid, acknowledge)) = state.colliding.take() {
+                        state.inflight.insert(colliding_pkid, acknowledge);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+pub async fn connect_to_mqtt(
+    config: Config,
+) -> Result<(AsyncClient, Arc<Acknowledgements>), Box<dyn std::error::Error>> {
+    info!("Connecting to mqtt://{}:{}", config.host, config.port);
+
+    let mut options = MqttOptions::new(config.client_id, config.host, config.port);
+    options.set_keep_alive(KEEP_ALIVE);
+    if let Some(username) = config.username {
+        options.set_credentials(username, config.password.unwrap_or_default());
+    }
+
+    let (client, mut event_loop) = AsyncClient::new(options, REQUEST_CAPACITY);
+
+    // Await the acknowledgement of the broker, such that an unreachable broker or refused
+    // credentials surface at startup.
+    loop {
+        if let Event::Incoming(Packet::ConnAck(_)) = event_loop.poll().await? {
+            break;
+        }
+    }
+
+    // The event loop must be polled continuously, to send publications and to reconnect.
+    let acknowledgements = Arc::new(Acknowledgements::default());
+    let handling_acknowledgements = acknowledgements.clone();
+    tokio::spawn(async move {
+        loop {
+            match event_loop.poll().await {
+                Ok(event) => handling_acknowledgements.handle(&event),
+                Err(err) => {
+                    error!("Mqtt connection failed: {err}");
+                    tokio::time::sleep(RECONNECT_INTERVAL).await;
+                }
+            }
+        }
+    });
+
+    Ok((client, acknowledgements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rumqttc::{PubAck, PubComp};
+
+    fn acknowledged(acknowledged: &mut oneshot::Receiver<()>) -> bool {
+        acknowledged.try_recv().is_ok()
+    }
+
+    #[test]
+    fn test_acknowledgements() {
+        let acknowledgements = Acknowledgements::default();
+        let mut at_most_once = acknowledgements.expect();
+        let mut at_least_once = acknowledgements.expect();
+        let mut exactly_once = acknowledgements.expect();
+
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(0)));
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(1)));
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(2)));
+        assert!(acknowledged(&mut at_most_once), "sent QoS 0 publication");
+        assert!(
+            !acknowledged(&mut at_least_once),
+            "unacknowledged publication"
+        );
+
+        // The publication is sent again after a reconnect.
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(1)));
+        acknowledgements.handle(&Event::Incoming(Packet::PubComp(PubComp::new(2))));
+        assert!(
+            !acknowledged(&mut at_least_once),
+            "other publication acknowledged"
+        );
+        assert!(
+            acknowledged(&mut exactly_once),
+            "completed QoS 2 publication"
+        );
+
+        acknowledgements.handle(&Event::Incoming(Packet::PubAck(PubAck::new(1))));
+        assert!(
+            acknowledged(&mut at_least_once),
+            "acknowledged QoS 1 publication"
+        );
+    }
+
+    #[test]
+    fn test_acknowledgements_collision() {
+        let acknowledgements = Acknowledgements::default();
+        let mut first = acknowledgements.expect();
+        let mut colliding = acknowledgements.expect();
+
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(1)));
+        acknowledgements.handle(&Event::Outgoing(Outgoing::AwaitAck(1)));
+        acknowledgements.handle(&Event::Incoming(Packet::PubAck(PubAck::new(1))));
+        assert!(acknowledged(&mut first), "first publication");
+        assert!(
+            !acknowledged(&mut colliding),
+            "colliding publication before it was sent"
+        );
+
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(1)));
+        acknowledgements.handle(&Event::Incoming(Packet::PubAck(PubAck::new(1))));
+        assert!(acknowledged(&mut colliding), "colliding publication");
+    }
+
+    #[test]
+    fn test_acknowledgements_cancel() {
+        let acknowledgements = Acknowledgements::default();
+        let mut queued = acknowledgements.expect();
+        let _cancelled = acknowledgements.expect();
+        acknowledgements.cancel();
+
+        acknowledgements.handle(&Event::Outgoing(Outgoing::Publish(1)));
+        acknowledgements.handle(&Event::Incoming(Packet::PubAck(PubAck::new(1))));
+        assert!(acknowledged(&mut queued));
+    }
+}
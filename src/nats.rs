@@ -1,3 +1,7 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use async_nats::{ConnectOptions, ServerAddr};
 use log::info;
 use serde::Deserialize;
 
@@ -5,16 +9,376 @@ use serde::Deserialize;
 pub struct Config {
     pub port: u16,
     pub host: String,
+    // servers contains the urls of further servers of the cluster, which the client fails over to
+    // if the server at host and port is unavailable.
+    #[serde(default)]
+    pub servers: Vec<String>,
+    // tls requires the connection to be secured with TLS, if set.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    // auth is loaded from secrets, so is not deserialized.
+    #[serde(skip)]
+    pub auth: Auth,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TlsConfig {
+    // ca_path is the path to the PEM encoded certificates of the authorities that the server
+    // certificate may be issued by, in addition to the system's roots.
+    #[serde(default)]
+    pub ca_path: Option<String>,
+    // cert_path and key_path are the paths to the PEM encoded certificate and private key that
+    // the client presents, if the server requires mutual TLS.
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    #[serde(default)]
+    pub key_path: Option<String>,
+}
+
+// Auth is the method that the client authenticates with.
+#[derive(Clone, Default)]
+pub enum Auth {
+    #[default]
+    Anonymous,
+    // Credentials holds the contents of a .creds file, containing a user JWT and NKey seed.
+    Credentials(String),
+    // NKey holds the seed of an NKey, of which the public key is configured in the server.
+    NKey(String),
+    UserPassword {
+        user: String,
+        password: String,
+    },
+}
+
+// The credentials are secret, so only the method and user are printed.
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Auth::Anonymous => write!(f, "Anonymous"),
+            Auth::Credentials(_) => write!(f, "Credentials"),
+            Auth::NKey(_) => write!(f, "NKey"),
+            Auth::UserPassword { user, .. } => f
+                .debug_struct("UserPassword")
+                .field("user", user)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl Config {
+    // server_addrs returns the address of host and port, followed by those of the other servers.
+    fn server_addrs(&self) -> Result<Vec<ServerAddr>, String> {
+        std::iter::once(format!("nats://{}:{}", self.host, self.port))
+            .chain(self.servers.iter().cloned())
+            .map(|url| {
+                url.parse::<ServerAddr>()
+                    .map_err(|err| format!("invalid nats server url '{url}': {err}"))
+            })
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.server_addrs()?;
+
+        if let Some(tls) = &self.tls {
+            if tls.cert_path.is_some() != tls.key_path.is_some() {
+                return Err(String::from(
+                    "nats tls requires both a client certificate and key, or neither",
+                ));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 pub async fn connect_to_nats(
     config: Config,
 ) -> Result<async_nats::Client, Box<dyn std::error::Error>> {
-    let connection_string = format!("nats://{}:{}", config.host, config.port);
+    let server_addrs = config.server_addrs()?;
+
+    let mut options = match config.auth {
+        Auth::Anonymous => ConnectOptions::new(),
+        Auth::Credentials(credentials) => ConnectOptions::with_credentials(&credentials)?,
+        Auth::NKey(seed) => ConnectOptions::with_nkey(seed),
+        Auth::UserPassword { user, password } => {
+            ConnectOptions::with_user_and_password(user, password)
+        }
+    };
+    if let Some(tls) = config.tls {
+        options = options.require_tls(true);
+        if let Some(ca_path) = tls.ca_path {
+            options = options.add_root_certificates(ca_path.into());
+        }
+        if let (Some(cert_path), Some(key_path)) = (tls.cert_path, tls.key_path) {
+            options = options.add_client_certificate(cert_path.into(), key_path.into());
+        }
+    }
 
-    info!("Connecting to {}", connection_string);
+    info!(
+        "Connecting to {}",
+        server_addrs
+            .iter()
+            .map(|server_addr| format!("{}:{}", server_addr.host(), server_addr.port()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
 
-    let client = async_nats::connect(connection_string).await?;
+    let client = options.connect(server_addrs).await?;
 
     Ok(client)
 }
+
+// SubjectAcl restricts the subjects that NatsEvent messages may be scheduled onto, by patterns of
+// subjects with the NATS wildcards: `*` matches a single token, and a trailing `>` matches one or
+// more tokens. A subject matching a pattern of deny is never allowed. Callers with client rules
+// may only schedule onto the subjects their rules allow; the other callers, including anonymous
+// callers, onto the subjects of allow, or any subject if it is empty.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SubjectAcl {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    // clients contains the rules by the name of the authenticated principal they apply to.
+    #[serde(default)]
+    pub clients: BTreeMap<String, ClientSubjectRules>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ClientSubjectRules {
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl SubjectAcl {
+    // is_allowed asserts whether the caller, identified by the name of its principal if
+    // authenticated, may schedule onto the subject.
+    pub fn is_allowed(&self, subject: &str, principal_name: Option<&str>) -> bool {
+        let matches_any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| subject_matches(pattern, subject))
+        };
+
+        if matches_any(&self.deny) {
+            return false;
+        }
+
+        match principal_name.and_then(|name| self.clients.get(name)) {
+            Some(rules) => matches_any(&rules.allow) && !matches_any(&rules.deny),
+            None => self.allow.is_empty() || matches_any(&self.allow),
+        }
+    }
+
+    // validate asserts that each pattern is a valid subject, with wildcards only as full tokens and
+    // `>` only as the last token.
+    pub fn validate(&self) -> Result<(), String> {
+        self.allow
+            .iter()
+            .chain(&self.deny)
+            .chain(
+                self.clients
+                    .values()
+                    .flat_map(|rules| rules.allow.iter().chain(&rules.deny)),
+            )
+            .try_for_each(|pattern| {
+                let tokens: Vec<&str> = pattern.split('.').collect();
+                let valid = tokens.iter().enumerate().all(|(index, token)| {
+                    !token.is_empty()
+                        && !token.contains(char::is_whitespace)
+                        && (*token != ">" || index == tokens.len() - 1)
+                        && (token.len() == 1 || !token.contains(['*', '>']))
+                });
+
+                match valid {
+                    true => Ok(()),
+                    false => Err(format!("invalid subject pattern '{pattern}'")),
+                }
+            })
+    }
+}
+
+// subject_matches matches the subject against the pattern token by token, as NATS matches
+// subscriptions.
+fn subject_matches(pattern: &str, subject: &str) -> bool {
+    let mut subject_tokens = subject.split('.');
+
+    for pattern_token in pattern.split('.') {
+        match (pattern_token, subject_tokens.next()) {
+            (">", Some(_)) => return true,
+            (_, None) => return false,
+            ("*", Some(_)) => continue,
+            (pattern_token, Some(subject_token)) if pattern_token == subject_token => continue,
+            _ => return false,
+        }
+    }
+
+    subject_tokens.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subject_matches() {
+        struct TestCase {
+            pattern: &'static str,
+            subject: &'static str,
+            expected_match: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                pattern: "ORDERS.created",
+                subject: "ORDERS.created",
+                expected_match: true,
+            },
+            TestCase {
+                pattern: "ORDERS.created",
+                subject: "ORDERS.created.eu",
+                expected_match: false,
+            },
+            TestCase {
+                pattern: "ORDERS.*",
+                subject: "ORDERS.created",
+                expected_match: true,
+            },
+            TestCase {
+                pattern: "ORDERS.*",
+                subject: "ORDERS.created.eu",
+                expected_match: false,
+            },
+            TestCase {
+                pattern: "ORDERS.>",
+                subject: "ORDERS.created.eu",
+                expected_match: true,
+            },
+            TestCase {
+                pattern: "ORDERS.>",
+                subject: "ORDERS",
+                expected_match: false,
+            },
+            TestCase {
+                pattern: "ORDERS.*.eu",
+                subject: "ORDERS.created.us",
+                expected_match: false,
+            },
+            TestCase {
+                pattern: "ORDERS",
+                subject: "ORDERSX",
+                expected_match: false,
+            },
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                subject_matches(test_case.pattern, test_case.subject),
+                test_case.expected_match,
+                "test case failed: {} against {}",
+                test_case.subject,
+                test_case.pattern
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        struct TestCase {
+            name: String,
+            subject: String,
+            principal_name: Option<String>,
+            expected_allowed: bool,
+        }
+
+        let acl = SubjectAcl {
+            allow: vec![String::from("PUBLIC.>")],
+            deny: vec![String::from("INTERNAL.>")],
+            clients: BTreeMap::from([(
+                String::from("orders"),
+                ClientSubjectRules {
+                    allow: vec![String::from("ORDERS.>"), String::from("INTERNAL.>")],
+                    deny: vec![String::from("ORDERS.admin.>")],
+                },
+            )]),
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("anonymous onto allowed subject"),
+                subject: String::from("PUBLIC.news"),
+                principal_name: None,
+                expected_allowed: true,
+            },
+            TestCase {
+                name: String::from("anonymous onto other subject"),
+                subject: String::from("ORDERS.created"),
+                principal_name: None,
+                expected_allowed: false,
+            },
+            TestCase {
+                name: String::from("client onto own prefix"),
+                subject: String::from("ORDERS.created"),
+                principal_name: Some(String::from("orders")),
+                expected_allowed: true,
+            },
+            TestCase {
+                name: String::from("client onto subject denied to client"),
+                subject: String::from("ORDERS.admin.purge"),
+                principal_name: Some(String::from("orders")),
+                expected_allowed: false,
+            },
+            TestCase {
+                name: String::from("client onto globally denied subject"),
+                subject: String::from("INTERNAL.control"),
+                principal_name: Some(String::from("orders")),
+                expected_allowed: false,
+            },
+            TestCase {
+                name: String::from("client onto subject allowed to others"),
+                subject: String::from("PUBLIC.news"),
+                principal_name: Some(String::from("orders")),
+                expected_allowed: false,
+            },
+            TestCase {
+                name: String::from("principal without client rules"),
+                subject: String::from("PUBLIC.news"),
+                principal_name: Some(String::from("billing")),
+                expected_allowed: true,
+            },
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                acl.is_allowed(&test_case.subject, test_case.principal_name.as_deref()),
+                test_case.expected_allowed,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+
+        assert!(SubjectAcl::default().is_allowed("ANY.subject", None));
+    }
+
+    #[test]
+    fn test_validate_patterns() {
+        let valid_acl = SubjectAcl {
+            allow: vec![String::from("ORDERS.*.created"), String::from("ORDERS.>")],
+            ..SubjectAcl::default()
+        };
+        assert!(valid_acl.validate().is_ok());
+
+        for pattern in ["ORDERS.>.created", "ORDERS..created", "ORDERS.crea*", ""] {
+            let invalid_acl = SubjectAcl {
+                deny: vec![String::from(pattern)],
+                ..SubjectAcl::default()
+            };
+            assert!(
+                invalid_acl.validate().is_err(),
+                "pattern should be invalid: {pattern}"
+            );
+        }
+    }
+}
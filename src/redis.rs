@@ -0,0 +1,48 @@
+use log::info;
+use redis::aio::ConnectionManager;
+use redis::{ConnectionAddr, ConnectionInfo, RedisConnectionInfo};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub db: i64,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+// connect_to_redis returns a connection that is shared by clones and reconnects on failure.
+pub async fn connect_to_redis(
+    config: Config,
+) -> Result<ConnectionManager, Box<dyn std::error::Error>> {
+    info!(
+        "Connecting to redis://{}:{}/{}",
+        config.host, config.port, config.db
+    );
+
+    let client = redis::Client::open(ConnectionInfo {
+        addr: ConnectionAddr::Tcp(config.host, config.port),
+        redis: RedisConnectionInfo {
+            db: config.db,
+            username: config.username,
+            password: config.password,
+        },
+    })?;
+
+    let connection = ConnectionManager::new(client).await?;
+
+    Ok(connection)
+}
+
+// hash_tag returns the part of the key that Redis Cluster hashes instead of the whole key: the
+// non-empty part between the first '{' and the first '}' after it.
+pub fn hash_tag(key: &str) -> Option<&str> {
+    let open = key.find('{')?;
+    let close = key[open + 1..].find('}')?;
+
+    match &key[open + 1..open + 1 + close] {
+        "" => None,
+        tag => Some(tag),
+    }
+}
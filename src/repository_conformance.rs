@@ -0,0 +1,453 @@
+// The conformance suite describes the behaviour the scheduler relies on from a repository, such
+// that each implementation of contract::Repository is checked against the same expectations. Each
+// repository module runs it from a test, with a function that opens an empty repository.
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time;
+
+use chrono::prelude::*;
+use chrono::{Duration, DurationRound};
+
+use mockall::predicate::eq;
+
+use crate::contract::{Metrics, MockMetrics, Repository};
+use crate::model::*;
+
+// LEASE_DURATION is the lease duration of the repositories of the cases that do not let leases
+// expire.
+const LEASE_DURATION: time::Duration = time::Duration::from_secs(300);
+
+// check_conformance runs each case of the suite against a new repository, which is opened with the
+// given lease duration and metrics.
+pub async fn check_conformance<F, Fut>(new_repository: F)
+where
+    F: Fn(time::Duration, Arc<dyn Metrics>) -> Fut,
+    Fut: Future<Output = Arc<dyn Repository>>,
+{
+    check_store(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_poll_order(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_batch_limit(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_concurrent_pollers(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_save(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_reschedule(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_renew_lease(new_repository(LEASE_DURATION, no_metrics()).await).await;
+    check_reclaim_expired_lease(&new_repository).await;
+    check_next_due(new_repository(LEASE_DURATION, no_metrics()).await).await;
+}
+
+// no_metrics fails the case when any metric is counted.
+fn no_metrics() -> Arc<dyn Metrics> {
+    Arc::new(MockMetrics::new())
+}
+
+// now is truncated to microseconds, the precision of the Postgres timestamps.
+fn now() -> DateTime<Utc> {
+    Utc::now()
+        .duration_trunc(Duration::microseconds(1))
+        .expect("now should truncate")
+}
+
+// delayed_transmission is a transmission of an arbitrary message that is due at the given time.
+pub(crate) fn delayed_transmission(transmit_at: DateTime<Utc>, payload: &str) -> Transmission {
+    Transmission::new(
+        Schedule::Delayed(Delayed::new(transmit_at)),
+        Message::NatsEvent(NatsEvent::new(
+            "ARBITRARY.subject".into(),
+            payload.to_string().into(),
+        )),
+    )
+}
+
+async fn store(repository: &Arc<dyn Repository>, transmissions: &[&Transmission]) {
+    for transmission in transmissions {
+        repository
+            .store_transmission(transmission)
+            .await
+            .expect("store transmission should be ok");
+    }
+}
+
+async fn poll(
+    repository: &Arc<dyn Repository>,
+    before: DateTime<Utc>,
+    batch_size: u32,
+) -> Vec<Transmission> {
+    repository
+        .poll_transmissions(before, batch_size)
+        .await
+        .expect("poll batch should be ok")
+}
+
+// check_store checks that stored transmissions are polled unchanged once they are due.
+async fn check_store(repository: Arc<dyn Repository>) {
+    let now = now();
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![],
+        "store: empty poll"
+    );
+
+    let due = Transmission {
+        owner: Some(Principal {
+            name: "arbitrary-principal".into(),
+        }),
+        ..delayed_transmission(now - Duration::milliseconds(100), "due payload")
+    };
+    let future = delayed_transmission(now + Duration::milliseconds(100), "future payload");
+    let transmitted = delayed_transmission(now - Duration::milliseconds(100), "transmitted")
+        .transmitted()
+        .expect("transmission should transition to transmitted");
+    store(&repository, &[&due, &future, &transmitted]).await;
+
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![due],
+        "store: due transmission"
+    );
+}
+
+// check_poll_order checks that the transmissions that are due first are polled first.
+async fn check_poll_order(repository: Arc<dyn Repository>) {
+    let now = now();
+    let transmissions: Vec<Transmission> = [300, 100, 400, 200]
+        .into_iter()
+        .map(|age| {
+            delayed_transmission(
+                now - Duration::milliseconds(age),
+                &format!("payload due {age}ms ago"),
+            )
+        })
+        .collect();
+    store(&repository, &transmissions.iter().collect::<Vec<_>>()).await;
+
+    let mut expected = transmissions.clone();
+    expected.sort_by_key(|transmission| transmission.next);
+    let mut polled = vec![];
+    for _ in 0..transmissions.len() {
+        polled.extend(poll(&repository, now, 1).await);
+    }
+    assert_eq!(polled, expected, "poll order");
+}
+
+// check_batch_limit checks that a poll returns at most the batch size, and that the remaining
+// transmissions are returned by subsequent polls.
+async fn check_batch_limit(repository: Arc<dyn Repository>) {
+    let now = now();
+    let transmissions: Vec<Transmission> = (0..5)
+        .map(|index| {
+            delayed_transmission(
+                now - Duration::milliseconds(index + 1),
+                &format!("payload {index}"),
+            )
+        })
+        .collect();
+    store(&repository, &transmissions.iter().collect::<Vec<_>>()).await;
+
+    for expected_len in [2, 2, 1, 0] {
+        assert_eq!(
+            poll(&repository, now, 2).await.len(),
+            expected_len,
+            "batch limit"
+        );
+    }
+}
+
+// check_concurrent_pollers checks that concurrent pollers never receive the same transmission.
+async fn check_concurrent_pollers(repository: Arc<dyn Repository>) {
+    let now = now();
+    let transmissions: Vec<Transmission> = (0..50)
+        .map(|index| {
+            delayed_transmission(
+                now - Duration::milliseconds(index + 1),
+                &format!("payload {index}"),
+            )
+        })
+        .collect();
+    store(&repository, &transmissions.iter().collect::<Vec<_>>()).await;
+
+    let pollers: Vec<_> = (0..8)
+        .map(|_| {
+            let repository = repository.clone();
+            tokio::spawn(async move {
+                let mut polled_ids = vec![];
+                loop {
+                    let polled_batch = repository
+                        .poll_transmissions(now, 3)
+                        .await
+                        .expect("poll batch should be ok");
+                    if polled_batch.is_empty() {
+                        return polled_ids;
+                    }
+                    polled_ids.extend(polled_batch.iter().map(|transmission| transmission.id));
+                }
+            })
+        })
+        .collect();
+
+    let mut polled_ids = vec![];
+    for poller in pollers {
+        polled_ids.extend(poller.await.expect("poller should finish"));
+    }
+    assert_eq!(
+        polled_ids.len(),
+        transmissions.len(),
+        "concurrent pollers: transmission polled twice"
+    );
+    assert_eq!(
+        polled_ids.into_iter().collect::<HashSet<_>>(),
+        transmissions
+            .iter()
+            .map(|transmission| transmission.id)
+            .collect::<HashSet<_>>(),
+        "concurrent pollers"
+    );
+}
+
+// check_save checks that a saved transmission is polled at its next occurrence, and that it is
+// never replaced by the state of an earlier occurrence.
+async fn check_save(repository: Arc<dyn Repository>) {
+    let now = now();
+    let first_transmission = now - Duration::milliseconds(100);
+    let interval = time::Duration::from_secs(3600);
+    let transmission = Transmission::new(
+        Schedule::Interval(Interval::new(
+            first_transmission,
+            interval,
+            Iterate::Times(2),
+        )),
+        Message::NatsEvent(NatsEvent::new(
+            "ARBITRARY.subject".into(),
+            "interval payload".into(),
+        )),
+    );
+    store(&repository, &[&transmission]).await;
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission.clone()],
+        "save: first occurrence"
+    );
+
+    let partially_transmitted = transmission.partially_transmitted(vec![0], vec![1]);
+    repository
+        .save(&partially_transmitted)
+        .await
+        .expect("save should be ok");
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![partially_transmitted],
+        "save: partially transmitted occurrence"
+    );
+
+    let transmitted = transmission
+        .transmitted()
+        .expect("transmission should transition to transmitted");
+    repository
+        .save(&transmitted)
+        .await
+        .expect("save should be ok");
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![],
+        "save: next occurrence polled early"
+    );
+
+    // The state of a poller that lost its lease is stale.
+    repository
+        .save(&transmission)
+        .await
+        .expect("save should be ok");
+    let second_occurrence = first_transmission + interval;
+    let after_second_occurrence = second_occurrence + Duration::milliseconds(1);
+    assert_eq!(
+        poll(&repository, after_second_occurrence, 100).await,
+        vec![transmitted.clone()],
+        "save: second occurrence"
+    );
+
+    let exhausted = transmitted
+        .transmitted()
+        .expect("transmission should transition to transmitted");
+    assert_eq!(exhausted.next, None);
+    repository
+        .save(&exhausted)
+        .await
+        .expect("save should be ok");
+    assert_eq!(
+        poll(&repository, after_second_occurrence, 100).await,
+        vec![],
+        "save: exhausted transmission"
+    );
+}
+
+// check_reschedule checks that a polled transmission is leased until it is rescheduled.
+async fn check_reschedule(repository: Arc<dyn Repository>) {
+    let now = now();
+    let transmission = delayed_transmission(now - Duration::milliseconds(100), "payload");
+    store(&repository, &[&transmission]).await;
+
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission.clone()],
+        "reschedule: first poll"
+    );
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![],
+        "reschedule: leased transmission polled"
+    );
+
+    repository
+        .reschedule(&transmission.id)
+        .await
+        .expect("reschedule should be ok");
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission],
+        "reschedule: rescheduled transmission"
+    );
+
+    repository
+        .reschedule(&uuid::Uuid::new_v4())
+        .await
+        .expect("reschedule of unknown transmission should be ok");
+}
+
+// check_renew_lease checks that only a leased transmission has its lease renewed.
+async fn check_renew_lease(repository: Arc<dyn Repository>) {
+    let renew_lease = |repository: Arc<dyn Repository>, id: uuid::Uuid| async move {
+        repository
+            .renew_lease(&id)
+            .await
+            .expect("renew lease should be ok")
+    };
+
+    let now = now();
+    let transmission = delayed_transmission(now - Duration::milliseconds(100), "payload");
+    store(&repository, &[&transmission]).await;
+    assert!(
+        !renew_lease(repository.clone(), transmission.id).await,
+        "renew lease: not leased"
+    );
+
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission.clone()]
+    );
+    assert!(
+        renew_lease(repository.clone(), transmission.id).await,
+        "renew lease: leased"
+    );
+
+    repository
+        .reschedule(&transmission.id)
+        .await
+        .expect("reschedule should be ok");
+    assert!(
+        !renew_lease(repository.clone(), transmission.id).await,
+        "renew lease: rescheduled"
+    );
+    assert!(
+        !renew_lease(repository.clone(), uuid::Uuid::new_v4()).await,
+        "renew lease: unknown transmission"
+    );
+}
+
+// check_reclaim_expired_lease checks that a transmission is reclaimed once the lease of a stalled
+// poller expires, and that a rescheduled transmission is released rather than reclaimed.
+async fn check_reclaim_expired_lease<F, Fut>(new_repository: &F)
+where
+    F: Fn(time::Duration, Arc<dyn Metrics>) -> Fut,
+    Fut: Future<Output = Arc<dyn Repository>>,
+{
+    let lease_duration = time::Duration::from_millis(500);
+    let mut metrics = MockMetrics::new();
+    metrics
+        .expect_count()
+        .with(eq(MetricEvent::LeaseReclaimed))
+        .times(1)
+        .returning(|_| ());
+    let repository = new_repository(lease_duration, Arc::new(metrics)).await;
+
+    let now = now();
+    let transmission = delayed_transmission(now - Duration::milliseconds(100), "stalled payload");
+    store(&repository, &[&transmission]).await;
+
+    // The poller stalls without saving or rescheduling the transmission.
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission.clone()],
+        "reclaim: first poll"
+    );
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![],
+        "reclaim: leased transmission polled"
+    );
+
+    tokio::time::sleep(lease_duration).await;
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission.clone()],
+        "reclaim: expired lease"
+    );
+
+    repository
+        .reschedule(&transmission.id)
+        .await
+        .expect("reschedule should be ok");
+    assert_eq!(
+        poll(&repository, now, 100).await,
+        vec![transmission],
+        "reclaim: rescheduled transmission"
+    );
+}
+
+// check_next_due checks that the next due time is that of the earliest transmission, or the expiry
+// of its lease while it is leased.
+async fn check_next_due(repository: Arc<dyn Repository>) {
+    let next_due = |repository: Arc<dyn Repository>| async move {
+        repository.next_due().await.expect("next due should be ok")
+    };
+
+    let now = now();
+    assert_eq!(next_due(repository.clone()).await, None, "next due: empty");
+
+    let earliest = delayed_transmission(now - Duration::milliseconds(100), "earliest");
+    let latest = delayed_transmission(now + Duration::hours(1), "latest");
+    let transmitted = delayed_transmission(now - Duration::milliseconds(200), "transmitted")
+        .transmitted()
+        .expect("transmission should transition to transmitted");
+    store(&repository, &[&latest, &transmitted]).await;
+    assert_eq!(
+        next_due(repository.clone()).await,
+        latest.next,
+        "next due: latest"
+    );
+
+    store(&repository, &[&earliest]).await;
+    assert_eq!(
+        next_due(repository.clone()).await,
+        earliest.next,
+        "next due: earliest"
+    );
+
+    assert_eq!(poll(&repository, now, 100).await, vec![earliest.clone()]);
+    let leased_due = next_due(repository.clone())
+        .await
+        .expect("next due: leased transmission");
+    assert!(
+        leased_due > now && leased_due <= latest.next.expect("latest should be due"),
+        "next due: leased transmission due at {leased_due}"
+    );
+
+    repository
+        .reschedule(&earliest.id)
+        .await
+        .expect("reschedule should be ok");
+    assert_eq!(
+        next_due(repository.clone()).await,
+        earliest.next,
+        "next due: rescheduled"
+    );
+}
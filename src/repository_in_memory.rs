@@ -1,76 +1,206 @@
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::prelude::*;
+use log::warn;
+use uuid::Uuid;
 
-use crate::contract::Repository;
+use crate::contract::{Metrics, Repository};
 use crate::model::*;
+use crate::repository_postgres::DEFAULT_LEASE_DURATION;
 
+// RepositoryInMemory indexes the transmissions by id and by next occurrence, such that polling
+// takes time proportional to the batch rather than to the number of stored transmissions. Polled
+// transmissions are leased like in the Postgres repository, such that concurrent pollers never
+// receive the same transmission.
 pub struct RepositoryInMemory {
-    transmissions: Arc<Mutex<Vec<Transmission>>>,
+    state: Arc<Mutex<State>>,
+    // lease_duration is the time after which the leases of polled transmissions expire, such that
+    // the transmissions of a stalled poller are reclaimed.
+    lease_duration: Duration,
+    // metrics counts the reclaimed leases, if set.
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+#[derive(Default)]
+struct State {
+    transmissions: HashMap<Uuid, Transmission>,
+    // schedule orders the transmissions that are due at some point by their next occurrence, and
+    // contains the expiry of the lease on each polled transmission.
+    schedule: BTreeMap<(DateTime<Utc>, Uuid), Option<DateTime<Utc>>>,
+}
+
+impl Default for RepositoryInMemory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RepositoryInMemory {
     pub fn new() -> RepositoryInMemory {
         RepositoryInMemory {
-            transmissions: Arc::new(Mutex::new(vec![])),
+            state: Arc::new(Mutex::new(State::default())),
+            lease_duration: DEFAULT_LEASE_DURATION,
+            metrics: None,
+        }
+    }
+
+    pub fn with_lease_duration(self, lease_duration: Duration) -> RepositoryInMemory {
+        RepositoryInMemory {
+            lease_duration,
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<dyn Metrics>) -> RepositoryInMemory {
+        RepositoryInMemory {
+            metrics: Some(metrics),
+            ..self
         }
     }
 }
 
 #[async_trait]
 impl Repository for RepositoryInMemory {
+    // store_transmission replaces the state of the transmission, which releases its lease. A state
+    // is never replaced by an earlier occurrence, e.g. of a poller that lost its lease.
     async fn store_transmission(
         &self,
         transmission: &Transmission,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        self.transmissions
-            .lock()
-            .expect("mutex is poisoned")
-            .push(transmission.clone());
+        let mut state = self.state.lock().expect("mutex is poisoned");
+        let State {
+            transmissions,
+            schedule,
+        } = &mut *state;
+
+        if let Some(stored) = transmissions.get(&transmission.id) {
+            if stored.transmission_count > transmission.transmission_count {
+                return Ok(());
+            }
+            if let Some(next) = stored.next {
+                schedule.remove(&(next, stored.id));
+            }
+        }
+
+        if let Some(next) = transmission.next {
+            schedule.insert((next, transmission.id), None);
+        }
+        transmissions.insert(transmission.id, transmission.clone());
 
         Ok(())
     }
 
+    // poll_transmissions leases due transmissions that are not leased, or whose lease expired.
     async fn poll_transmissions(
         &self,
         before: DateTime<Utc>,
         batch_size: u32,
     ) -> Result<Vec<Transmission>, Box<dyn Error>> {
-        Ok(self
-            .transmissions
-            .clone()
-            .lock()
-            .expect("mutex is poisoned")
-            .iter()
-            .filter(|transmission| match transmission.next {
-                None => false,
-                Some(next) => next <= before,
-            })
-            .map(|transmission_reference| transmission_reference.clone())
-            .take(batch_size as usize)
-            .collect())
-    }
+        let now = Utc::now();
+        let leased_until = now + chrono::Duration::from_std(self.lease_duration)?;
 
-    async fn save(&self, schedule: &Transmission) -> Result<(), Box<dyn Error + Send + Sync>> {
-        for stored_schedule in self.transmissions.lock().unwrap().iter_mut() {
-            if stored_schedule.id == schedule.id {
-                *stored_schedule = schedule.clone();
+        let mut state = self.state.lock().expect("mutex is poisoned");
+        let State {
+            transmissions,
+            schedule,
+        } = &mut *state;
+
+        let mut polled_transmissions = vec![];
+        let mut reclaimed_ids = vec![];
+        for ((next, id), lease_expiry) in schedule.iter_mut() {
+            if polled_transmissions.len() >= batch_size as usize || *next >= before {
+                break;
             }
+
+            match lease_expiry {
+                Some(expiry) if *expiry >= now => continue,
+                Some(_) => reclaimed_ids.push(*id),
+                None => (),
+            }
+            *lease_expiry = Some(leased_until);
+            polled_transmissions.push(transmissions[id].clone());
         }
+        drop(state);
 
-        Ok(())
+        for id in reclaimed_ids {
+            warn!("Reclaimed transmission {id} after its lease expired.");
+            if let Some(metrics) = &self.metrics {
+                metrics.count(MetricEvent::LeaseReclaimed);
+            }
+        }
+
+        Ok(polled_transmissions)
     }
 
-    // reschedule is unnecessary for an in-memory implementation.
+    async fn save(&self, schedule: &Transmission) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.store_transmission(schedule).await
+    }
+
+    // reschedule releases the lease on the transmission, such that it is polled again.
     async fn reschedule(
         &self,
-        _schedule_id: &uuid::Uuid,
+        transmission_id: &uuid::Uuid,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut state = self.state.lock().expect("mutex is poisoned");
+        let next = state
+            .transmissions
+            .get(transmission_id)
+            .and_then(|transmission| transmission.next);
+        if let Some(next) = next {
+            if let Some(lease_expiry) = state.schedule.get_mut(&(next, *transmission_id)) {
+                *lease_expiry = None;
+            }
+        }
+
         Ok(())
     }
+
+    // renew_lease extends the lease on the transmission, if it is leased. All pollers of the
+    // repository share this process, so any lease is held by them.
+    async fn renew_lease(
+        &self,
+        transmission_id: &uuid::Uuid,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let leased_until = Utc::now() + chrono::Duration::from_std(self.lease_duration)?;
+
+        let mut state = self.state.lock().expect("mutex is poisoned");
+        let next = state
+            .transmissions
+            .get(transmission_id)
+            .and_then(|transmission| transmission.next);
+        let lease_expiry = next.and_then(|next| state.schedule.get_mut(&(next, *transmission_id)));
+
+        match lease_expiry {
+            Some(Some(lease_expiry)) => {
+                *lease_expiry = leased_until;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // next_due scans the schedule only until the next occurrence exceeds the earliest due time
+    // found, since a transmission is never due before its next occurrence.
+    async fn next_due(&self) -> Result<Option<DateTime<Utc>>, Box<dyn Error + Send + Sync>> {
+        let state = self.state.lock().expect("mutex is poisoned");
+
+        let mut next_due: Option<DateTime<Utc>> = None;
+        for ((next, _), lease_expiry) in state.schedule.iter() {
+            if next_due.is_some_and(|next_due| *next >= next_due) {
+                break;
+            }
+
+            let due = lease_expiry.map_or(*next, |expiry| expiry.max(*next));
+            next_due = Some(next_due.map_or(due, |next_due| next_due.min(due)));
+        }
+
+        Ok(next_due)
+    }
 }
 
 #[cfg(test)]
@@ -79,6 +209,8 @@ mod tests {
 
     use tokio::time::pause;
 
+    use crate::repository_conformance;
+
     #[tokio::test]
     async fn test_store() {
         let repository = RepositoryInMemory::new();
@@ -115,6 +247,9 @@ mod tests {
             message: transmissions[0].message.clone(),
             next: Some(past),
             transmission_count: 0,
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         }];
 
         for transmission in transmissions.iter() {
@@ -149,4 +284,16 @@ mod tests {
             .expect("poll batch should be ok");
         assert_eq!(polled_transmissions_transmitted, vec![]);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_conformance() {
+        repository_conformance::check_conformance(|lease_duration, metrics| async move {
+            Arc::new(
+                RepositoryInMemory::new()
+                    .with_lease_duration(lease_duration)
+                    .with_metrics(metrics),
+            ) as Arc<dyn Repository>
+        })
+        .await;
+    }
 }
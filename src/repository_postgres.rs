@@ -1,25 +1,93 @@
 use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use chrono::{DateTime, Utc};
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use sqlx::postgres::PgPool;
+use sqlx::postgres::{PgListener, PgPool};
+use sqlx::PgExecutor;
+use tokio::sync::Notify;
 use uuid::Uuid;
 
-use crate::contract::Repository;
+use crate::compression;
+use crate::contract::{Metrics, Repository};
+use crate::encryption::{Envelope, Keyring};
 use crate::model::*;
 
+// RE_ENCRYPTION_BATCH_SIZE is the number of rows re-encrypted per query, such that the job does
+// not hold on to many rows at once.
+const RE_ENCRYPTION_BATCH_SIZE: i64 = 100;
+// DEFAULT_LEASE_DURATION is the time a poller holds on to the transmissions it polled, after which
+// other pollers may reclaim them.
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(300);
+// WAKEUP_CHANNEL is notified when a transmission is stored that is due earlier than any other, such
+// that the schedulers of all replicas wake up for it.
+const WAKEUP_CHANNEL: &str = "transmission_wakeup";
+// LISTEN_RETRY_INTERVAL is the time to wait before receiving notifications again after a failure.
+const LISTEN_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct RepositoryPostgres {
     conn: PgPool,
+    // keyring encrypts the message column, if configured. Without it, messages are stored in plain
+    // text.
+    keyring: Option<Keyring>,
+    // compression compresses messages from its size threshold, if configured.
+    compression: Option<compression::Config>,
+    // lease_owner identifies this poller in the locks it takes on polled transmissions.
+    lease_owner: String,
+    // lease_duration is the time after which the locks of this poller expire, such that the
+    // transmissions of a crashed poller are reclaimed by the others.
+    lease_duration: Duration,
+    // metrics counts the reclaimed leases, if set.
+    metrics: Option<Arc<dyn Metrics>>,
 }
 
 impl RepositoryPostgres {
     pub fn new(conn: PgPool) -> RepositoryPostgres {
-        info!("constructing new repository");
+        let lease_owner = Uuid::new_v4().to_string();
+        info!("constructing new repository with lease owner {lease_owner}");
+
+        RepositoryPostgres {
+            conn,
+            keyring: None,
+            compression: None,
+            lease_owner,
+            lease_duration: DEFAULT_LEASE_DURATION,
+            metrics: None,
+        }
+    }
+
+    pub fn with_keyring(self, keyring: Keyring) -> RepositoryPostgres {
+        RepositoryPostgres {
+            keyring: Some(keyring),
+            ..self
+        }
+    }
+
+    pub fn with_compression(self, compression: compression::Config) -> RepositoryPostgres {
+        RepositoryPostgres {
+            compression: Some(compression),
+            ..self
+        }
+    }
 
-        RepositoryPostgres { conn }
+    pub fn with_lease_duration(self, lease_duration: Duration) -> RepositoryPostgres {
+        RepositoryPostgres {
+            lease_duration,
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<dyn Metrics>) -> RepositoryPostgres {
+        RepositoryPostgres {
+            metrics: Some(metrics),
+            ..self
+        }
     }
 
     pub async fn migrate(&self) -> Result<(), sqlx::Error> {
@@ -30,86 +98,446 @@ impl RepositoryPostgres {
         Ok(())
     }
 
+    // listen notifies wakeups whenever any replica stores a transmission that is due earlier than
+    // any other. Since notifications sent while the connection is lost are missed, wakeups is also
+    // notified when the connection is reestablished.
+    pub async fn listen(&self, wakeups: Arc<Notify>) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(&self.conn).await?;
+        listener.listen(WAKEUP_CHANNEL).await?;
+
+        tokio::spawn(async move {
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(_)) => wakeups.notify_one(),
+                    Ok(None) => {
+                        warn!("Lost connection to listen for wakeups; reconnecting.");
+                        wakeups.notify_one();
+                    }
+                    Err(err) => {
+                        error!("Failed to listen for wakeups: {err}");
+                        tokio::time::sleep(LISTEN_RETRY_INTERVAL).await;
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
     pub async fn clear_all(&self) -> Result<(), Box<dyn Error>> {
         info!("deleting all transmissions");
 
+        let _ = sqlx::query!("delete from transmission_occurrence;")
+            .execute(&self.conn)
+            .await?;
         let _ = sqlx::query!("delete from transmission;")
             .execute(&self.conn)
             .await?;
 
         Ok(())
     }
+
+    // re_encrypt encrypts the messages that are stored in plain text or with a key other than the
+    // current one, in batches until none remain. It returns the number of re-encrypted rows. It
+    // stops early if none of a batch could be updated, since its rows are being written
+    // concurrently, e.g. by replicas that do not have the current key yet.
+    pub async fn re_encrypt(&self) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let keyring = match &self.keyring {
+            Some(keyring) => keyring,
+            None => return Err("no encryption keys are configured".into()),
+        };
+
+        let mut re_encrypted = 0;
+        loop {
+            let rows = sqlx::query_as!(
+                StaleMessageSql,
+                "
+SELECT id, transmission_count, message, message_key_id, message_data_key
+FROM transmission
+WHERE message_key_id IS DISTINCT FROM $1
+LIMIT $2;
+        ",
+                keyring.current_key_id(),
+                RE_ENCRYPTION_BATCH_SIZE,
+            )
+            .fetch_all(&self.conn)
+            .await?;
+
+            if rows.is_empty() {
+                return Ok(re_encrypted);
+            }
+
+            let mut re_encrypted_batch = 0;
+            for row in rows {
+                let envelope = match (&row.message_key_id, &row.message_data_key) {
+                    // Only the data key is wrapped with the current key, since the message itself
+                    // is encrypted with the data key.
+                    (Some(key_id), Some(data_key)) => keyring.rewrap(&Envelope {
+                        key_id: key_id.clone(),
+                        data_key: data_key.clone(),
+                        ciphertext: row.message.clone(),
+                    })?,
+                    (None, None) => keyring.seal(row.message.as_bytes(), row.id.as_bytes())?,
+                    _ => return Err(format!("row {} has an incomplete envelope", row.id).into()),
+                };
+
+                // The row is only updated if it was not re-encrypted concurrently.
+                let updated = sqlx::query!(
+                    "
+UPDATE transmission
+SET message = $1, message_key_id = $2, message_data_key = $3
+WHERE id = $4
+  AND transmission_count = $5
+  AND message_key_id IS NOT DISTINCT FROM $6
+  AND message_data_key IS NOT DISTINCT FROM $7;
+        ",
+                    envelope.ciphertext,
+                    envelope.key_id,
+                    envelope.data_key,
+                    row.id,
+                    row.transmission_count,
+                    row.message_key_id,
+                    row.message_data_key,
+                )
+                .execute(&self.conn)
+                .await?;
+
+                re_encrypted_batch += updated.rows_affected();
+            }
+
+            if re_encrypted_batch == 0 {
+                warn!(
+                    "Stopped re-encryption after {re_encrypted} messages, since the remaining \
+                    messages were modified concurrently; run it again once all replicas use the \
+                    current key."
+                );
+                return Ok(re_encrypted);
+            }
+
+            re_encrypted += re_encrypted_batch;
+            debug!("re-encrypted {re_encrypted} messages");
+        }
+    }
+
+    // compress compresses the message of the row, if compression is configured and the message
+    // reaches its threshold. The compressed message is base64 encoded to fit the text column.
+    fn compress(
+        &self,
+        transmission_sql: TransmissionSql,
+    ) -> Result<TransmissionSql, Box<dyn Error + Send + Sync>> {
+        let compression = match &self.compression {
+            Some(compression) if transmission_sql.message.len() >= compression.threshold => {
+                compression
+            }
+            _ => return Ok(transmission_sql),
+        };
+
+        let compressed = compression
+            .algorithm
+            .compress(transmission_sql.message.as_bytes())?;
+
+        Ok(TransmissionSql {
+            message: STANDARD.encode(compressed),
+            message_compression: Some(compression.algorithm.to_string()),
+            ..transmission_sql
+        })
+    }
+
+    // decompress decompresses the message of the row, if it was compressed. It does not depend on
+    // the configured compression, such that it may be changed or disabled at any time.
+    fn decompress(
+        &self,
+        transmission_sql: TransmissionSql,
+    ) -> Result<TransmissionSql, Box<dyn Error + Send + Sync>> {
+        let algorithm: compression::Algorithm = match &transmission_sql.message_compression {
+            Some(algorithm) => algorithm.parse()?,
+            None => return Ok(transmission_sql),
+        };
+
+        let message = algorithm.decompress(&STANDARD.decode(&transmission_sql.message)?)?;
+
+        Ok(TransmissionSql {
+            message: String::from_utf8(message)?,
+            message_compression: None,
+            ..transmission_sql
+        })
+    }
+
+    // seal encrypts the message of the row, if a keyring is configured. The row id is
+    // authenticated, such that a message cannot be moved to another transmission.
+    fn seal(
+        &self,
+        transmission_sql: TransmissionSql,
+    ) -> Result<TransmissionSql, Box<dyn Error + Send + Sync>> {
+        let keyring = match &self.keyring {
+            Some(keyring) => keyring,
+            None => return Ok(transmission_sql),
+        };
+
+        let envelope = keyring.seal(
+            transmission_sql.message.as_bytes(),
+            transmission_sql.id.as_bytes(),
+        )?;
+
+        Ok(TransmissionSql {
+            message: envelope.ciphertext,
+            message_key_id: Some(envelope.key_id),
+            message_data_key: Some(envelope.data_key),
+            ..transmission_sql
+        })
+    }
+
+    // quarantine stops polling the transmission by clearing its next occurrence, and releases its
+    // lease. Its row is kept, such that it can be inspected and repaired.
+    async fn quarantine(&self, transmission_id: &Uuid) -> Result<(), sqlx::Error> {
+        let _ = sqlx::query!(
+            "
+UPDATE transmission
+SET next = NULL, locked_by = NULL, locked_until = NULL
+WHERE id = $1
+  AND locked_by = $2;
+        ",
+            transmission_id,
+            self.lease_owner,
+        )
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    // open decrypts the message of the row, if it was encrypted.
+    fn open(
+        &self,
+        transmission_sql: TransmissionSql,
+    ) -> Result<TransmissionSql, Box<dyn Error + Send + Sync>> {
+        let envelope = match (
+            &transmission_sql.message_key_id,
+            &transmission_sql.message_data_key,
+        ) {
+            (Some(key_id), Some(data_key)) => Envelope {
+                key_id: key_id.clone(),
+                data_key: data_key.clone(),
+                ciphertext: transmission_sql.message.clone(),
+            },
+            (None, None) => return Ok(transmission_sql),
+            _ => {
+                return Err(
+                    format!("row {} has an incomplete envelope", transmission_sql.id).into(),
+                )
+            }
+        };
+
+        let keyring = match &self.keyring {
+            Some(keyring) => keyring,
+            None => {
+                return Err("message is encrypted, but no encryption keys are configured".into())
+            }
+        };
+
+        let message = keyring.open(&envelope, transmission_sql.id.as_bytes())?;
+
+        Ok(TransmissionSql {
+            message: String::from_utf8(message)?,
+            message_key_id: None,
+            message_data_key: None,
+            ..transmission_sql
+        })
+    }
 }
 
 #[async_trait]
 impl Repository for RepositoryPostgres {
+    // store_transmission stores the current state of the transmission, which releases its lease.
+    // If the transmission moved on to a later occurrence, the occurrence it moved on from is
+    // recorded in its history.
     async fn store_transmission(
         &self,
         schedule: &Transmission,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         info!("storing transmission");
 
-        let schedule_sql = TransmissionSql::from(schedule);
+        // The message is compressed before it is encrypted, since ciphertext does not compress.
+        let schedule_sql = self.seal(self.compress(TransmissionSql::from(schedule))?)?;
+
+        let mut transaction = self.conn.begin().await?;
 
+        let stored = sqlx::query!(
+            "
+SELECT transmission_count, next, locked_by
+FROM transmission
+WHERE id = $1
+FOR UPDATE;
+        ",
+            schedule_sql.id,
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        if let Some(stored) = stored {
+            // A poller whose lease was reclaimed by another poller must not overwrite its state.
+            if stored
+                .locked_by
+                .as_ref()
+                .is_some_and(|locked_by| *locked_by != self.lease_owner)
+            {
+                return Err(format!(
+                    "transmission {} is leased by another poller",
+                    schedule_sql.id
+                )
+                .into());
+            }
+
+            if stored.transmission_count < schedule_sql.transmission_count {
+                let _ = sqlx::query!(
+                    "
+INSERT INTO transmission_occurrence (
+    transmission_id, transmission_count, scheduled_at, transmitted_at
+) VALUES (
+    $1, $2, $3, now()
+)
+ON CONFLICT DO NOTHING;
+        ",
+                    schedule_sql.id,
+                    stored.transmission_count,
+                    stored.next,
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+
+        // A state is never replaced by an earlier occurrence, e.g. of a poller that lost its lease.
         let _ = sqlx::query!(
             "
 INSERT INTO transmission (
-    id, message, next, schedule, transmission_count, inserted_at, is_locked
+    id, message, message_key_id, message_data_key, message_compression, next, schedule,
+    transmission_count, delivered_destinations, failed_destinations, owner, inserted_at
 ) VALUES (
-    $1, $2, $3, $4, $5, now(), false
-);
+    $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, now()
+)
+ON CONFLICT (id) DO UPDATE
+SET message = EXCLUDED.message,
+    message_key_id = EXCLUDED.message_key_id,
+    message_data_key = EXCLUDED.message_data_key,
+    message_compression = EXCLUDED.message_compression,
+    next = EXCLUDED.next,
+    schedule = EXCLUDED.schedule,
+    transmission_count = EXCLUDED.transmission_count,
+    delivered_destinations = EXCLUDED.delivered_destinations,
+    failed_destinations = EXCLUDED.failed_destinations,
+    owner = EXCLUDED.owner,
+    inserted_at = EXCLUDED.inserted_at,
+    locked_by = NULL,
+    locked_until = NULL
+WHERE transmission.transmission_count <= EXCLUDED.transmission_count;
         ",
             schedule_sql.id,
             schedule_sql.message,
+            schedule_sql.message_key_id,
+            schedule_sql.message_data_key,
+            schedule_sql.message_compression,
             schedule_sql.next,
             schedule_sql.schedule,
             schedule_sql.transmission_count as i32,
+            &schedule_sql.delivered_destinations,
+            &schedule_sql.failed_destinations,
+            schedule_sql.owner,
         )
-        .execute(&self.conn)
+        .execute(&mut *transaction)
         .await?;
 
+        // The notification is delivered when the transaction commits.
+        if let Some(next) = schedule_sql.next {
+            let others_due = next_due(&mut *transaction, Some(schedule_sql.id)).await?;
+            let earliest = match others_due {
+                None => true,
+                Some(others_due) => next < others_due,
+            };
+            if earliest {
+                let _ = sqlx::query("SELECT pg_notify($1, '');")
+                    .bind(WAKEUP_CHANNEL)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
+
+        transaction.commit().await?;
+
         Ok(())
     }
 
+    // poll_transmissions leases due transmissions that are not leased, or whose lease expired.
+    // Leases expire by the clock of the database, such that pollers need not agree on the time.
     async fn poll_transmissions(
         &self,
         before: DateTime<Utc>,
         batch_size: u32,
     ) -> Result<Vec<Transmission>, Box<dyn Error>> {
         debug!("polling batch");
-        let message_schedules_sql = sqlx::query_as!(
-            TransmissionSql,
-            "
-WITH locked_schedules AS (
-    UPDATE transmission
-    SET is_locked = true
-    WHERE id IN (
-        SELECT id
-        FROM (
-            SELECT id, MAX(inserted_at) AS latest_inserted_at
-            FROM transmission
-            GROUP BY id
-        ) latest_entries
-        WHERE inserted_at = latest_inserted_at
-    )
-    AND next IS NOT NULL
-    AND next < $1
-    AND is_locked = false
-    RETURNING id, message, next, schedule, transmission_count
+        let leased_schedules_sql = sqlx::query_as!(
+            LeasedTransmissionSql,
+            r#"
+WITH leasable AS (
+    SELECT id, locked_until IS NOT NULL AS reclaimed
+    FROM transmission
+    WHERE next IS NOT NULL
+      AND next < $1
+      AND (locked_until IS NULL OR locked_until < now())
+    ORDER BY next
+    LIMIT $2
+    FOR UPDATE SKIP LOCKED
 )
-SELECT * FROM locked_schedules
-LIMIT $2;
-        ",
+UPDATE transmission
+SET locked_by = $3, locked_until = now() + make_interval(secs => $4)
+FROM leasable
+WHERE transmission.id = leasable.id
+RETURNING transmission.id, transmission.message, transmission.message_key_id,
+    transmission.message_data_key, transmission.message_compression, transmission.next,
+    transmission.schedule, transmission.transmission_count,
+    transmission.delivered_destinations, transmission.failed_destinations, transmission.owner,
+    leasable.reclaimed AS "reclaimed!";
+        "#,
             before,
             batch_size as i64,
+            self.lease_owner,
+            self.lease_duration.as_secs_f64(),
         )
         .fetch_all(&self.conn)
         .await?;
 
-        let message_schedules: Vec<Transmission> = message_schedules_sql
-            .iter()
-            .map(|message_schedule_sql| Transmission::from((*message_schedule_sql).clone()))
-            .collect();
+        let mut message_schedules = Vec::with_capacity(leased_schedules_sql.len());
+        for leased_schedule_sql in leased_schedules_sql {
+            if leased_schedule_sql.reclaimed {
+                warn!(
+                    "Reclaimed transmission {} after its lease expired.",
+                    leased_schedule_sql.id
+                );
+                if let Some(metrics) = &self.metrics {
+                    metrics.count(MetricEvent::LeaseReclaimed);
+                }
+            }
+
+            // A row that cannot be read would fail every poll, so it is quarantined rather than
+            // failing the rest of the batch.
+            let id = leased_schedule_sql.id;
+            match self
+                .open(TransmissionSql::from(leased_schedule_sql))
+                .and_then(|message_schedule_sql| self.decompress(message_schedule_sql))
+                .and_then(|message_schedule_sql| Ok(Transmission::try_from(message_schedule_sql)?))
+            {
+                Ok(message_schedule) => message_schedules.push(message_schedule),
+                Err(err) => {
+                    error!("Quarantining transmission {id}, since it cannot be read: {err}");
+                    if let Some(metrics) = &self.metrics {
+                        metrics.count(MetricEvent::Quarantined);
+                    }
+                    if let Err(err) = self.quarantine(&id).await {
+                        error!("Failed to quarantine transmission {id}: {err}");
+                    }
+                }
+            }
+        }
 
         Ok(message_schedules)
     }
@@ -118,6 +546,8 @@ LIMIT $2;
         self.store_transmission(schedule).await
     }
 
+    // reschedule releases the lease on the transmission, such that it is polled again. Leases that
+    // were reclaimed by another poller are left alone.
     async fn reschedule(
         &self,
         transmission_id: &uuid::Uuid,
@@ -125,34 +555,93 @@ LIMIT $2;
         let _ = sqlx::query!(
             "
 UPDATE transmission
-SET is_locked = true
+SET locked_by = NULL, locked_until = NULL
 WHERE id = $1
-  AND is_locked = false
-  AND (id, inserted_at) = (
-    SELECT id, MAX(inserted_at)
-    FROM transmission
-    WHERE id = $2
-      AND is_locked = false
-    GROUP BY id
-  );
+  AND locked_by = $2;
         ",
             transmission_id,
-            transmission_id,
+            self.lease_owner,
         )
         .execute(&self.conn)
         .await?;
 
         Ok(())
     }
+
+    // renew_lease extends the lease on the transmission, if this poller still holds it.
+    async fn renew_lease(
+        &self,
+        transmission_id: &uuid::Uuid,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let renewed = sqlx::query!(
+            "
+UPDATE transmission
+SET locked_until = now() + make_interval(secs => $3)
+WHERE id = $1
+  AND locked_by = $2;
+        ",
+            transmission_id,
+            self.lease_owner,
+            self.lease_duration.as_secs_f64(),
+        )
+        .execute(&self.conn)
+        .await?;
+
+        Ok(renewed.rows_affected() > 0)
+    }
+
+    async fn next_due(&self) -> Result<Option<DateTime<Utc>>, Box<dyn Error + Send + Sync>> {
+        Ok(next_due(&self.conn, None).await?)
+    }
+}
+
+// next_due returns the earliest time at which a poll may return a transmission other than the
+// excepted one, if any. Both subqueries are served by a partial index.
+async fn next_due<'c, E: PgExecutor<'c>>(
+    executor: E,
+    except: Option<Uuid>,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "
+SELECT LEAST(
+    (
+        SELECT min(next)
+        FROM transmission
+        WHERE next IS NOT NULL
+          AND locked_until IS NULL
+          AND id IS DISTINCT FROM $1
+    ),
+    (
+        SELECT min(GREATEST(next, locked_until))
+        FROM transmission
+        WHERE locked_until IS NOT NULL
+          AND next IS NOT NULL
+          AND id IS DISTINCT FROM $1
+    )
+) AS next_due;
+        ",
+        except,
+    )
+    .fetch_one(executor)
+    .await
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 struct TransmissionSql {
     id: Uuid,
+    // message is encrypted if message_key_id and message_data_key are set, and compressed with
+    // the algorithm named by message_compression if it is set.
     message: String,
+    message_key_id: Option<String>,
+    message_data_key: Option<String>,
+    message_compression: Option<String>,
     schedule: String,
     next: Option<DateTime<Utc>>,
     transmission_count: i32,
+    delivered_destinations: Vec<i32>,
+    failed_destinations: Vec<i32>,
+    // owner is the name of the principal that scheduled the transmission, if any.
+    owner: Option<String>,
 }
 
 impl From<&Transmission> for TransmissionSql {
@@ -160,29 +649,98 @@ impl From<&Transmission> for TransmissionSql {
         TransmissionSql {
             id: schedule.id,
             message: serde_json::to_string(&schedule.message).expect("Failed to serialize message"),
+            message_key_id: None,
+            message_data_key: None,
+            message_compression: None,
             schedule: serde_json::to_string(&schedule.schedule)
                 .expect("Failed to serialize schedule"),
             transmission_count: schedule.transmission_count as i32,
             next: schedule.next,
+            delivered_destinations: schedule
+                .delivered_destinations
+                .iter()
+                .map(|index| *index as i32)
+                .collect(),
+            failed_destinations: schedule
+                .failed_destinations
+                .iter()
+                .map(|index| *index as i32)
+                .collect(),
+            owner: schedule
+                .owner
+                .as_ref()
+                .map(|principal| principal.name.clone()),
         }
     }
 }
 
-impl From<TransmissionSql> for Transmission {
-    fn from(schedule_sql: TransmissionSql) -> Transmission {
-        Transmission {
+// LeasedTransmissionSql is a polled row, along with whether its lease was reclaimed from another
+// poller.
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct LeasedTransmissionSql {
+    id: Uuid,
+    message: String,
+    message_key_id: Option<String>,
+    message_data_key: Option<String>,
+    message_compression: Option<String>,
+    schedule: String,
+    next: Option<DateTime<Utc>>,
+    transmission_count: i32,
+    delivered_destinations: Vec<i32>,
+    failed_destinations: Vec<i32>,
+    owner: Option<String>,
+    reclaimed: bool,
+}
+
+impl From<LeasedTransmissionSql> for TransmissionSql {
+    fn from(leased_sql: LeasedTransmissionSql) -> TransmissionSql {
+        TransmissionSql {
+            id: leased_sql.id,
+            message: leased_sql.message,
+            message_key_id: leased_sql.message_key_id,
+            message_data_key: leased_sql.message_data_key,
+            message_compression: leased_sql.message_compression,
+            schedule: leased_sql.schedule,
+            next: leased_sql.next,
+            transmission_count: leased_sql.transmission_count,
+            delivered_destinations: leased_sql.delivered_destinations,
+            failed_destinations: leased_sql.failed_destinations,
+            owner: leased_sql.owner,
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct StaleMessageSql {
+    id: Uuid,
+    transmission_count: i32,
+    message: String,
+    message_key_id: Option<String>,
+    message_data_key: Option<String>,
+}
+
+impl TryFrom<TransmissionSql> for Transmission {
+    type Error = serde_json::Error;
+
+    fn try_from(schedule_sql: TransmissionSql) -> Result<Transmission, serde_json::Error> {
+        Ok(Transmission {
             id: schedule_sql.id,
-            schedule: match serde_json::from_str(&schedule_sql.schedule) {
-                Ok(schedule) => schedule,
-                Err(err) => panic!("Failed to deserialize schedule: {:?}", err),
-            },
-            message: match serde_json::from_str(&schedule_sql.message) {
-                Ok(message) => message,
-                Err(err) => panic!("Failed to deserialize message: {:?}", err),
-            },
+            schedule: serde_json::from_str(&schedule_sql.schedule)?,
+            message: serde_json::from_str(&schedule_sql.message)?,
             transmission_count: schedule_sql.transmission_count as u32,
             next: schedule_sql.next,
-        }
+            delivered_destinations: schedule_sql
+                .delivered_destinations
+                .iter()
+                .map(|index| *index as u32)
+                .collect(),
+            failed_destinations: schedule_sql
+                .failed_destinations
+                .iter()
+                .map(|index| *index as u32)
+                .collect(),
+            owner: schedule_sql.owner.map(|name| Principal { name }),
+        })
     }
 }
 
@@ -190,19 +748,34 @@ impl From<TransmissionSql> for Transmission {
 mod tests {
     use super::*;
 
+    use mockall::predicate::eq;
+
+    use crate::contract::MockMetrics;
     use crate::postgres;
+    use crate::repository_conformance;
 
-    #[tokio::test]
-    async fn test_store() {
-        let config = postgres::Config {
+    // test_config connects to the postgres of the development environment. Tests may override its
+    // fields with struct update syntax.
+    fn test_config() -> postgres::Config {
+        postgres::Config {
             name: "transmit".into(),
             host: "localhost".into(),
             port: 5432,
             user: "postgres".into(),
             password: "postgres".into(),
-            ssl: false,
-        };
-        let connection = postgres::connect_to_test_database(config)
+            ssl_mode: postgres::SslMode::Disable,
+            ssl_root_cert: None,
+            url: None,
+            pool: postgres::PoolConfig::default(),
+            lease_duration: None,
+            message_keyring: None,
+            message_compression: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store() {
+        let connection = postgres::connect_to_test_database(test_config())
             .await
             .expect("connecting to postgres failed. Is postgres running on port 5432?");
 
@@ -245,6 +818,9 @@ mod tests {
             message: schedules[0].message.clone(),
             next: Some(past),
             transmission_count: 0,
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         }];
 
         for schedule in schedules.iter() {
@@ -280,4 +856,505 @@ mod tests {
             .expect("last poll batch should be ok");
         assert_eq!(polled_schedules_transmitted, vec![]);
     }
+
+    #[tokio::test]
+    async fn test_store_encrypted() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        let old_key = format!("2025-01:{}", STANDARD.encode([1u8; 32]));
+        let new_key = format!("2026-10:{}", STANDARD.encode([2u8; 32]));
+        let old_keyring = Keyring::parse(&old_key).expect("keys should parse");
+        let rotated_keyring =
+            Keyring::parse(&format!("{new_key}\n{old_key}")).expect("keys should parse");
+        let new_keyring = Keyring::parse(&new_key).expect("keys should parse");
+
+        let repository = RepositoryPostgres::new(connection.clone()).with_keyring(old_keyring);
+        repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        repository.clear_all().await.expect("could not clear table");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(past)),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "confidential payload".into(),
+            )),
+        );
+        repository
+            .store_transmission(&transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        let stored_message = || async {
+            sqlx::query_as::<_, (String, Option<String>)>(
+                "SELECT message, message_key_id FROM transmission WHERE id = $1;",
+            )
+            .bind(transmission.id)
+            .fetch_one(&connection)
+            .await
+            .expect("stored message should be selected")
+        };
+
+        let (message, key_id) = stored_message().await;
+        assert!(!message.contains("ARBITRARY.subject"));
+        assert_eq!(key_id, Some(String::from("2025-01")));
+
+        // Rotate the key, after which the retired key is no longer needed.
+        let rotated_repository =
+            RepositoryPostgres::new(connection.clone()).with_keyring(rotated_keyring);
+        let re_encrypted = rotated_repository
+            .re_encrypt()
+            .await
+            .expect("re-encryption should succeed");
+        assert_eq!(re_encrypted, 1);
+
+        let (_, key_id) = stored_message().await;
+        assert_eq!(key_id, Some(String::from("2026-10")));
+
+        let new_repository = RepositoryPostgres::new(connection.clone()).with_keyring(new_keyring);
+        let polled_schedules = new_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_schedules.len(), 1);
+        assert_eq!(polled_schedules[0].id, transmission.id);
+        assert_eq!(polled_schedules[0].message, transmission.message);
+    }
+
+    #[tokio::test]
+    async fn test_poll_quarantines_unreadable() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        let key = format!("2025-01:{}", STANDARD.encode([1u8; 32]));
+        let keyring = Keyring::parse(&key).expect("keys should parse");
+        let encrypting_repository =
+            RepositoryPostgres::new(connection.clone()).with_keyring(keyring);
+        encrypting_repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        encrypting_repository
+            .clear_all()
+            .await
+            .expect("could not clear table");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let new_transmission = |payload: &str| {
+            Transmission::new(
+                Schedule::Delayed(Delayed::new(past)),
+                Message::NatsEvent(NatsEvent::new(
+                    "ARBITRARY.subject".into(),
+                    payload.to_string().into(),
+                )),
+            )
+        };
+        let encrypted_transmission = new_transmission("encrypted payload");
+        encrypting_repository
+            .store_transmission(&encrypted_transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        // Without the key, the encrypted transmission cannot be read.
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::Quarantined))
+            .times(1)
+            .returning(|_| ());
+        let repository =
+            RepositoryPostgres::new(connection.clone()).with_metrics(Arc::new(metrics));
+        let readable_transmission = new_transmission("readable payload");
+        repository
+            .store_transmission(&readable_transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        let polled_schedules = repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_schedules.len(), 1);
+        assert_eq!(polled_schedules[0].id, readable_transmission.id);
+
+        let (next, locked_by) = sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<String>)>(
+            "SELECT next, locked_by FROM transmission WHERE id = $1;",
+        )
+        .bind(encrypted_transmission.id)
+        .fetch_one(&connection)
+        .await
+        .expect("quarantined transmission should be kept");
+        assert_eq!((next, locked_by), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_store_compressed() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        let repository =
+            RepositoryPostgres::new(connection.clone()).with_compression(compression::Config {
+                algorithm: compression::Algorithm::Zstd,
+                threshold: 1024,
+            });
+        repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        repository.clear_all().await.expect("could not clear table");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let small_transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(past)),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "small payload".into(),
+            )),
+        );
+        let large_transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(past)),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "large payload ".repeat(1000).into(),
+            )),
+        );
+        for transmission in [&small_transmission, &large_transmission] {
+            repository
+                .store_transmission(transmission)
+                .await
+                .expect("store schedule should be ok");
+        }
+
+        let stored_compression = |id: Uuid| {
+            let connection = connection.clone();
+            async move {
+                sqlx::query_as::<_, (Option<String>,)>(
+                    "SELECT message_compression FROM transmission WHERE id = $1;",
+                )
+                .bind(id)
+                .fetch_one(&connection)
+                .await
+                .expect("stored message should be selected")
+                .0
+            }
+        };
+        assert_eq!(stored_compression(small_transmission.id).await, None);
+        assert_eq!(
+            stored_compression(large_transmission.id).await,
+            Some(String::from("zstd"))
+        );
+
+        // Compressed messages remain readable without compression configured.
+        let polled_schedules = RepositoryPostgres::new(connection.clone())
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_schedules.len(), 2);
+        for transmission in [&small_transmission, &large_transmission] {
+            let polled = polled_schedules
+                .iter()
+                .find(|polled| polled.id == transmission.id)
+                .expect("transmission should be polled");
+            assert_eq!(polled.message, transmission.message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_owner() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        let repository = RepositoryPostgres::new(connection);
+        repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        repository.clear_all().await.expect("could not clear table");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let owned_transmission = Transmission {
+            owner: Some(Principal {
+                name: String::from("orders"),
+            }),
+            ..Transmission::new(
+                Schedule::Delayed(Delayed::new(past)),
+                Message::NatsEvent(NatsEvent::new(
+                    "ARBITRARY.subject".into(),
+                    "owned payload".into(),
+                )),
+            )
+        };
+        let anonymous_transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(past)),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "anonymous payload".into(),
+            )),
+        );
+        for transmission in [&owned_transmission, &anonymous_transmission] {
+            repository
+                .store_transmission(transmission)
+                .await
+                .expect("store schedule should be ok");
+        }
+
+        let polled_schedules = repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_schedules.len(), 2);
+        for transmission in [&owned_transmission, &anonymous_transmission] {
+            let polled = polled_schedules
+                .iter()
+                .find(|polled| polled.id == transmission.id)
+                .expect("transmission should be polled");
+            assert_eq!(polled.owner, transmission.owner);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_occurrences() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        let repository = RepositoryPostgres::new(connection.clone());
+        repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        repository.clear_all().await.expect("could not clear table");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let transmission = Transmission::new(
+            Schedule::Interval(Interval::new(
+                past,
+                std::time::Duration::from_millis(10),
+                Iterate::Times(3),
+            )),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "periodic payload".into(),
+            )),
+        );
+        repository
+            .store_transmission(&transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        // Each transmitted occurrence replaces the state of the transmission, and is recorded in
+        // its history.
+        for expected_transmission_count in 0..3 {
+            let polled_schedules = repository
+                .poll_transmissions(now, 100)
+                .await
+                .expect("poll batch should be ok");
+            assert_eq!(polled_schedules.len(), 1);
+            assert_eq!(
+                polled_schedules[0].transmission_count,
+                expected_transmission_count
+            );
+
+            let transmitted = polled_schedules[0]
+                .transmitted()
+                .expect("transmission should transition to transmitted");
+            repository
+                .save(&transmitted)
+                .await
+                .expect("save should be ok");
+        }
+
+        let polled_schedules_transmitted = repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("last poll batch should be ok");
+        assert_eq!(polled_schedules_transmitted, vec![]);
+
+        let (transmission_count,) = sqlx::query_as::<_, (i32,)>(
+            "SELECT transmission_count FROM transmission WHERE id = $1;",
+        )
+        .bind(transmission.id)
+        .fetch_one(&connection)
+        .await
+        .expect("current state should be selected");
+        assert_eq!(transmission_count, 3);
+
+        let occurrences = sqlx::query_as::<_, (i32,)>(
+            "
+SELECT transmission_count
+FROM transmission_occurrence
+WHERE transmission_id = $1
+ORDER BY transmission_count;
+        ",
+        )
+        .bind(transmission.id)
+        .fetch_all(&connection)
+        .await
+        .expect("occurrences should be selected");
+        assert_eq!(occurrences, vec![(0,), (1,), (2,)]);
+    }
+
+    #[tokio::test]
+    async fn test_fence_reclaimed_lease() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        let lease_duration = Duration::from_millis(500);
+        let stalled_repository =
+            RepositoryPostgres::new(connection.clone()).with_lease_duration(lease_duration);
+        let reclaiming_repository =
+            RepositoryPostgres::new(connection).with_lease_duration(lease_duration);
+        reclaiming_repository
+            .migrate()
+            .await
+            .expect("migration should be ok");
+
+        let now = Utc::now();
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - chrono::Duration::milliseconds(100))),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "first payload".into(),
+            )),
+        );
+        reclaiming_repository
+            .store_transmission(&transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        // The stalled poller leases the transmission, but only resumes once its lease expired and
+        // the transmission was reclaimed.
+        let polled_stalled = stalled_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_stalled.len(), 1);
+        tokio::time::sleep(lease_duration).await;
+        let polled_reclaimed = reclaiming_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_reclaimed.len(), 1);
+
+        let renewed = stalled_repository
+            .renew_lease(&transmission.id)
+            .await
+            .expect("renew lease should be ok");
+        assert!(!renewed, "reclaimed lease renewed");
+        let transmitted = polled_stalled[0]
+            .transmitted()
+            .expect("transmission should transition to transmitted");
+        assert!(
+            stalled_repository.save(&transmitted).await.is_err(),
+            "reclaimed transmission saved"
+        );
+
+        assert!(reclaiming_repository
+            .renew_lease(&transmission.id)
+            .await
+            .expect("renew lease should be ok"));
+        reclaiming_repository
+            .save(&transmitted)
+            .await
+            .expect("save should be ok");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_conformance() {
+        repository_conformance::check_conformance(|lease_duration, metrics| async move {
+            let connection = postgres::connect_to_test_database(test_config())
+                .await
+                .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+            let repository = RepositoryPostgres::new(connection)
+                .with_lease_duration(lease_duration)
+                .with_metrics(metrics);
+            repository
+                .migrate()
+                .await
+                .expect("could not run migrations");
+            Arc::new(repository) as Arc<dyn Repository>
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_listen() {
+        let connection = postgres::connect_to_test_database(test_config())
+            .await
+            .expect("connecting to postgres failed. Is postgres running on port 5432?");
+
+        // The repositories belong to different replicas.
+        let listening_repository = RepositoryPostgres::new(connection.clone());
+        listening_repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        let wakeups = Arc::new(Notify::new());
+        listening_repository
+            .listen(wakeups.clone())
+            .await
+            .expect("listen should be ok");
+        let repository = RepositoryPostgres::new(connection);
+
+        let woken = || async {
+            tokio::time::timeout(Duration::from_millis(500), wakeups.notified())
+                .await
+                .is_ok()
+        };
+
+        let now = Utc::now();
+        let later = Transmission::new(
+            Schedule::Delayed(Delayed::new(now + chrono::Duration::hours(1))),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "later payload".into(),
+            )),
+        );
+        repository
+            .store_transmission(&later)
+            .await
+            .expect("store schedule should be ok");
+        assert!(woken().await, "not woken for first transmission");
+
+        let latest = Transmission::new(
+            Schedule::Delayed(Delayed::new(now + chrono::Duration::hours(2))),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "latest payload".into(),
+            )),
+        );
+        repository
+            .store_transmission(&latest)
+            .await
+            .expect("store schedule should be ok");
+        assert!(!woken().await, "woken for transmission due after another");
+
+        let earlier = Transmission::new(
+            Schedule::Delayed(Delayed::new(now + chrono::Duration::minutes(1))),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "earlier payload".into(),
+            )),
+        );
+        repository
+            .store_transmission(&earlier)
+            .await
+            .expect("store schedule should be ok");
+        assert!(woken().await, "not woken for earlier transmission");
+    }
 }
@@ -0,0 +1,525 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use sqlx::sqlite::SqlitePool;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::contract::{Metrics, Repository};
+use crate::model::*;
+use crate::repository_postgres::DEFAULT_LEASE_DURATION;
+
+// RepositorySqlite stores transmissions in a local database file, for single node deployments
+// without a database server. Its schema and leases mirror those of RepositoryPostgres.
+pub struct RepositorySqlite {
+    conn: SqlitePool,
+    // lease_owner identifies this poller in the locks it takes on polled transmissions.
+    lease_owner: String,
+    // lease_duration is the time after which the locks of this poller expire, such that the
+    // transmissions of a crashed poller are reclaimed.
+    lease_duration: Duration,
+    // metrics counts the reclaimed leases, if set.
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl RepositorySqlite {
+    pub fn new(conn: SqlitePool) -> RepositorySqlite {
+        let lease_owner = Uuid::new_v4().to_string();
+        info!("constructing new sqlite repository with lease owner {lease_owner}");
+
+        RepositorySqlite {
+            conn,
+            lease_owner,
+            lease_duration: DEFAULT_LEASE_DURATION,
+            metrics: None,
+        }
+    }
+
+    pub fn with_lease_duration(self, lease_duration: Duration) -> RepositorySqlite {
+        RepositorySqlite {
+            lease_duration,
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<dyn Metrics>) -> RepositorySqlite {
+        RepositorySqlite {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        info!("migrating");
+
+        sqlx::migrate!("./migrations/sqlite")
+            .run(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_all(&self) -> Result<(), Box<dyn Error>> {
+        info!("deleting all transmissions");
+
+        let _ = sqlx::query("DELETE FROM transmission_occurrence;")
+            .execute(&self.conn)
+            .await?;
+        let _ = sqlx::query("DELETE FROM transmission;")
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Repository for RepositorySqlite {
+    // store_transmission stores the current state of the transmission, which releases its lease.
+    // If the transmission moved on to a later occurrence, the occurrence it moved on from is
+    // recorded in its history.
+    async fn store_transmission(
+        &self,
+        schedule: &Transmission,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        info!("storing transmission");
+
+        let schedule_sql = TransmissionSqlite::from(schedule);
+        let now = Utc::now();
+
+        let mut transaction = self.conn.begin().await?;
+
+        let stored = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>, Option<String>)>(
+            "
+SELECT transmission_count, next, locked_by
+FROM transmission
+WHERE id = ?1;
+        ",
+        )
+        .bind(schedule_sql.id)
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        if let Some((stored_transmission_count, stored_next, locked_by)) = stored {
+            // A poller whose lease was reclaimed by another poller must not overwrite its state.
+            if locked_by.is_some_and(|locked_by| locked_by != self.lease_owner) {
+                return Err(format!(
+                    "transmission {} is leased by another poller",
+                    schedule_sql.id
+                )
+                .into());
+            }
+
+            if stored_transmission_count < schedule_sql.transmission_count {
+                let _ = sqlx::query(
+                    "
+INSERT INTO transmission_occurrence (
+    transmission_id, transmission_count, scheduled_at, transmitted_at
+) VALUES (
+    ?1, ?2, ?3, ?4
+)
+ON CONFLICT DO NOTHING;
+        ",
+                )
+                .bind(schedule_sql.id)
+                .bind(stored_transmission_count)
+                .bind(stored_next)
+                .bind(now)
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+
+        // A state is never replaced by an earlier occurrence, e.g. of a poller that lost its lease.
+        let _ = sqlx::query(
+            "
+INSERT INTO transmission (
+    id, message, next, schedule, transmission_count, delivered_destinations, failed_destinations,
+    owner, inserted_at
+) VALUES (
+    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9
+)
+ON CONFLICT (id) DO UPDATE
+SET message = excluded.message,
+    next = excluded.next,
+    schedule = excluded.schedule,
+    transmission_count = excluded.transmission_count,
+    delivered_destinations = excluded.delivered_destinations,
+    failed_destinations = excluded.failed_destinations,
+    owner = excluded.owner,
+    inserted_at = excluded.inserted_at,
+    locked_by = NULL,
+    locked_until = NULL
+WHERE transmission.transmission_count <= excluded.transmission_count;
+        ",
+        )
+        .bind(schedule_sql.id)
+        .bind(&schedule_sql.message)
+        .bind(schedule_sql.next)
+        .bind(&schedule_sql.schedule)
+        .bind(schedule_sql.transmission_count)
+        .bind(&schedule_sql.delivered_destinations)
+        .bind(&schedule_sql.failed_destinations)
+        .bind(&schedule_sql.owner)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    // poll_transmissions leases due transmissions that are not leased, or whose lease expired.
+    async fn poll_transmissions(
+        &self,
+        before: DateTime<Utc>,
+        batch_size: u32,
+    ) -> Result<Vec<Transmission>, Box<dyn Error>> {
+        debug!("polling batch");
+        let now = Utc::now();
+        let locked_until = now + chrono::Duration::from_std(self.lease_duration)?;
+
+        let mut transaction = self.conn.begin().await?;
+
+        let leasable = sqlx::query_as::<_, (Uuid, bool)>(
+            "
+SELECT id, locked_until IS NOT NULL AS reclaimed
+FROM transmission
+WHERE next IS NOT NULL
+  AND next < ?1
+  AND (locked_until IS NULL OR locked_until < ?2)
+ORDER BY next
+LIMIT ?3;
+        ",
+        )
+        .bind(before)
+        .bind(now)
+        .bind(batch_size as i64)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut message_schedules = Vec::with_capacity(leasable.len());
+        let mut reclaimed_ids = vec![];
+        for (id, reclaimed) in leasable {
+            if reclaimed {
+                reclaimed_ids.push(id);
+            }
+
+            let leased_sql = sqlx::query_as::<_, TransmissionSqlite>(
+                "
+UPDATE transmission
+SET locked_by = ?1, locked_until = ?2
+WHERE id = ?3
+RETURNING id, message, next, schedule, transmission_count, delivered_destinations,
+    failed_destinations, owner;
+        ",
+            )
+            .bind(&self.lease_owner)
+            .bind(locked_until)
+            .bind(id)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+            message_schedules.push(Transmission::try_from(leased_sql)?);
+        }
+
+        transaction.commit().await?;
+
+        for id in reclaimed_ids {
+            warn!("Reclaimed transmission {id} after its lease expired.");
+            if let Some(metrics) = &self.metrics {
+                metrics.count(MetricEvent::LeaseReclaimed);
+            }
+        }
+
+        Ok(message_schedules)
+    }
+
+    async fn save(&self, schedule: &Transmission) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.store_transmission(schedule).await
+    }
+
+    // reschedule releases the lease on the transmission, such that it is polled again. Leases that
+    // were reclaimed by another poller are left alone.
+    async fn reschedule(
+        &self,
+        transmission_id: &uuid::Uuid,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let _ = sqlx::query(
+            "
+UPDATE transmission
+SET locked_by = NULL, locked_until = NULL
+WHERE id = ?1
+  AND locked_by = ?2;
+        ",
+        )
+        .bind(transmission_id)
+        .bind(&self.lease_owner)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    // renew_lease extends the lease on the transmission, if this poller still holds it.
+    async fn renew_lease(
+        &self,
+        transmission_id: &uuid::Uuid,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let locked_until = Utc::now() + chrono::Duration::from_std(self.lease_duration)?;
+
+        let renewed = sqlx::query(
+            "
+UPDATE transmission
+SET locked_until = ?1
+WHERE id = ?2
+  AND locked_by = ?3;
+        ",
+        )
+        .bind(locked_until)
+        .bind(transmission_id)
+        .bind(&self.lease_owner)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(renewed.rows_affected() > 0)
+    }
+
+    async fn next_due(&self) -> Result<Option<DateTime<Utc>>, Box<dyn Error + Send + Sync>> {
+        let next_due = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "
+SELECT min(CASE WHEN locked_until IS NULL THEN next ELSE max(next, locked_until) END)
+FROM transmission
+WHERE next IS NOT NULL;
+        ",
+        )
+        .fetch_one(&self.conn)
+        .await?;
+
+        Ok(next_due)
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct TransmissionSqlite {
+    id: Uuid,
+    message: String,
+    schedule: String,
+    next: Option<DateTime<Utc>>,
+    transmission_count: i64,
+    delivered_destinations: Json<Vec<u32>>,
+    failed_destinations: Json<Vec<u32>>,
+    // owner is the name of the principal that scheduled the transmission, if any.
+    owner: Option<String>,
+}
+
+impl From<&Transmission> for TransmissionSqlite {
+    fn from(schedule: &Transmission) -> TransmissionSqlite {
+        TransmissionSqlite {
+            id: schedule.id,
+            message: serde_json::to_string(&schedule.message).expect("Failed to serialize message"),
+            schedule: serde_json::to_string(&schedule.schedule)
+                .expect("Failed to serialize schedule"),
+            next: schedule.next,
+            transmission_count: schedule.transmission_count as i64,
+            delivered_destinations: Json(schedule.delivered_destinations.clone()),
+            failed_destinations: Json(schedule.failed_destinations.clone()),
+            owner: schedule
+                .owner
+                .as_ref()
+                .map(|principal| principal.name.clone()),
+        }
+    }
+}
+
+impl TryFrom<TransmissionSqlite> for Transmission {
+    type Error = serde_json::Error;
+
+    fn try_from(schedule_sql: TransmissionSqlite) -> Result<Transmission, serde_json::Error> {
+        Ok(Transmission {
+            id: schedule_sql.id,
+            schedule: serde_json::from_str(&schedule_sql.schedule)?,
+            message: serde_json::from_str(&schedule_sql.message)?,
+            transmission_count: schedule_sql.transmission_count as u32,
+            next: schedule_sql.next,
+            delivered_destinations: schedule_sql.delivered_destinations.0,
+            failed_destinations: schedule_sql.failed_destinations.0,
+            owner: schedule_sql.owner.map(|name| Principal { name }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::repository_conformance;
+    use crate::sqlite;
+
+    async fn repository() -> (RepositorySqlite, SqlitePool) {
+        let connection = sqlite::connect_to_test_database()
+            .await
+            .expect("opening sqlite database failed");
+
+        let repository = RepositorySqlite::new(connection.clone());
+        repository
+            .migrate()
+            .await
+            .expect("could not run migrations");
+        repository.clear_all().await.expect("could not clear table");
+
+        (repository, connection)
+    }
+
+    #[tokio::test]
+    async fn test_store_occurrences() {
+        let (repository, connection) = repository().await;
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let transmission = Transmission::new(
+            Schedule::Interval(Interval::new(
+                past,
+                std::time::Duration::from_millis(10),
+                Iterate::Times(3),
+            )),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "periodic payload".into(),
+            )),
+        );
+        repository
+            .store_transmission(&transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        // Each transmitted occurrence replaces the state of the transmission, and is recorded in
+        // its history.
+        for expected_transmission_count in 0..3 {
+            let polled_schedules = repository
+                .poll_transmissions(now, 100)
+                .await
+                .expect("poll batch should be ok");
+            assert_eq!(polled_schedules.len(), 1);
+            assert_eq!(
+                polled_schedules[0].transmission_count,
+                expected_transmission_count
+            );
+
+            let transmitted = polled_schedules[0]
+                .transmitted()
+                .expect("transmission should transition to transmitted");
+            repository
+                .save(&transmitted)
+                .await
+                .expect("save should be ok");
+        }
+
+        let polled_schedules_transmitted = repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("last poll batch should be ok");
+        assert_eq!(polled_schedules_transmitted, vec![]);
+
+        let (transmission_count,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT transmission_count FROM transmission WHERE id = ?1;",
+        )
+        .bind(transmission.id)
+        .fetch_one(&connection)
+        .await
+        .expect("current state should be selected");
+        assert_eq!(transmission_count, 3);
+
+        let occurrences = sqlx::query_as::<_, (i64,)>(
+            "
+SELECT transmission_count
+FROM transmission_occurrence
+WHERE transmission_id = ?1
+ORDER BY transmission_count;
+        ",
+        )
+        .bind(transmission.id)
+        .fetch_all(&connection)
+        .await
+        .expect("occurrences should be selected");
+        assert_eq!(occurrences, vec![(0,), (1,), (2,)]);
+    }
+
+    #[tokio::test]
+    async fn test_fence_reclaimed_lease() {
+        let (_, connection) = repository().await;
+
+        let lease_duration = Duration::from_millis(500);
+        let stalled_repository =
+            RepositorySqlite::new(connection.clone()).with_lease_duration(lease_duration);
+        let reclaiming_repository =
+            RepositorySqlite::new(connection).with_lease_duration(lease_duration);
+
+        let now = Utc::now();
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - chrono::Duration::milliseconds(100))),
+            Message::NatsEvent(NatsEvent::new(
+                "ARBITRARY.subject".into(),
+                "first payload".into(),
+            )),
+        );
+        reclaiming_repository
+            .store_transmission(&transmission)
+            .await
+            .expect("store schedule should be ok");
+
+        // The stalled poller leases the transmission, but only resumes once its lease expired and
+        // the transmission was reclaimed.
+        let polled_stalled = stalled_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_stalled.len(), 1);
+        tokio::time::sleep(lease_duration).await;
+        let polled_reclaimed = reclaiming_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_reclaimed.len(), 1);
+
+        let renewed = stalled_repository
+            .renew_lease(&transmission.id)
+            .await
+            .expect("renew lease should be ok");
+        assert!(!renewed, "reclaimed lease renewed");
+        let transmitted = polled_stalled[0]
+            .transmitted()
+            .expect("transmission should transition to transmitted");
+        assert!(
+            stalled_repository.save(&transmitted).await.is_err(),
+            "reclaimed transmission saved"
+        );
+
+        assert!(reclaiming_repository
+            .renew_lease(&transmission.id)
+            .await
+            .expect("renew lease should be ok"));
+        reclaiming_repository
+            .save(&transmitted)
+            .await
+            .expect("save should be ok");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_conformance() {
+        repository_conformance::check_conformance(|lease_duration, metrics| async move {
+            let (repository, _) = repository().await;
+            Arc::new(
+                repository
+                    .with_lease_duration(lease_duration)
+                    .with_metrics(metrics),
+            ) as Arc<dyn Repository>
+        })
+        .await;
+    }
+}
@@ -0,0 +1,620 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::contract::{Metrics, Repository};
+use crate::model::*;
+use crate::repository_postgres::DEFAULT_LEASE_DURATION;
+use crate::wal::{Config, FsyncPolicy};
+
+const LOG_FILE: &str = "transmit.log";
+const SNAPSHOT_FILE: &str = "transmit.snapshot";
+const SNAPSHOT_TEMP_FILE: &str = "transmit.snapshot.tmp";
+
+// RepositoryWal persists transmissions without a database, by appending each stored state to a log
+// file before it is applied to an in-memory index. The state is recovered by replaying the log on
+// top of the latest snapshot, which is written periodically to bound the size of the log. The
+// directory must not be shared by multiple processes.
+pub struct RepositoryWal {
+    state: Arc<Mutex<State>>,
+    // lease_duration is the time after which the leases of polled transmissions expire, such that
+    // the transmissions of a stalled poller are reclaimed. Leases are not persisted, so all
+    // transmissions are available again after a restart.
+    lease_duration: Duration,
+    // metrics counts the reclaimed leases, if set.
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+struct State {
+    directory: PathBuf,
+    log: File,
+    fsync: FsyncPolicy,
+    // unsynced is set when records were written since the last sync.
+    unsynced: bool,
+    snapshot_threshold: u64,
+    // sequence is the number of the last record written, which is stored with a snapshot, such
+    // that the records it includes are not replayed on top of it.
+    sequence: u64,
+    records_since_snapshot: u64,
+    transmissions: HashMap<Uuid, Transmission>,
+    // schedule orders the transmissions that are due at some point by their next occurrence.
+    schedule: BTreeSet<(DateTime<Utc>, Uuid)>,
+    // leases contains the expiry of the lease on each polled transmission.
+    leases: HashMap<Uuid, DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    sequence: u64,
+    transmission: Transmission,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    sequence: u64,
+    transmissions: Vec<Transmission>,
+}
+
+impl RepositoryWal {
+    // open recovers the state from the files in the configured directory.
+    pub async fn open(config: Config) -> Result<RepositoryWal, Box<dyn Error>> {
+        let directory = PathBuf::from(&config.directory);
+        tokio::fs::create_dir_all(&directory).await?;
+
+        let mut state = State {
+            log: open_log(&directory)
+                .await
+                .map_err(|err| err as Box<dyn Error>)?,
+            directory,
+            fsync: config.fsync,
+            unsynced: false,
+            snapshot_threshold: config.snapshot_threshold,
+            sequence: 0,
+            records_since_snapshot: 0,
+            transmissions: HashMap::new(),
+            schedule: BTreeSet::new(),
+            leases: HashMap::new(),
+        };
+        state.recover().await.map_err(|err| err as Box<dyn Error>)?;
+
+        info!(
+            "Recovered {} transmissions from {}.",
+            state.transmissions.len(),
+            config.directory
+        );
+
+        let state = Arc::new(Mutex::new(state));
+        if let FsyncPolicy::Interval(interval) = config.fsync {
+            tokio::spawn(sync_periodically(Arc::downgrade(&state), interval));
+        }
+
+        Ok(RepositoryWal {
+            state,
+            lease_duration: DEFAULT_LEASE_DURATION,
+            metrics: None,
+        })
+    }
+
+    pub fn with_lease_duration(self, lease_duration: Duration) -> RepositoryWal {
+        RepositoryWal {
+            lease_duration,
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<dyn Metrics>) -> RepositoryWal {
+        RepositoryWal {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    pub async fn clear_all(&self) -> Result<(), Box<dyn Error>> {
+        info!("deleting all transmissions");
+
+        let mut state = self.state.lock().await;
+        state.transmissions.clear();
+        state.schedule.clear();
+        state.leases.clear();
+
+        state.snapshot().await.map_err(|err| err as Box<dyn Error>)
+    }
+}
+
+impl State {
+    async fn recover(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let snapshot_path = self.directory.join(SNAPSHOT_FILE);
+        if tokio::fs::try_exists(&snapshot_path).await? {
+            let snapshot: Snapshot =
+                serde_json::from_slice(&tokio::fs::read(&snapshot_path).await?)
+                    .map_err(|err| format!("parsing {}: {err}", snapshot_path.display()))?;
+
+            self.sequence = snapshot.sequence;
+            for transmission in snapshot.transmissions {
+                self.apply(transmission);
+            }
+        }
+
+        let log_path = self.directory.join(LOG_FILE);
+        let contents = tokio::fs::read(&log_path).await?;
+
+        let mut offset = 0;
+        while let Some(length) = contents[offset..].iter().position(|byte| *byte == b'\n') {
+            let record: Record = serde_json::from_slice(&contents[offset..offset + length])
+                .map_err(|err| {
+                    format!(
+                        "parsing record at byte {offset} of {}: {err}",
+                        log_path.display()
+                    )
+                })?;
+            offset += length + 1;
+
+            // Records of a log that was not truncated after its snapshot are included already.
+            if record.sequence <= self.sequence {
+                continue;
+            }
+
+            self.sequence = record.sequence;
+            self.records_since_snapshot += 1;
+            self.apply(record.transmission);
+        }
+
+        // A record without a line ending was being written when the process stopped, so it was
+        // never acknowledged.
+        if offset < contents.len() {
+            warn!(
+                "Discarding incomplete record at byte {offset} of {}.",
+                log_path.display()
+            );
+            self.log.set_len(offset as u64).await?;
+            self.log.sync_all().await?;
+        }
+
+        Ok(())
+    }
+
+    // apply replaces the state of the transmission, which releases its lease. A state is never
+    // replaced by an earlier occurrence, e.g. of a poller that lost its lease.
+    fn apply(&mut self, transmission: Transmission) {
+        if let Some(stored) = self.transmissions.get(&transmission.id) {
+            if stored.transmission_count > transmission.transmission_count {
+                return;
+            }
+            if let Some(next) = stored.next {
+                self.schedule.remove(&(next, stored.id));
+            }
+        }
+
+        if let Some(next) = transmission.next {
+            self.schedule.insert((next, transmission.id));
+        }
+        self.leases.remove(&transmission.id);
+        self.transmissions.insert(transmission.id, transmission);
+    }
+
+    // append writes the state of the transmission to the log, and applies it once it is written.
+    async fn append(
+        &mut self,
+        transmission: &Transmission,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let sequence = self.sequence + 1;
+        let mut line = serde_json::to_vec(&Record {
+            sequence,
+            transmission: transmission.clone(),
+        })?;
+        line.push(b'\n');
+
+        // A partially written record would precede the records appended after it, so the log is
+        // truncated to its length before the write when the record is not acknowledged.
+        let length = self.log.metadata().await?.len();
+        if let Err(err) = self.write(&line).await {
+            if let Err(truncate_err) = self.log.set_len(length).await {
+                error!(
+                    "failed to truncate log after failed write: {:?}",
+                    truncate_err
+                );
+            }
+            return Err(err);
+        }
+
+        self.sequence = sequence;
+        self.records_since_snapshot += 1;
+        self.apply(transmission.clone());
+
+        // The record is durable in the log, so a failed snapshot is retried on the next append.
+        if self.records_since_snapshot >= self.snapshot_threshold {
+            if let Err(err) = self.snapshot().await {
+                error!("failed to snapshot state: {:?}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    // write writes the line to the log, and syncs it according to the fsync policy.
+    async fn write(&mut self, line: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.log.write_all(line).await?;
+        self.log.flush().await?;
+        match self.fsync {
+            FsyncPolicy::Always => self.log.sync_data().await?,
+            FsyncPolicy::Interval(_) | FsyncPolicy::Never => self.unsynced = true,
+        }
+
+        Ok(())
+    }
+
+    // snapshot writes the state to the snapshot file and truncates the log. The snapshot replaces
+    // the previous one atomically, such that a crash leaves either of them intact.
+    async fn snapshot(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        debug!("snapshotting {} transmissions", self.transmissions.len());
+
+        let snapshot = serde_json::to_vec(&Snapshot {
+            sequence: self.sequence,
+            transmissions: self.transmissions.values().cloned().collect(),
+        })?;
+
+        let temp_path = self.directory.join(SNAPSHOT_TEMP_FILE);
+        let mut temp_file = File::create(&temp_path).await?;
+        temp_file.write_all(&snapshot).await?;
+        temp_file.sync_all().await?;
+        tokio::fs::rename(&temp_path, self.directory.join(SNAPSHOT_FILE)).await?;
+        sync_directory(&self.directory).await?;
+
+        self.log.set_len(0).await?;
+        self.log.sync_all().await?;
+        self.unsynced = false;
+        self.records_since_snapshot = 0;
+
+        Ok(())
+    }
+}
+
+async fn open_log(directory: &Path) -> Result<File, Box<dyn Error + Send + Sync>> {
+    let log = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(directory.join(LOG_FILE))
+        .await?;
+
+    Ok(log)
+}
+
+// sync_directory syncs the entries of the directory, such that a renamed file survives a power
+// loss.
+async fn sync_directory(directory: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    File::open(directory).await?.sync_all().await?;
+
+    Ok(())
+}
+
+// sync_periodically syncs the log at each interval if records were written since, until the
+// repository is dropped.
+async fn sync_periodically(state: Weak<Mutex<State>>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let mut state = state.lock().await;
+        if !state.unsynced {
+            continue;
+        }
+
+        match state.log.sync_data().await {
+            Ok(()) => state.unsynced = false,
+            Err(err) => error!("Failed to sync log: {err}"),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for RepositoryWal {
+    async fn store_transmission(
+        &self,
+        schedule: &Transmission,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        info!("storing transmission");
+
+        self.state.lock().await.append(schedule).await
+    }
+
+    // poll_transmissions leases due transmissions that are not leased, or whose lease expired.
+    async fn poll_transmissions(
+        &self,
+        before: DateTime<Utc>,
+        batch_size: u32,
+    ) -> Result<Vec<Transmission>, Box<dyn Error>> {
+        debug!("polling batch");
+        let now = Utc::now();
+        let leased_until = now + chrono::Duration::from_std(self.lease_duration)?;
+
+        let mut state = self.state.lock().await;
+        let mut leasable = vec![];
+        let mut reclaimed_ids = vec![];
+        for (next, id) in &state.schedule {
+            if leasable.len() >= batch_size as usize || *next >= before {
+                break;
+            }
+
+            match state.leases.get(id) {
+                Some(lease_expiry) if *lease_expiry >= now => continue,
+                Some(_) => reclaimed_ids.push(*id),
+                None => (),
+            }
+            leasable.push(*id);
+        }
+
+        let mut message_schedules = Vec::with_capacity(leasable.len());
+        for id in leasable {
+            state.leases.insert(id, leased_until);
+            message_schedules.push(state.transmissions[&id].clone());
+        }
+        drop(state);
+
+        for id in reclaimed_ids {
+            warn!("Reclaimed transmission {id} after its lease expired.");
+            if let Some(metrics) = &self.metrics {
+                metrics.count(MetricEvent::LeaseReclaimed);
+            }
+        }
+
+        Ok(message_schedules)
+    }
+
+    async fn save(&self, schedule: &Transmission) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.store_transmission(schedule).await
+    }
+
+    // reschedule releases the lease on the transmission, such that it is polled again.
+    async fn reschedule(
+        &self,
+        transmission_id: &uuid::Uuid,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.state.lock().await.leases.remove(transmission_id);
+
+        Ok(())
+    }
+
+    // renew_lease extends the lease on the transmission, if it is leased. Leases are not persisted,
+    // and all pollers of the log share this process, so any lease is held by them.
+    async fn renew_lease(
+        &self,
+        transmission_id: &uuid::Uuid,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let leased_until = Utc::now() + chrono::Duration::from_std(self.lease_duration)?;
+
+        match self.state.lock().await.leases.get_mut(transmission_id) {
+            Some(lease_expiry) => {
+                *lease_expiry = leased_until;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // next_due scans the schedule only until the next occurrence exceeds the earliest due time
+    // found, since a transmission is never due before its next occurrence.
+    async fn next_due(&self) -> Result<Option<DateTime<Utc>>, Box<dyn Error + Send + Sync>> {
+        let state = self.state.lock().await;
+
+        let mut next_due: Option<DateTime<Utc>> = None;
+        for (next, id) in &state.schedule {
+            if next_due.is_some_and(|next_due| *next >= next_due) {
+                break;
+            }
+
+            let due = state
+                .leases
+                .get(id)
+                .map_or(*next, |lease_expiry| (*lease_expiry).max(*next));
+            next_due = Some(next_due.map_or(due, |next_due| next_due.min(due)));
+        }
+
+        Ok(next_due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::repository_conformance::{self, delayed_transmission};
+
+    fn config() -> Config {
+        Config {
+            directory: std::env::temp_dir()
+                .join(format!("transmit_wal_{}", Uuid::new_v4().as_simple()))
+                .to_string_lossy()
+                .to_string(),
+            fsync: FsyncPolicy::Always,
+            snapshot_threshold: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover() {
+        let config = config();
+        let repository = RepositoryWal::open(config.clone())
+            .await
+            .expect("repository should open");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let pending = delayed_transmission(past, "pending payload");
+        let transmitted = delayed_transmission(past, "transmitted payload");
+        for transmission in [&pending, &transmitted] {
+            repository
+                .store_transmission(transmission)
+                .await
+                .expect("store schedule should be ok");
+        }
+        repository
+            .save(
+                &transmitted
+                    .transmitted()
+                    .expect("schedule should transition to transmitted"),
+            )
+            .await
+            .expect("save should be ok");
+
+        // The process crashes while the pending transmission is leased.
+        let polled_schedules = repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(polled_schedules, vec![pending.clone()]);
+        drop(repository);
+
+        let recovered_repository = RepositoryWal::open(config)
+            .await
+            .expect("repository should recover");
+        let recovered_schedules = recovered_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(recovered_schedules, vec![pending]);
+    }
+
+    #[tokio::test]
+    async fn test_recover_incomplete_record() {
+        let config = config();
+        let repository = RepositoryWal::open(config.clone())
+            .await
+            .expect("repository should open");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let stored = delayed_transmission(past, "stored payload");
+        repository
+            .store_transmission(&stored)
+            .await
+            .expect("store schedule should be ok");
+        drop(repository);
+
+        // The process crashed while it wrote the next record.
+        let log_path = PathBuf::from(&config.directory).join(LOG_FILE);
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .await
+            .expect("log should open");
+        log.write_all(br#"{"sequence":2,"transmission":{"id":"#)
+            .await
+            .expect("incomplete record should be written");
+        drop(log);
+
+        let recovered_repository = RepositoryWal::open(config.clone())
+            .await
+            .expect("repository should recover");
+        let later = delayed_transmission(past, "later payload");
+        recovered_repository
+            .store_transmission(&later)
+            .await
+            .expect("store schedule should be ok");
+        drop(recovered_repository);
+
+        let recovered_repository = RepositoryWal::open(config)
+            .await
+            .expect("repository should recover");
+        let recovered_schedules = recovered_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(recovered_schedules.len(), 2);
+        for transmission in [&stored, &later] {
+            assert!(recovered_schedules.contains(transmission));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recover_corrupt_record() {
+        let config = config();
+        let repository = RepositoryWal::open(config.clone())
+            .await
+            .expect("repository should open");
+        let past = Utc::now() - chrono::Duration::milliseconds(100);
+        repository
+            .store_transmission(&delayed_transmission(past, "stored payload"))
+            .await
+            .expect("store schedule should be ok");
+        drop(repository);
+
+        let log_path = PathBuf::from(&config.directory).join(LOG_FILE);
+        let log = tokio::fs::read(&log_path).await.expect("log should read");
+        tokio::fs::write(&log_path, [b"not a record\n".as_slice(), &log].concat())
+            .await
+            .expect("log should be written");
+
+        assert!(RepositoryWal::open(config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let config = Config {
+            snapshot_threshold: 2,
+            ..config()
+        };
+        let repository = RepositoryWal::open(config.clone())
+            .await
+            .expect("repository should open");
+
+        let now = Utc::now();
+        let past = now - chrono::Duration::milliseconds(100);
+        let transmissions: Vec<Transmission> = (0..5)
+            .map(|index| delayed_transmission(past, &format!("payload {index}")))
+            .collect();
+        for transmission in transmissions.iter() {
+            repository
+                .store_transmission(transmission)
+                .await
+                .expect("store schedule should be ok");
+        }
+        drop(repository);
+
+        // Only the record after the last snapshot remains in the log.
+        let log = tokio::fs::read_to_string(PathBuf::from(&config.directory).join(LOG_FILE))
+            .await
+            .expect("log should read");
+        assert_eq!(log.lines().count(), 1);
+
+        let recovered_repository = RepositoryWal::open(config)
+            .await
+            .expect("repository should recover");
+        let recovered_schedules = recovered_repository
+            .poll_transmissions(now, 100)
+            .await
+            .expect("poll batch should be ok");
+        assert_eq!(recovered_schedules.len(), transmissions.len());
+        for transmission in transmissions.iter() {
+            assert!(recovered_schedules.contains(transmission));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_conformance() {
+        repository_conformance::check_conformance(|lease_duration, metrics| async move {
+            Arc::new(
+                RepositoryWal::open(config())
+                    .await
+                    .expect("repository should open")
+                    .with_lease_duration(lease_duration)
+                    .with_metrics(metrics),
+            ) as Arc<dyn Repository>
+        })
+        .await;
+    }
+}
@@ -1,25 +1,47 @@
+use std::collections::HashSet;
 use std::error::Error;
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::time;
 
 use async_trait::async_trait;
 use chrono::prelude::*;
+use lettre::message::Mailbox;
 use log::{error, info, trace, warn};
 #[cfg(test)]
 use mockall::predicate::*;
+use tokio::sync::Notify;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue};
+use tonic::transport::Uri;
 use uuid::Uuid;
 
 use crate::contract::{Metrics, Now, Repository, Scheduler, Transmitter};
-use crate::model::{Message, MetricEvent, Schedule, ScheduleError, Transmission};
+use crate::model::{
+    FanOut, Message, MetricEvent, Occurrence, PayloadFormat, PermanentTransmitError, Principal,
+    Schedule, ScheduleError, Transmission, Transport,
+};
+use crate::nats::SubjectAcl;
+use crate::redis::hash_tag;
+use crate::repository_postgres::DEFAULT_LEASE_DURATION;
+use crate::schema::SchemaRegistry;
+use crate::template;
 
 static BATCH_SIZE: u32 = 100;
 static MAX_DELAYED_AGE: time::Duration = time::Duration::from_secs(1);
 static MAX_NATS_SUBJECT_LENGTH: u32 = 256;
+static MAX_MQTT_TOPIC_LENGTH: u32 = 65535;
+static MAX_AMQP_SHORT_STRING_LENGTH: u32 = 255;
+static MAX_KAFKA_TOPIC_LENGTH: u32 = 249;
+// DEFAULT_MAX_PAYLOAD_SIZE matches the default max payload of NATS, which is the smallest of the
+// supported brokers.
+pub static DEFAULT_MAX_PAYLOAD_SIZE: usize = 1024 * 1024;
 
 #[derive(Clone)]
 pub struct TransmissionScheduler {
-    // clock_cycle_interval is the duration between each transmission batch.
+    // clock_cycle_interval is the minimum duration between each transmission batch, which bounds
+    // the load on the repository.
     clock_cycle_interval: time::Duration,
     // repository keeps the program stateless, by providing a storage interface to store and
     // retrieve transmissions.
@@ -30,18 +52,49 @@ pub struct TransmissionScheduler {
     now: Arc<dyn Now>,
     // metrics measures events of interest.
     metrics: Arc<dyn Metrics>,
+    // lease_duration is the time after which the repository lets other pollers reclaim polled
+    // transmissions, so leases are renewed well within it while they are transmitted.
+    lease_duration: time::Duration,
+    // transports contains the transports that messages may be scheduled for, or None if messages
+    // of any transport can be transmitted, e.g. to a sink.
+    transports: Option<HashSet<Transport>>,
+    // exec_allowlist contains the commands that Exec messages may run.
+    exec_allowlist: Vec<String>,
+    // max_payload_size is the size in bytes above which messages are rejected when scheduled.
+    max_payload_size: usize,
+    // schemas contains the JSON Schemas that payloads are validated against when scheduled.
+    schemas: Arc<SchemaRegistry>,
+    // subject_acl restricts the subjects that callers may schedule NatsEvent messages onto.
+    subject_acl: SubjectAcl,
+    // wakeups interrupts the sleep between batches when a transmission is scheduled that is due
+    // earlier than the scheduler wakes up.
+    wakeups: Arc<Notify>,
+    // wake_up_at is the time the scheduler sleeps until, or None while it is processing a batch.
+    wake_up_at: Arc<Mutex<Option<DateTime<Utc>>>>,
 }
 
 #[async_trait]
 impl Scheduler for TransmissionScheduler {
-    async fn schedule(&self, when: Schedule, what: Message) -> Result<Uuid, ScheduleError> {
+    async fn schedule(
+        &self,
+        when: Schedule,
+        what: Message,
+        principal: Option<Principal>,
+    ) -> Result<Uuid, ScheduleError> {
         validate_schedule(self.now.now(), &when, self.clock_cycle_interval)?;
-        validate_message(&what)?;
-
-        let transmission = Transmission::new(when, what);
+        validate_message(&what, self.max_payload_size, &self.schemas)?;
+        validate_transport_configured(&what, self.transports.as_ref())?;
+        validate_exec_allowed(&what, &self.exec_allowlist)?;
+        validate_subject_allowed(&what, principal.as_ref(), &self.subject_acl)?;
+
+        let transmission = Transmission {
+            owner: principal,
+            ..Transmission::new(when, what)
+        };
         match self.repository.store_transmission(&transmission).await {
             Ok(_) => {
                 self.metrics.count(MetricEvent::Scheduled(true));
+                self.wake_up_for(transmission.next);
                 Ok(transmission.id)
             }
             Err(err) => {
@@ -84,38 +137,363 @@ fn validate_schedule(
         }
     }
 }
-fn validate_message(message: &Message) -> Result<(), ScheduleError> {
+fn validate_message(
+    message: &Message,
+    max_payload_size: usize,
+    schemas: &SchemaRegistry,
+) -> Result<(), ScheduleError> {
+    let payload_size = message.payload_size();
+    if payload_size > max_payload_size {
+        return Err(ScheduleError::PayloadTooLarge {
+            size: payload_size,
+            max_size: max_payload_size,
+        });
+    }
+
     match message {
         Message::NatsEvent(event) => {
-            if event.subject.len() as u32 > MAX_NATS_SUBJECT_LENGTH {
+            // A templated subject and payload are validated as they would be rendered.
+            let (subject, payload) = match event.templated {
+                true => {
+                    template::validate_bytes(&event.payload)
+                        .map_err(ScheduleError::InvalidTemplate)?;
+                    let payload = template::render_example(
+                        std::str::from_utf8(&event.payload).unwrap_or_default(),
+                    )
+                    .map_err(ScheduleError::InvalidTemplate)?;
+                    let subject = template::render_subject_example(&event.subject)
+                        .map_err(ScheduleError::InvalidTemplate)?;
+
+                    (subject, payload.into())
+                }
+                false => (event.subject.to_string(), event.payload.clone()),
+            };
+
+            if subject.len() as u32 > MAX_NATS_SUBJECT_LENGTH {
                 return Err(ScheduleError::NatsInvalidSubject);
             }
 
-            if let Some('$') = event.subject.chars().next() {
+            if let Some('$') = subject.chars().next() {
                 return Err(ScheduleError::NatsInvalidSubject);
             }
 
-            if event.subject.contains('\0') {
+            if subject.contains('\0') {
                 return Err(ScheduleError::NatsInvalidSubject);
             }
 
-            if event.subject.contains(' ') {
+            if subject.contains(' ') {
                 return Err(ScheduleError::NatsInvalidSubject);
             }
 
-            if event.subject.contains('>') {
+            if subject.contains('>') {
                 return Err(ScheduleError::NatsInvalidSubject);
             }
 
-            if event.subject.contains('*') {
+            if subject.contains('*') {
                 return Err(ScheduleError::NatsInvalidSubject);
             }
 
+            validate_payload(schemas, &event.format, &payload)
+        }
+        Message::MqttPublish(publish) => {
+            if publish.topic.is_empty() {
+                return Err(ScheduleError::MqttInvalidTopic);
+            }
+
+            if publish.topic.len() as u32 > MAX_MQTT_TOPIC_LENGTH {
+                return Err(ScheduleError::MqttInvalidTopic);
+            }
+
+            // Topics starting with $ are reserved for broker internals, e.g. $SYS.
+            if let Some('$') = publish.topic.chars().next() {
+                return Err(ScheduleError::MqttInvalidTopic);
+            }
+
+            if publish.topic.contains('\0') {
+                return Err(ScheduleError::MqttInvalidTopic);
+            }
+
+            // Wildcards are only allowed in subscriptions, not when publishing.
+            if publish.topic.contains('+') || publish.topic.contains('#') {
+                return Err(ScheduleError::MqttInvalidTopic);
+            }
+
+            validate_payload(schemas, &publish.format, &publish.payload)
+        }
+        Message::AmqpPublish(publish) => {
+            if publish.exchange.len() as u32 > MAX_AMQP_SHORT_STRING_LENGTH {
+                return Err(ScheduleError::AmqpInvalidExchange);
+            }
+
+            // Exchange names are restricted to the characters accepted by RabbitMQ.
+            if !publish
+                .exchange
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+            {
+                return Err(ScheduleError::AmqpInvalidExchange);
+            }
+
+            // The amq. prefix is reserved for the predeclared exchanges of the broker.
+            if publish.exchange.starts_with("amq.") {
+                return Err(ScheduleError::AmqpInvalidExchange);
+            }
+
+            if publish.routing_key.len() as u32 > MAX_AMQP_SHORT_STRING_LENGTH {
+                return Err(ScheduleError::AmqpInvalidRoutingKey);
+            }
+
+            let format = PayloadFormat {
+                content_type: publish
+                    .format
+                    .content_type
+                    .clone()
+                    .or_else(|| publish.properties.content_type.clone()),
+                schema: publish.format.schema.clone(),
+            };
+
+            validate_payload(schemas, &format, &publish.body)
+        }
+        Message::RedisPublish(publish) => {
+            if publish.channel.is_empty() {
+                return Err(ScheduleError::RedisInvalidChannel);
+            }
+
+            validate_payload(schemas, &publish.format, &publish.payload)
+        }
+        Message::RedisStreamAdd(stream_add) => {
+            if stream_add.stream.is_empty() {
+                return Err(ScheduleError::RedisInvalidStream);
+            }
+
+            // The deduplication key of an entry must hash to the slot of the stream, which is not
+            // possible for a stream containing a '}' outside of a hash tag.
+            if stream_add.stream.contains('}') && hash_tag(&stream_add.stream).is_none() {
+                return Err(ScheduleError::RedisInvalidStream);
+            }
+
+            // An entry must consist of at least one field-value pair.
+            if stream_add.fields.is_empty() {
+                return Err(ScheduleError::RedisInvalidStream);
+            }
+
+            Ok(())
+        }
+        Message::KafkaRecord(record) => {
+            if record.topic.is_empty() || record.topic.len() as u32 > MAX_KAFKA_TOPIC_LENGTH {
+                return Err(ScheduleError::KafkaInvalidTopic);
+            }
+
+            if record.topic == "." || record.topic == ".." {
+                return Err(ScheduleError::KafkaInvalidTopic);
+            }
+
+            if !record
+                .topic
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                return Err(ScheduleError::KafkaInvalidTopic);
+            }
+
+            if record.headers.keys().any(|key| key.contains('\0')) {
+                return Err(ScheduleError::KafkaInvalidHeader);
+            }
+
+            validate_payload(schemas, &record.format, &record.value)
+        }
+        Message::GrpcCall(call) => {
+            let endpoint =
+                Uri::from_str(&call.endpoint).map_err(|_| ScheduleError::GrpcInvalidEndpoint)?;
+            if !matches!(endpoint.scheme_str(), Some("http") | Some("https")) {
+                return Err(ScheduleError::GrpcInvalidEndpoint);
+            }
+            if endpoint.host().is_none() {
+                return Err(ScheduleError::GrpcInvalidEndpoint);
+            }
+
+            // The path of a method consists of the qualified service and the method name.
+            match call.method.strip_prefix('/').map(|path| path.split('/')) {
+                Some(mut segments) => {
+                    let service = segments.next().unwrap_or_default();
+                    let method = segments.next().unwrap_or_default();
+                    if service.is_empty() || method.is_empty() || segments.next().is_some() {
+                        return Err(ScheduleError::GrpcInvalidMethod);
+                    }
+                }
+                None => return Err(ScheduleError::GrpcInvalidMethod),
+            }
+            if Uri::from_str(&call.method).is_err() {
+                return Err(ScheduleError::GrpcInvalidMethod);
+            }
+
+            if call.deadline.is_zero() {
+                return Err(ScheduleError::GrpcInvalidDeadline);
+            }
+
+            for (key, value) in &call.metadata {
+                // Reserved headers are set by the transport itself.
+                if key.starts_with("grpc-") || key.ends_with("-bin") {
+                    return Err(ScheduleError::GrpcInvalidMetadata);
+                }
+                if AsciiMetadataKey::from_str(key).is_err() {
+                    return Err(ScheduleError::GrpcInvalidMetadata);
+                }
+                if AsciiMetadataValue::from_str(value).is_err() {
+                    return Err(ScheduleError::GrpcInvalidMetadata);
+                }
+            }
+
+            Ok(())
+        }
+        Message::Exec(exec) => {
+            if exec.command.is_empty() || exec.command.contains('\0') {
+                return Err(ScheduleError::ExecInvalidCommand);
+            }
+
+            if exec
+                .arguments
+                .iter()
+                .any(|argument| argument.contains('\0'))
+            {
+                return Err(ScheduleError::ExecInvalidCommand);
+            }
+
+            for (key, value) in &exec.environment {
+                if key.is_empty() || key.contains('=') || key.contains('\0') || value.contains('\0')
+                {
+                    return Err(ScheduleError::ExecInvalidCommand);
+                }
+            }
+
+            if exec.timeout.is_zero() {
+                return Err(ScheduleError::ExecInvalidCommand);
+            }
+
+            Ok(())
+        }
+        Message::Email(email) => {
+            if Mailbox::from_str(&email.from).is_err() {
+                return Err(ScheduleError::EmailInvalidAddress);
+            }
+
+            if email.to.is_empty() {
+                return Err(ScheduleError::EmailInvalidAddress);
+            }
+
+            if email.to.iter().any(|to| Mailbox::from_str(to).is_err()) {
+                return Err(ScheduleError::EmailInvalidAddress);
+            }
+
+            if email.text_body.is_none() && email.html_body.is_none() {
+                return Err(ScheduleError::EmailMissingBody);
+            }
+
+            Ok(())
+        }
+        Message::FanOut(fan_out) => {
+            if fan_out.destinations.is_empty() {
+                return Err(ScheduleError::FanOutInvalidDestinations);
+            }
+
+            for destination in &fan_out.destinations {
+                if let Message::FanOut(_) = destination {
+                    return Err(ScheduleError::FanOutInvalidDestinations);
+                }
+
+                validate_message(destination, max_payload_size, schemas)?;
+            }
+
             Ok(())
         }
     }
 }
 
+// validate_payload asserts that the payload is well-formed according to its format, such that
+// malformed payloads are rejected when scheduled, rather than failing their consumers later.
+fn validate_payload(
+    schemas: &SchemaRegistry,
+    format: &PayloadFormat,
+    payload: &[u8],
+) -> Result<(), ScheduleError> {
+    schemas
+        .validate(format, payload)
+        .map_err(ScheduleError::InvalidPayload)
+}
+
+// validate_subject_allowed asserts that the caller may schedule NatsEvent messages onto their
+// subject, such that clients cannot publish onto subjects of others, e.g. internal control
+// subjects. A templated subject is checked as it would be rendered.
+fn validate_subject_allowed(
+    message: &Message,
+    principal: Option<&Principal>,
+    subject_acl: &SubjectAcl,
+) -> Result<(), ScheduleError> {
+    match message {
+        Message::NatsEvent(event) => {
+            let subject = match event.templated {
+                true => template::render_subject_example(&event.subject)
+                    .map_err(ScheduleError::InvalidTemplate)?,
+                false => event.subject.to_string(),
+            };
+
+            match subject_acl
+                .is_allowed(&subject, principal.map(|principal| principal.name.as_str()))
+            {
+                true => Ok(()),
+                false => Err(ScheduleError::NatsSubjectNotAllowed),
+            }
+        }
+        Message::FanOut(fan_out) => fan_out.destinations.iter().try_for_each(|destination| {
+            validate_subject_allowed(destination, principal, subject_acl)
+        }),
+        _ => Ok(()),
+    }
+}
+
+// validate_transport_configured asserts that messages are only scheduled for transports that can
+// transmit them, since they would otherwise fail to transmit at every occurrence.
+fn validate_transport_configured(
+    message: &Message,
+    transports: Option<&HashSet<Transport>>,
+) -> Result<(), ScheduleError> {
+    let transports = match transports {
+        Some(transports) => transports,
+        None => return Ok(()),
+    };
+
+    match message {
+        Message::FanOut(fan_out) => fan_out.destinations.iter().try_for_each(|destination| {
+            validate_transport_configured(destination, Some(transports))
+        }),
+        _ => match message.transport() {
+            Some(transport) if !transports.contains(&transport) => {
+                Err(ScheduleError::TransportNotConfigured(transport))
+            }
+            _ => Ok(()),
+        },
+    }
+}
+
+// validate_exec_allowed asserts that Exec messages only run allowlisted commands, such that
+// clients cannot run arbitrary commands on the host.
+fn validate_exec_allowed(
+    message: &Message,
+    exec_allowlist: &[String],
+) -> Result<(), ScheduleError> {
+    match message {
+        Message::Exec(exec) => match exec_allowlist.contains(&exec.command) {
+            true => Ok(()),
+            false => Err(ScheduleError::ExecCommandNotAllowed),
+        },
+        Message::FanOut(fan_out) => fan_out
+            .destinations
+            .iter()
+            .try_for_each(|destination| validate_exec_allowed(destination, exec_allowlist)),
+        _ => Ok(()),
+    }
+}
+
 impl TransmissionScheduler {
     pub fn new(
         clock_cycle_interval: time::Duration,
@@ -130,11 +508,65 @@ impl TransmissionScheduler {
             transmitter,
             now,
             metrics,
+            lease_duration: DEFAULT_LEASE_DURATION,
+            transports: None,
+            exec_allowlist: vec![],
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            schemas: Arc::new(SchemaRegistry::new()),
+            subject_acl: SubjectAcl::default(),
+            wakeups: Arc::new(Notify::new()),
+            wake_up_at: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn with_lease_duration(self, lease_duration: time::Duration) -> TransmissionScheduler {
+        TransmissionScheduler {
+            lease_duration,
+            ..self
+        }
+    }
+
+    pub fn with_transports(self, transports: HashSet<Transport>) -> TransmissionScheduler {
+        TransmissionScheduler {
+            transports: Some(transports),
+            ..self
+        }
+    }
+
+    pub fn with_exec_allowlist(self, exec_allowlist: Vec<String>) -> TransmissionScheduler {
+        TransmissionScheduler {
+            exec_allowlist,
+            ..self
+        }
+    }
+
+    pub fn with_max_payload_size(self, max_payload_size: usize) -> TransmissionScheduler {
+        TransmissionScheduler {
+            max_payload_size,
+            ..self
+        }
+    }
+
+    pub fn with_schemas(self, schemas: Arc<SchemaRegistry>) -> TransmissionScheduler {
+        TransmissionScheduler { schemas, ..self }
+    }
+
+    pub fn with_subject_acl(self, subject_acl: SubjectAcl) -> TransmissionScheduler {
+        TransmissionScheduler {
+            subject_acl,
+            ..self
         }
     }
 
+    // with_wakeups shares wakeups with the repository, such that transmissions scheduled through
+    // other replicas wake up the scheduler.
+    pub fn with_wakeups(self, wakeups: Arc<Notify>) -> TransmissionScheduler {
+        TransmissionScheduler { wakeups, ..self }
+    }
+
     pub async fn run(&self, cancel_token: CancellationToken) -> () {
         loop {
+            let polled_at = Instant::now();
             match self.process_batch().await {
                 Ok(_) => (),
                 Err(err) => error!("error: {:?}", err),
@@ -149,13 +581,58 @@ impl TransmissionScheduler {
                     break;
                 }
 
-                _ = tokio::time::sleep(self.clock_cycle_interval) => {}
+                _ = self.sleep(polled_at) => {}
             }
         }
 
         info!("Application was shut down.");
     }
 
+    // sleep waits until the next transmission is due, or until a transmission is scheduled that is
+    // due earlier, but at least for the clock cycle interval since the previous poll.
+    async fn sleep(&self, polled_at: Instant) {
+        let next_due = match self.repository.next_due().await {
+            Ok(next_due) => next_due,
+            Err(err) => {
+                error!(
+                    "failed to determine when the next transmission is due: {:?}",
+                    err
+                );
+                tokio::time::sleep_until(polled_at + self.clock_cycle_interval).await;
+                return;
+            }
+        };
+        *self.wake_up_at.lock().expect("mutex is poisoned") = next_due;
+
+        match next_due {
+            Some(next_due) => {
+                let until_due = (next_due - self.now.now())
+                    .to_std()
+                    .unwrap_or(time::Duration::ZERO);
+                tokio::select! {
+                    _ = tokio::time::sleep(until_due) => {}
+                    _ = self.wakeups.notified() => {}
+                }
+            }
+            None => self.wakeups.notified().await,
+        }
+        *self.wake_up_at.lock().expect("mutex is poisoned") = None;
+
+        tokio::time::sleep_until(polled_at + self.clock_cycle_interval).await;
+    }
+
+    // wake_up_for wakes up the scheduler if the transmission is due before it wakes up. While a
+    // batch is processed, it is woken up regardless, since the transmission may have been stored
+    // after the repository was asked when the next transmission is due.
+    fn wake_up_for(&self, next: Option<DateTime<Utc>>) {
+        let wake_up_at = *self.wake_up_at.lock().expect("mutex is poisoned");
+        match (next, wake_up_at) {
+            (None, _) => (),
+            (Some(next), Some(wake_up_at)) if next >= wake_up_at => (),
+            (Some(_), _) => self.wakeups.notify_one(),
+        }
+    }
+
     // process_batch retrieves schedules that are overdue, and in scheduled state. It transitions them
     // to doing/queued, transmits them and then transitions them back to scheduled, done or
     // error. Or, errors should have a separate thing. We don't want any errors to meddle with
@@ -189,7 +666,27 @@ impl TransmissionScheduler {
             .collect();
 
         for schedule in &relevant_schedules {
-            match self.transmit(schedule).await {
+            // The lease may have expired while earlier transmissions of the batch were in progress,
+            // in which case another poller may have reclaimed the transmission.
+            match self.repository.renew_lease(&schedule.id).await {
+                Ok(true) => (),
+                Ok(false) => {
+                    warn!(
+                        "Skipping transmission {}, since its lease was reclaimed.",
+                        schedule.id
+                    );
+                    continue;
+                }
+                Err(err) => {
+                    error!(
+                        "failed to renew lease of transmission {}: {:?}",
+                        schedule.id, err
+                    );
+                    continue;
+                }
+            }
+
+            match self.transmit_renewing_lease(schedule, now).await {
                 Ok(_) => {
                     self.metrics.count(MetricEvent::Transmitted(true));
                 }
@@ -203,32 +700,103 @@ impl TransmissionScheduler {
         Ok(())
     }
 
-    async fn transmit(&self, schedule: &Transmission) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let transmission_result = self.transmitter.transmit(schedule.message.clone()).await;
+    // transmit_renewing_lease transmits the schedule, and renews its lease at a third of the lease
+    // duration while the transmission is in progress, such that a slow transmission is not
+    // reclaimed, and transmitted twice, by another poller.
+    async fn transmit_renewing_lease(
+        &self,
+        schedule: &Transmission,
+        now: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let renewal_interval = self.lease_duration / 3;
+        let renewals = async {
+            let mut renewals =
+                tokio::time::interval_at(Instant::now() + renewal_interval, renewal_interval);
+            loop {
+                renewals.tick().await;
+                match self.repository.renew_lease(&schedule.id).await {
+                    Ok(true) => (),
+                    // The save of a reclaimed transmission is rejected by the repository.
+                    Ok(false) => warn!(
+                        "Lease of transmission {} was reclaimed during its transmission.",
+                        schedule.id
+                    ),
+                    Err(err) => error!(
+                        "failed to renew lease of transmission {}: {:?}",
+                        schedule.id, err
+                    ),
+                }
+            }
+        };
 
-        info!("Transmitted message from schedule with id: {}", schedule.id);
+        tokio::select! {
+            result = self.transmit(schedule, now) => result,
+            _ = renewals => unreachable!("lease renewals never finish"),
+        }
+    }
 
-        match transmission_result {
-            Ok(_) => {
-                let transmitted_message = schedule.transmitted();
-                let transmitted_message = match transmitted_message {
-                    Ok(message) => message,
-                    Err(err) => {
-                        warn!("Transmitted message that ought not to have been sent: {err}");
-                        return Err(err);
-                    }
-                };
+    async fn transmit(
+        &self,
+        schedule: &Transmission,
+        now: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let occurrence = Occurrence {
+            transmission_id: schedule.id,
+            transmission_count: schedule.transmission_count,
+            scheduled_at: schedule.next.unwrap_or(now),
+            transmitted_at: now,
+        };
 
-                let state_transition_result = self.repository.save(&transmitted_message).await;
-                match state_transition_result {
-                    Ok(_) => {
-                        self.metrics.count(MetricEvent::ScheduleStateSaved(true));
-                        Ok(())
+        let transmission_result = match template::render_message(&schedule.message, &occurrence) {
+            Err(err) => Err(err.into()),
+            Ok(Message::FanOut(fan_out)) => {
+                match self.transmit_fan_out(schedule, &fan_out, &occurrence).await {
+                    Ok(()) => Ok(()),
+                    // Every remaining destination failed permanently, so the occurrence is skipped.
+                    Err((_, transmission_err))
+                        if transmission_err.is::<PermanentTransmitError>() =>
+                    {
+                        Err(transmission_err)
                     }
-                    Err(err) => {
-                        self.metrics.count(MetricEvent::ScheduleStateSaved(false));
-                        Err(err)
+                    // Progress was made, so store which destinations were settled, such that the
+                    // retry only concerns the remaining destinations.
+                    Err((partially_transmitted, transmission_err))
+                        if partially_transmitted.delivered_destinations.len()
+                            + partially_transmitted.failed_destinations.len()
+                            > schedule.delivered_destinations.len()
+                                + schedule.failed_destinations.len() =>
+                    {
+                        return match self.repository.save(&partially_transmitted).await {
+                            Ok(_) => {
+                                self.metrics.count(MetricEvent::ScheduleStateSaved(true));
+                                Err(transmission_err)
+                            }
+                            Err(err) => {
+                                self.metrics.count(MetricEvent::ScheduleStateSaved(false));
+                                Err(format!("{:?}: {:?}", transmission_err, err).into())
+                            }
+                        };
                     }
+                    Err((_, transmission_err)) => Err(transmission_err),
+                }
+            }
+            Ok(message) => self.transmitter.transmit(message, &occurrence).await,
+        };
+
+        info!("Transmitted message from schedule with id: {}", schedule.id);
+
+        match transmission_result {
+            Ok(_) => self.save_transmitted(schedule).await,
+            // Retrying cannot succeed, so the occurrence is skipped rather than rescheduled.
+            Err(transmission_err) if transmission_err.is::<PermanentTransmitError>() => {
+                warn!(
+                    "Skipping occurrence of schedule with id {} after permanent failure: {}",
+                    schedule.id, transmission_err
+                );
+
+                match self.save_transmitted(schedule).await {
+                    Ok(_) => Err(transmission_err),
+                    Err(err) => Err(format!("{:?}: {:?}", transmission_err, err).into()),
                 }
             }
             Err(transmission_err) => {
@@ -239,23 +807,113 @@ impl TransmissionScheduler {
                     }
                     Err(err) => {
                         self.metrics.count(MetricEvent::Rescheduled(false));
-                        // This requires extra attention, since unrestored non-transmitted
-                        // scheduled messages will remain stuck in a DOING state.
-                        // It should be logged for manual intervention, retried or
-                        // forcibly rescheduled.
+                        // The transmission remains leased by this poller, so it is only
+                        // retried once the lease expires and another poll reclaims it.
                         Err(format!("{:?}: {:?}", transmission_err, err).into())
                     }
                 }
             }
         }
     }
+
+    // save_transmitted transitions the transmission to its next occurrence.
+    async fn save_transmitted(
+        &self,
+        schedule: &Transmission,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let transmitted_message = match schedule.transmitted() {
+            Ok(message) => message,
+            Err(err) => {
+                warn!("Transmitted message that ought not to have been sent: {err}");
+                return Err(err);
+            }
+        };
+
+        match self.repository.save(&transmitted_message).await {
+            Ok(_) => {
+                self.metrics.count(MetricEvent::ScheduleStateSaved(true));
+                Ok(())
+            }
+            Err(err) => {
+                self.metrics.count(MetricEvent::ScheduleStateSaved(false));
+                Err(err)
+            }
+        }
+    }
+
+    // count_destination counts the transmission to a destination of a fan out by its transport,
+    // rather than by the destination itself, which may contain recipients and rendered templates.
+    fn count_destination(&self, destination: &Message, success: bool) {
+        if let Some(transport) = destination.transport() {
+            self.metrics
+                .count(MetricEvent::DestinationTransmitted(transport, success));
+        }
+    }
+
+    // transmit_fan_out transmits the message to each destination that was not yet settled in the
+    // current occurrence. If any destination fails, it returns the transmission with the
+    // destinations settled thus far, along with the first error that may succeed when retried, or
+    // the first permanent error if there is none.
+    async fn transmit_fan_out(
+        &self,
+        schedule: &Transmission,
+        fan_out: &FanOut,
+        occurrence: &Occurrence,
+    ) -> Result<(), (Transmission, Box<dyn Error + Send + Sync>)> {
+        let mut delivered_destinations = schedule.delivered_destinations.clone();
+        let mut failed_destinations = schedule.failed_destinations.clone();
+        let mut first_retryable_err = None;
+        let mut first_permanent_err = None;
+
+        for (index, destination) in fan_out.destinations.iter().enumerate() {
+            let index = index as u32;
+            if schedule.is_settled(index) {
+                continue;
+            }
+
+            match self
+                .transmitter
+                .transmit(destination.clone(), occurrence)
+                .await
+            {
+                Ok(_) => {
+                    self.count_destination(destination, true);
+                    delivered_destinations.push(index);
+                }
+                Err(err) => {
+                    self.count_destination(destination, false);
+                    error!(
+                        "failed to transmit to destination '{}': {:?}",
+                        destination.destination(),
+                        err
+                    );
+                    if err.is::<PermanentTransmitError>() {
+                        failed_destinations.push(index);
+                        first_permanent_err.get_or_insert(err);
+                    } else {
+                        first_retryable_err.get_or_insert(err);
+                    }
+                }
+            }
+        }
+
+        match first_retryable_err.or(first_permanent_err) {
+            None => Ok(()),
+            Some(err) => Err((
+                schedule.partially_transmitted(delivered_destinations, failed_destinations),
+                err,
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::collections::BTreeMap;
     use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use mockall::Sequence;
     use std::time;
@@ -263,6 +921,9 @@ mod tests {
 
     use crate::contract::*;
     use crate::model::*;
+    use crate::nats::ClientSubjectRules;
+    use crate::schema::SchemaError;
+    use crate::template::TemplateError;
 
     const DEFAULT_CLOCK_CYCLE_INTERVAL: time::Duration = time::Duration::from_micros(10);
 
@@ -311,6 +972,7 @@ mod tests {
 
             let schedules = test_case_schedules.clone();
             let mut repository = MockRepository::new();
+            repository.expect_renew_lease().returning(|_| Ok(true));
             repository
                 .expect_poll_transmissions()
                 .times(1)
@@ -371,6 +1033,8 @@ mod tests {
             .returning(move || timestamp_some_time_later);
 
         let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
         repository
             .expect_poll_transmissions()
             .times(2)
@@ -378,7 +1042,10 @@ mod tests {
         repository.expect_save().returning(|_| Ok(())).times(1);
 
         let mut transmitter = MockTransmitter::new();
-        transmitter.expect_transmit().returning(|_| Ok(())).times(1);
+        transmitter
+            .expect_transmit()
+            .returning(|_, _| Ok(()))
+            .times(1);
 
         let mut metrics = MockMetrics::new();
         metrics
@@ -416,6 +1083,7 @@ mod tests {
     async fn test_infinite_interval() {
         let mut transmitter = MockTransmitter::new();
         let mut repository = MockRepository::new();
+        repository.expect_renew_lease().returning(|_| Ok(true));
         let mut metrics = MockMetrics::new();
 
         let just_now = Utc::now() - chrono::Duration::milliseconds(10);
@@ -439,6 +1107,9 @@ mod tests {
             next: Some(just_now + interval),
             transmission_count: 1,
             message: original_schedule.message.clone(),
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         };
         let expected_transmission_1 = Transmission {
             id: original_schedule.id,
@@ -450,6 +1121,9 @@ mod tests {
             next: Some(just_now + interval + interval),
             transmission_count: 2,
             message: original_schedule.message.clone(),
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         };
         let expected_transmission_2 = Transmission {
             id: original_schedule.id,
@@ -461,6 +1135,9 @@ mod tests {
             next: Some(just_now + interval + interval + interval),
             transmission_count: 3,
             message: original_schedule.message.clone(),
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         };
 
         repository
@@ -479,7 +1156,10 @@ mod tests {
             .returning(|_| Ok(()))
             .times(1);
 
-        transmitter.expect_transmit().returning(|_| Ok(())).times(3);
+        transmitter
+            .expect_transmit()
+            .returning(|_, _| Ok(()))
+            .times(3);
 
         metrics
             .expect_count()
@@ -495,11 +1175,15 @@ mod tests {
             Arc::new(metrics),
         );
 
-        let result = scheduler.transmit(&original_schedule).await;
+        let result = scheduler.transmit(&original_schedule, Utc::now()).await;
         assert!(result.is_ok());
-        let result = scheduler.transmit(&expected_transmission_0).await;
+        let result = scheduler
+            .transmit(&expected_transmission_0, Utc::now())
+            .await;
         assert!(result.is_ok());
-        let result = scheduler.transmit(&expected_transmission_1).await;
+        let result = scheduler
+            .transmit(&expected_transmission_1, Utc::now())
+            .await;
         assert!(result.is_ok());
     }
 
@@ -507,6 +1191,7 @@ mod tests {
     async fn test_finite_interval() {
         let mut transmitter = MockTransmitter::new();
         let mut repository = MockRepository::new();
+        repository.expect_renew_lease().returning(|_| Ok(true));
         let mut metrics = MockMetrics::new();
 
         let repetitions = 3;
@@ -532,6 +1217,9 @@ mod tests {
             next: Some(just_now + interval),
             transmission_count: 1,
             message: original_transmission.message.clone(),
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         };
         let expected_transmission_last = Transmission {
             id: original_transmission.id,
@@ -539,6 +1227,9 @@ mod tests {
             next: Some(just_now + interval + interval),
             transmission_count: 2,
             message: original_transmission.message.clone(),
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         };
         let expected_transmission_done = Transmission {
             id: original_transmission.id,
@@ -546,6 +1237,9 @@ mod tests {
             next: None,
             transmission_count: 3,
             message: original_transmission.message.clone(),
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
         };
 
         repository
@@ -564,7 +1258,10 @@ mod tests {
             .returning(|_| Ok(()))
             .times(1);
 
-        transmitter.expect_transmit().returning(|_| Ok(())).times(3);
+        transmitter
+            .expect_transmit()
+            .returning(|_, _| Ok(()))
+            .times(3);
 
         metrics
             .expect_count()
@@ -580,19 +1277,22 @@ mod tests {
             Arc::new(metrics),
         );
 
-        let result = scheduler.transmit(&original_transmission).await;
+        let result = scheduler.transmit(&original_transmission, Utc::now()).await;
         assert!(result.is_ok());
         let result = scheduler
-            .transmit(&expected_transmission_second_to_last)
+            .transmit(&expected_transmission_second_to_last, Utc::now())
             .await;
         assert!(result.is_ok());
-        let result = scheduler.transmit(&expected_transmission_last).await;
+        let result = scheduler
+            .transmit(&expected_transmission_last, Utc::now())
+            .await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
     async fn test_schedule() {
         let mut repository = MockRepository::new();
+        repository.expect_renew_lease().returning(|_| Ok(true));
         repository
             .expect_store_transmission()
             .returning(|_| Ok(()))
@@ -617,25 +1317,31 @@ mod tests {
         let now = Utc::now();
         let schedule = Schedule::Delayed(Delayed::new(now));
 
-        let result = scheduler.schedule(schedule, arbitrary_message()).await;
+        let result = scheduler
+            .schedule(schedule, arbitrary_message(), None)
+            .await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
-    async fn test_schedule_fail() {
+    async fn test_schedule_records_owner() {
+        let owner = Principal {
+            name: String::from("orders"),
+        };
+
         let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        let expected_owner = Some(owner.clone());
         repository
             .expect_store_transmission()
-            .returning(|_| Err("Failed to schedule message".into()))
+            .withf(move |transmission| transmission.owner == expected_owner)
+            .returning(|_| Ok(()))
             .times(1);
 
         let transmitter = MockTransmitter::new();
         let mut metrics = MockMetrics::new();
-        metrics
-            .expect_count()
-            .with(eq(MetricEvent::Scheduled(false)))
-            .returning(|_| ())
-            .times(1);
+        metrics.expect_count().returning(|_| ());
 
         let scheduler = TransmissionScheduler::new(
             DEFAULT_CLOCK_CYCLE_INTERVAL,
@@ -645,12 +1351,170 @@ mod tests {
             Arc::new(metrics),
         );
 
-        let now = Utc::now();
-        let schedule = Schedule::Delayed(Delayed::new(now));
-
-        let result = scheduler.schedule(schedule, arbitrary_message()).await;
-        assert!(result.is_err());
-    }
+        let schedule = Schedule::Delayed(Delayed::new(Utc::now()));
+
+        let result = scheduler
+            .schedule(schedule, arbitrary_message(), Some(owner))
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_fail() {
+        let mut repository = MockRepository::new();
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        repository
+            .expect_store_transmission()
+            .returning(|_| Err("Failed to schedule message".into()))
+            .times(1);
+
+        let transmitter = MockTransmitter::new();
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::Scheduled(false)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let now = Utc::now();
+        let schedule = Schedule::Delayed(Delayed::new(now));
+
+        let result = scheduler
+            .schedule(schedule, arbitrary_message(), None)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_schedule_wakes_up() {
+        struct TestCase {
+            name: String,
+            wake_up_at: Option<DateTime<Utc>>,
+            transmit_after: chrono::Duration,
+            expected_woken: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("due before wake up"),
+                wake_up_at: Some(Utc::now() + chrono::Duration::hours(1)),
+                transmit_after: chrono::Duration::minutes(1),
+                expected_woken: true,
+            },
+            TestCase {
+                name: String::from("due after wake up"),
+                wake_up_at: Some(Utc::now() + chrono::Duration::minutes(1)),
+                transmit_after: chrono::Duration::hours(1),
+                expected_woken: false,
+            },
+            TestCase {
+                name: String::from("processing batch"),
+                wake_up_at: None,
+                transmit_after: chrono::Duration::hours(1),
+                expected_woken: true,
+            },
+        ];
+
+        for test_case in test_cases {
+            let mut repository = MockRepository::new();
+            repository.expect_renew_lease().returning(|_| Ok(true));
+            repository.expect_store_transmission().returning(|_| Ok(()));
+            let mut metrics = MockMetrics::new();
+            metrics.expect_count().returning(|_| ());
+
+            let scheduler = TransmissionScheduler::new(
+                DEFAULT_CLOCK_CYCLE_INTERVAL,
+                Arc::new(repository),
+                Arc::new(MockTransmitter::new()),
+                Arc::new(Utc::now),
+                Arc::new(metrics),
+            );
+            *scheduler.wake_up_at.lock().unwrap() = test_case.wake_up_at;
+
+            let schedule = new_delayed(Utc::now() + test_case.transmit_after);
+            scheduler
+                .schedule(schedule, arbitrary_message(), None)
+                .await
+                .expect("schedule should be ok");
+
+            let woken = tokio::time::timeout(
+                time::Duration::from_millis(10),
+                scheduler.wakeups.notified(),
+            )
+            .await
+            .is_ok();
+            assert_eq!(
+                woken, test_case.expected_woken,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_sleeps_until_next_due() {
+        let now = Utc::now();
+        let polls = Arc::new(AtomicUsize::new(0));
+
+        let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        let counted_polls = polls.clone();
+        repository
+            .expect_poll_transmissions()
+            .returning(move |_, _| {
+                counted_polls.fetch_add(1, Ordering::SeqCst);
+                Ok(vec![])
+            });
+        repository
+            .expect_next_due()
+            .returning(move || Ok(Some(now + chrono::Duration::hours(1))));
+        repository.expect_store_transmission().returning(|_| Ok(()));
+        let mut metrics = MockMetrics::new();
+        metrics.expect_count().returning(|_| ());
+
+        let scheduler = Arc::new(TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(MockTransmitter::new()),
+            Arc::new(move || now),
+            Arc::new(metrics),
+        ));
+
+        let cancel = CancellationToken::new();
+        let running_scheduler = scheduler.clone();
+        let cancel_scheduler = cancel.clone();
+        let running = tokio::spawn(async move { running_scheduler.run(cancel_scheduler).await });
+
+        tokio::time::sleep(time::Duration::from_secs(30 * 60)).await;
+        assert_eq!(polls.load(Ordering::SeqCst), 1, "polled before due");
+
+        tokio::time::sleep(time::Duration::from_secs(31 * 60)).await;
+        assert_eq!(polls.load(Ordering::SeqCst), 2, "not polled when due");
+
+        // A transmission that is due earlier wakes up the scheduler.
+        scheduler
+            .schedule(
+                new_delayed(now + chrono::Duration::minutes(1)),
+                arbitrary_message(),
+                None,
+            )
+            .await
+            .expect("schedule should be ok");
+        tokio::time::sleep(time::Duration::from_secs(1)).await;
+        assert_eq!(polls.load(Ordering::SeqCst), 3, "not polled when woken");
+
+        cancel.cancel();
+        running.await.expect("scheduler should shut down");
+    }
 
     fn arbitrary_message() -> Message {
         Message::NatsEvent(NatsEvent::new(
@@ -750,6 +1614,74 @@ mod tests {
         ))
     }
 
+    fn new_mqtt_message() -> Message {
+        Message::MqttPublish(MqttPublish::new(
+            "topic/arbitrary".into(),
+            "arbitrary payload".into(),
+            QualityOfService::AtLeastOnce,
+            false,
+        ))
+    }
+
+    fn new_amqp_message() -> Message {
+        Message::AmqpPublish(AmqpPublish::new(
+            "exchange".into(),
+            "routing.key".into(),
+            AmqpProperties::default(),
+            "arbitrary body".into(),
+        ))
+    }
+
+    fn new_grpc_call() -> GrpcCall {
+        GrpcCall::new(
+            "http://orders:8080".into(),
+            "/orders.Orders/Create".into(),
+            "arbitrary body".into(),
+            time::Duration::from_secs(1),
+            BTreeMap::from([(String::from("authorization"), String::from("token"))]),
+        )
+    }
+
+    fn new_grpc_message() -> Message {
+        Message::GrpcCall(new_grpc_call())
+    }
+
+    fn new_exec_message() -> Message {
+        Message::Exec(Exec::new(
+            "/usr/bin/backup".into(),
+            vec!["--incremental".into()],
+            BTreeMap::from([(String::from("TARGET"), String::from("/var/backups"))]),
+            time::Duration::from_secs(60),
+        ))
+    }
+
+    fn new_email() -> Email {
+        Email::new(
+            "Transmit <transmit@example.com>".into(),
+            vec!["someone@example.com".into()],
+            "Reminder".into(),
+            Some("Do not forget.".into()),
+            None,
+        )
+    }
+
+    fn new_email_message() -> Message {
+        Message::Email(new_email())
+    }
+
+    fn new_fan_out_message() -> Message {
+        Message::FanOut(FanOut::new(vec![
+            Message::NatsEvent(NatsEvent::new(
+                "FIRST.destination".into(),
+                "arbitrary payload".into(),
+            )),
+            Message::NatsEvent(NatsEvent::new(
+                "SECOND.destination".into(),
+                "arbitrary payload".into(),
+            )),
+        ]))
+    }
+
     type ScheduleSaveFn =
         dyn Fn(&Transmission) -> Result<(), Box<dyn Error + Send + Sync + 'static>> + Send + Sync;
     type RescheduleFn =
@@ -762,8 +1694,10 @@ mod tests {
 
     struct TransmissionTestCase {
         name: String,
-        transmission_response:
-            Box<dyn Fn(Message) -> Result<(), Box<dyn Error + Send + Sync + 'static>> + Send>,
+        transmission_response: Box<
+            dyn Fn(Message, &Occurrence) -> Result<(), Box<dyn Error + Send + Sync + 'static>>
+                + Send,
+        >,
         schedule_state_transition: ScheduleStateTransition,
         success: bool,
     }
@@ -782,7 +1716,7 @@ mod tests {
             let test_cases = vec![
                 TransmissionTestCase {
                     name: "success".into(),
-                    transmission_response: Box::new(move |_| Ok(())),
+                    transmission_response: Box::new(move |_, _| Ok(())),
                     schedule_state_transition: ScheduleStateTransition::Save(
                         Box::new(move |_| Ok(())),
                         true,
@@ -791,7 +1725,7 @@ mod tests {
                 },
                 TransmissionTestCase {
                     name: "fail_and_reschedule".into(),
-                    transmission_response: Box::new(move |_| {
+                    transmission_response: Box::new(move |_, _| {
                         Err("Let's hope this gets rescheduled.".into())
                     }),
                     schedule_state_transition: ScheduleStateTransition::Reschedule(
@@ -802,7 +1736,7 @@ mod tests {
                 },
                 TransmissionTestCase {
                     name: "transmit_but_fail_mark_done".into(),
-                    transmission_response: Box::new(move |_| Ok(())),
+                    transmission_response: Box::new(move |_, _| Ok(())),
                     schedule_state_transition: ScheduleStateTransition::Save(
                         Box::new(move |_| Err("The schedule is stuck in doing now.".into())),
                         false,
@@ -811,7 +1745,7 @@ mod tests {
                 },
                 TransmissionTestCase {
                     name: "transmit_fail_and_reschedule_fail".into(),
-                    transmission_response: Box::new(move |_| {
+                    transmission_response: Box::new(move |_, _| {
                         Err("Even the reschedule hereafter fails".into())
                     }),
                     schedule_state_transition: ScheduleStateTransition::Reschedule(
@@ -825,6 +1759,7 @@ mod tests {
             for test_case in test_cases {
                 let mut transmitter = MockTransmitter::new();
                 let mut repository = MockRepository::new();
+                repository.expect_renew_lease().returning(|_| Ok(true));
                 let mut metrics = MockMetrics::new();
 
                 transmitter
@@ -859,7 +1794,7 @@ mod tests {
                     Arc::new(metrics),
                 );
 
-                let result = scheduler.transmit(&transmission).await;
+                let result = scheduler.transmit(&transmission, Utc::now()).await;
                 assert_eq!(
                     result.is_ok(),
                     test_case.success,
@@ -877,6 +1812,8 @@ mod tests {
         let transmission_list_clone = transmission_list.clone();
 
         let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
         repository
             .expect_poll_transmissions()
             .times(1)
@@ -889,7 +1826,7 @@ mod tests {
         transmitter
             .expect_transmit()
             .times(amount_transmissions)
-            .returning(|_message| Ok(()));
+            .returning(|_message, _| Ok(()));
 
         repository
             .expect_save()
@@ -944,6 +1881,8 @@ mod tests {
         )];
 
         let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
         repository
             .expect_poll_transmissions()
             .times(1)
@@ -957,7 +1896,7 @@ mod tests {
         transmitter
             .expect_transmit()
             .times(1)
-            .returning(move |_message| Err("Message fails to transmit".into()));
+            .returning(move |_message, _| Err("Message fails to transmit".into()));
 
         let mut metrics = MockMetrics::new();
         metrics
@@ -988,6 +1927,106 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_poll_lease_reclaimed() {
+        let now = Utc::now();
+        let ten_milliseconds = chrono::Duration::milliseconds(10);
+
+        let schedules = vec![Transmission::new(
+            Schedule::Delayed(Delayed::new(now - ten_milliseconds)),
+            arbitrary_message(),
+        )];
+
+        let mut repository = MockRepository::new();
+        repository
+            .expect_poll_transmissions()
+            .times(1)
+            .returning(move |_, _| Ok(schedules.clone()));
+        repository
+            .expect_renew_lease()
+            .times(1)
+            .returning(|_| Ok(false));
+        repository.expect_save().times(0);
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter.expect_transmit().times(0);
+
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::Polled(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.process_batch().await;
+        assert!(result.is_ok());
+    }
+
+    // SlowTransmitter transmits successfully after the given duration.
+    struct SlowTransmitter(time::Duration);
+
+    #[async_trait]
+    impl Transmitter for SlowTransmitter {
+        async fn transmit(
+            &self,
+            _message: Message,
+            _occurrence: &Occurrence,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            tokio::time::sleep(self.0).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_lease_renewed_during_transmission() {
+        let transmission = new_transmission_delayed();
+
+        let mut repository = MockRepository::new();
+        repository
+            .expect_poll_transmissions()
+            .times(1)
+            .returning(move |_, _| Ok(vec![transmission.clone()]));
+        // Renewed once before the transmission, and every 100 milliseconds during it.
+        repository
+            .expect_renew_lease()
+            .times(4)
+            .returning(|_| Ok(true));
+        repository.expect_save().times(1).returning(|_| Ok(()));
+
+        let mut metrics = MockMetrics::new();
+        for event in [
+            MetricEvent::Polled(true),
+            MetricEvent::Transmitted(true),
+            MetricEvent::ScheduleStateSaved(true),
+        ] {
+            metrics
+                .expect_count()
+                .with(eq(event))
+                .returning(|_| ())
+                .times(1);
+        }
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(SlowTransmitter(time::Duration::from_millis(350))),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        )
+        .with_lease_duration(time::Duration::from_millis(300));
+
+        let result = scheduler.process_batch().await;
+        assert!(result.is_ok());
+    }
+
     #[tokio::test]
     // The first message will succeed, the second fails to transmit.
     async fn test_poll_datetimes_schedules_fail_transmit() {
@@ -1021,6 +2060,8 @@ mod tests {
         let schedule_list_clone_0 = schedule_list.clone();
 
         let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
         repository
             .expect_poll_transmissions()
             .times(1)
@@ -1033,7 +2074,7 @@ mod tests {
         transmitter
             .expect_transmit()
             .times(amount_schedules)
-            .returning(move |message| match message {
+            .returning(move |message, _| match message {
                 Message::NatsEvent(data) if data.subject == message_subject_success.into() => {
                     Ok(())
                 }
@@ -1102,73 +2143,467 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_cron() {
-        // Friday 15 May 2015;
-        let timestamp_first_poll = Utc.with_ymd_and_hms(2015, 5, 15, 0, 0, 0).unwrap();
-        assert_eq!(timestamp_first_poll.to_string(), "2015-05-15 00:00:00 UTC");
-        let timestamp_first_poll_clone = timestamp_first_poll.clone();
-
-        // Every minute at 5 seconds of every hour, starting midnight, on any Fridays in May 2015.
-        let expression = "5 * * * May Fri 2015";
-        let cron_schedule =
-            cron::Schedule::from_str(expression).expect("should be valid cron schedule");
+    // The first destination succeeds and the second fails, so only the progress is saved.
+    async fn test_fan_out_partial_failure() {
+        let now = Utc::now();
+        let ten_milliseconds = chrono::Duration::milliseconds(10);
 
-        let schedule = Transmission::new(
-            Schedule::Cron(Cron::new(
-                timestamp_first_poll,
-                cron_schedule.clone(),
-                Iterate::Times(5),
-            )),
-            Message::NatsEvent(NatsEvent::new(
-                "SUBJECT.arbitrary".into(),
-                "arbitrary payload".into(),
-            )),
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - ten_milliseconds)),
+            new_fan_out_message(),
         );
+        let expected_partially_transmitted = Transmission {
+            delivered_destinations: vec![0],
+            ..transmission.clone()
+        };
 
-        let mut sequence = Sequence::new();
         let mut repository = MockRepository::new();
-        let mut now = MockNow::new();
-        let mut transmitter = MockTransmitter::new();
-        let mut metrics = MockMetrics::new();
-
-        let schedule_first_poll = schedule.clone();
-        let schedule_second_poll = schedule.clone();
 
-        // First poll: no ready schedules yet, so no transmission.
+        repository.expect_renew_lease().returning(|_| Ok(true));
         repository
-            .expect_poll_transmissions()
+            .expect_save()
+            .with(eq(expected_partially_transmitted))
             .times(1)
-            .in_sequence(&mut sequence)
-            .returning(move |_, _| Ok(vec![schedule_first_poll.clone()]));
+            .returning(|_| Ok(()));
+        repository.expect_reschedule().never();
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter
+            .expect_transmit()
+            .times(2)
+            .returning(move |message, _| match message {
+                Message::NatsEvent(data) if data.subject == "FIRST.destination".into() => Ok(()),
+                _ => Err("Second destination fails to transmit".into()),
+            });
+
+        let mut metrics = MockMetrics::new();
         metrics
             .expect_count()
-            .with(eq(MetricEvent::Polled(true)))
-            .times(1)
-            .returning(|_| ());
-        now.expect_now()
-            .times(1)
-            .returning(move || timestamp_first_poll_clone.clone());
-
-        // Second poll: schedule is ready: so transmit.
-        let expected_schedule = Transmission {
-            id: schedule.id,
-            message: schedule.message,
-            schedule: Schedule::Cron(Cron {
-                first_transmission_after: timestamp_first_poll,
-                iterate: Iterate::Times(5),
-                expression: cron_schedule.clone(),
-            }),
-            next: Some(Utc.with_ymd_and_hms(2015, 5, 15, 0, 1, 5).unwrap()),
-            transmission_count: 1,
-        };
-        repository
-            .expect_poll_transmissions()
-            .times(1)
-            .in_sequence(&mut sequence)
-            .returning(move |_, _| Ok(vec![schedule_second_poll.clone()]));
+            .with(eq(MetricEvent::DestinationTransmitted(
+                Transport::Nats,
+                true,
+            )))
+            .returning(|_| ())
+            .times(1);
         metrics
             .expect_count()
-            .with(eq(MetricEvent::Polled(true)))
+            .with(eq(MetricEvent::DestinationTransmitted(
+                Transport::Nats,
+                false,
+            )))
+            .returning(|_| ())
+            .times(1);
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::ScheduleStateSaved(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.transmit(&transmission, Utc::now()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    // The first destination fails permanently and the second may succeed when retried, so the
+    // first is settled and the occurrence is retried for the second.
+    async fn test_fan_out_permanent_and_retryable_failure() {
+        let now = Utc::now();
+        let ten_milliseconds = chrono::Duration::milliseconds(10);
+
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - ten_milliseconds)),
+            new_fan_out_message(),
+        );
+        let expected_partially_transmitted = Transmission {
+            failed_destinations: vec![0],
+            ..transmission.clone()
+        };
+
+        let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        repository
+            .expect_save()
+            .with(eq(expected_partially_transmitted))
+            .times(1)
+            .returning(|_| Ok(()));
+        repository.expect_reschedule().never();
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter
+            .expect_transmit()
+            .times(2)
+            .returning(move |message, _| match message {
+                Message::NatsEvent(data) if data.subject == "FIRST.destination".into() => {
+                    Err(Box::new(PermanentTransmitError::new("subject rejected")))
+                }
+                _ => Err("Second destination fails to transmit".into()),
+            });
+
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::DestinationTransmitted(
+                Transport::Nats,
+                false,
+            )))
+            .returning(|_| ())
+            .times(2);
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::ScheduleStateSaved(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.transmit(&transmission, Utc::now()).await;
+        let err = result.expect_err("transmission should fail");
+        assert!(!err.is::<PermanentTransmitError>());
+    }
+
+    #[tokio::test]
+    // The first destination succeeds and the second fails permanently, so no destination remains
+    // to be retried and the occurrence is skipped.
+    async fn test_fan_out_only_permanent_failure() {
+        let now = Utc::now();
+        let ten_milliseconds = chrono::Duration::milliseconds(10);
+
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - ten_milliseconds)),
+            new_fan_out_message(),
+        );
+        let expected_transmitted = Transmission {
+            next: None,
+            transmission_count: 1,
+            ..transmission.clone()
+        };
+
+        let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        repository
+            .expect_save()
+            .with(eq(expected_transmitted))
+            .times(1)
+            .returning(|_| Ok(()));
+        repository.expect_reschedule().never();
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter
+            .expect_transmit()
+            .times(2)
+            .returning(move |message, _| match message {
+                Message::NatsEvent(data) if data.subject == "FIRST.destination".into() => Ok(()),
+                _ => Err(Box::new(PermanentTransmitError::new("subject rejected"))),
+            });
+
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::DestinationTransmitted(
+                Transport::Nats,
+                true,
+            )))
+            .returning(|_| ())
+            .times(1);
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::DestinationTransmitted(
+                Transport::Nats,
+                false,
+            )))
+            .returning(|_| ())
+            .times(1);
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::ScheduleStateSaved(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.transmit(&transmission, Utc::now()).await;
+        let err = result.expect_err("transmission should fail");
+        assert!(err.is::<PermanentTransmitError>());
+    }
+
+    #[tokio::test]
+    // A permanent failure cannot succeed when retried, so the occurrence is skipped.
+    async fn test_transmit_permanent_failure() {
+        let now = Utc::now();
+        let ten_milliseconds = chrono::Duration::milliseconds(10);
+
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - ten_milliseconds)),
+            new_nats_message(),
+        );
+        let expected_transmitted = Transmission {
+            next: None,
+            transmission_count: 1,
+            ..transmission.clone()
+        };
+
+        let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        repository
+            .expect_save()
+            .with(eq(expected_transmitted))
+            .times(1)
+            .returning(|_| Ok(()));
+        repository.expect_reschedule().never();
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter
+            .expect_transmit()
+            .times(1)
+            .returning(|_, _| Err(Box::new(PermanentTransmitError::new("recipient rejected"))));
+
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::ScheduleStateSaved(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.transmit(&transmission, Utc::now()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    // The first destination was transmitted to before, so the retry only concerns the second.
+    async fn test_fan_out_retry_skips_delivered_destinations() {
+        let now = Utc::now();
+        let ten_milliseconds = chrono::Duration::milliseconds(10);
+
+        let transmission = Transmission::new(
+            Schedule::Delayed(Delayed::new(now - ten_milliseconds)),
+            new_fan_out_message(),
+        )
+        .partially_transmitted(vec![0], vec![]);
+        let expected_transmitted = Transmission {
+            next: None,
+            transmission_count: 1,
+            delivered_destinations: vec![],
+            ..transmission.clone()
+        };
+
+        let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        repository
+            .expect_save()
+            .with(eq(expected_transmitted))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter
+            .expect_transmit()
+            .with(
+                eq(Message::NatsEvent(NatsEvent::new(
+                    "SECOND.destination".into(),
+                    "arbitrary payload".into(),
+                ))),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::DestinationTransmitted(
+                Transport::Nats,
+                true,
+            )))
+            .returning(|_| ())
+            .times(1);
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::ScheduleStateSaved(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.transmit(&transmission, Utc::now()).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_transmit_templated() {
+        let now = DateTime::from_timestamp(1431648000, 0).expect("should be valid timestamp");
+        let scheduled_at = now - chrono::Duration::seconds(1);
+
+        let transmission = Transmission::new(
+            Schedule::Interval(Interval::new(
+                scheduled_at,
+                time::Duration::from_secs(60),
+                Iterate::Infinitely,
+            )),
+            Message::NatsEvent(NatsEvent {
+                subject: "SUBJECT.{{transmission_count}}".into(),
+                payload: "{{transmission_id}} at {{scheduled_at}} sent {{transmitted_at}}".into(),
+                templated: true,
+                format: PayloadFormat::default(),
+            }),
+        );
+        let expected_message = Message::NatsEvent(NatsEvent {
+            subject: "SUBJECT.0".into(),
+            payload: format!(
+                "{} at 2015-05-14T23:59:59+00:00 sent 2015-05-15T00:00:00+00:00",
+                transmission.id
+            )
+            .into(),
+            templated: true,
+            format: PayloadFormat::default(),
+        });
+        let expected_occurrence = Occurrence {
+            transmission_id: transmission.id,
+            transmission_count: 0,
+            scheduled_at,
+            transmitted_at: now,
+        };
+
+        let mut repository = MockRepository::new();
+
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        repository.expect_save().times(1).returning(|_| Ok(()));
+
+        let mut transmitter = MockTransmitter::new();
+        transmitter
+            .expect_transmit()
+            .with(eq(expected_message), eq(expected_occurrence))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut metrics = MockMetrics::new();
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::ScheduleStateSaved(true)))
+            .returning(|_| ())
+            .times(1);
+
+        let scheduler = TransmissionScheduler::new(
+            DEFAULT_CLOCK_CYCLE_INTERVAL,
+            Arc::new(repository),
+            Arc::new(transmitter),
+            Arc::new(Utc::now),
+            Arc::new(metrics),
+        );
+
+        let result = scheduler.transmit(&transmission, now).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_cron() {
+        // Friday 15 May 2015;
+        let timestamp_first_poll = Utc.with_ymd_and_hms(2015, 5, 15, 0, 0, 0).unwrap();
+        assert_eq!(timestamp_first_poll.to_string(), "2015-05-15 00:00:00 UTC");
+        let timestamp_first_poll_clone = timestamp_first_poll;
+
+        // Every minute at 5 seconds of every hour, starting midnight, on any Fridays in May 2015.
+        let expression = "5 * * * May Fri 2015";
+        let cron_schedule =
+            cron::Schedule::from_str(expression).expect("should be valid cron schedule");
+
+        let schedule = Transmission::new(
+            Schedule::Cron(Cron::new(
+                timestamp_first_poll,
+                cron_schedule.clone(),
+                Iterate::Times(5),
+            )),
+            Message::NatsEvent(NatsEvent::new(
+                "SUBJECT.arbitrary".into(),
+                "arbitrary payload".into(),
+            )),
+        );
+
+        let mut sequence = Sequence::new();
+        let mut repository = MockRepository::new();
+        repository.expect_renew_lease().returning(|_| Ok(true));
+        let mut now = MockNow::new();
+        let mut transmitter = MockTransmitter::new();
+        let mut metrics = MockMetrics::new();
+
+        let schedule_first_poll = schedule.clone();
+        let schedule_second_poll = schedule.clone();
+
+        // First poll: no ready schedules yet, so no transmission.
+        repository
+            .expect_poll_transmissions()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, _| Ok(vec![schedule_first_poll.clone()]));
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::Polled(true)))
+            .times(1)
+            .returning(|_| ());
+        now.expect_now()
+            .times(1)
+            .returning(move || timestamp_first_poll_clone);
+
+        // Second poll: schedule is ready: so transmit.
+        let expected_schedule = Transmission {
+            id: schedule.id,
+            message: schedule.message,
+            schedule: Schedule::Cron(Cron {
+                first_transmission_after: timestamp_first_poll,
+                iterate: Iterate::Times(5),
+                expression: cron_schedule.clone(),
+            }),
+            next: Some(Utc.with_ymd_and_hms(2015, 5, 15, 0, 1, 5).unwrap()),
+            transmission_count: 1,
+            delivered_destinations: vec![],
+            failed_destinations: vec![],
+            owner: None,
+        };
+        repository
+            .expect_poll_transmissions()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, _| Ok(vec![schedule_second_poll.clone()]));
+        metrics
+            .expect_count()
+            .with(eq(MetricEvent::Polled(true)))
             .times(1)
             .returning(|_| ());
         now.expect_now()
@@ -1178,7 +2613,7 @@ mod tests {
             .expect_transmit()
             .times(1)
             .in_sequence(&mut sequence)
-            .returning(move |_| Ok(()));
+            .returning(move |_, _| Ok(()));
         repository
             .expect_save()
             .times(1)
@@ -1223,6 +2658,22 @@ mod tests {
             expected_result: Result<(), ScheduleError>,
         }
 
+        let schemas = SchemaRegistry::new();
+        schemas
+            .register(
+                "occurrence",
+                &serde_json::json!({
+                    "type": "object",
+                    "properties": { "count": { "type": "integer" } },
+                    "required": ["count"]
+                }),
+            )
+            .expect("schema should compile");
+        let json_format = PayloadFormat {
+            content_type: Some(String::from("application/json")),
+            schema: Some(String::from("occurrence")),
+        };
+
         let test_cases = vec![
             TestCase {
                 name: String::from("valid nats"),
@@ -1232,7 +2683,7 @@ mod tests {
             TestCase {
                 name: String::from("nats too large subject"),
                 message: Message::NatsEvent(NatsEvent::new(
-                    String::from("C".repeat((MAX_NATS_SUBJECT_LENGTH + 1) as usize)),
+                    "C".repeat((MAX_NATS_SUBJECT_LENGTH + 1) as usize),
                     "arbitrary payload".into(),
                 )),
                 expected_result: Err(ScheduleError::NatsInvalidSubject),
@@ -1277,10 +2728,501 @@ mod tests {
                 )),
                 expected_result: Err(ScheduleError::NatsInvalidSubject),
             },
+            TestCase {
+                name: String::from("nats payload of maximum size"),
+                message: Message::NatsEvent(NatsEvent::new(
+                    "SUBJECT.arbitrary".into(),
+                    "P".repeat(DEFAULT_MAX_PAYLOAD_SIZE).into(),
+                )),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("nats too large payload"),
+                message: Message::NatsEvent(NatsEvent::new(
+                    "SUBJECT.arbitrary".into(),
+                    "P".repeat(DEFAULT_MAX_PAYLOAD_SIZE + 1).into(),
+                )),
+                expected_result: Err(ScheduleError::PayloadTooLarge {
+                    size: DEFAULT_MAX_PAYLOAD_SIZE + 1,
+                    max_size: DEFAULT_MAX_PAYLOAD_SIZE,
+                }),
+            },
+            TestCase {
+                name: String::from("nats payload conforming to schema"),
+                message: Message::NatsEvent(NatsEvent {
+                    format: json_format.clone(),
+                    ..NatsEvent::new("SUBJECT.arbitrary".into(), r#"{"count": 1}"#.into())
+                }),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("nats payload violating schema"),
+                message: Message::NatsEvent(NatsEvent {
+                    format: json_format.clone(),
+                    ..NatsEvent::new("SUBJECT.arbitrary".into(), r#"{"count": "one"}"#.into())
+                }),
+                expected_result: Err(ScheduleError::InvalidPayload(SchemaError::Violation {
+                    instance_path: String::from("/count"),
+                    schema_path: String::from("/properties/count/type"),
+                    reason: String::from(r#""one" is not of type "integer""#),
+                })),
+            },
+            TestCase {
+                name: String::from("templated nats payload conforming to schema when rendered"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT.arbitrary".into(),
+                    payload: r#"{"count": {{transmission_count}}}"#.into(),
+                    templated: true,
+                    format: json_format.clone(),
+                }),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("valid templated nats"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT.{{transmission_count}}".into(),
+                    payload: "occurrence {{ transmission_count }} of {{transmission_id}}".into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("templated nats with unknown variable"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT.arbitrary".into(),
+                    payload: "{{unknown}}".into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                expected_result: Err(ScheduleError::InvalidTemplate(
+                    TemplateError::UnknownVariable(String::from("unknown")),
+                )),
+            },
+            TestCase {
+                name: String::from("templated nats with unclosed placeholder"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT.{{transmission_count".into(),
+                    payload: "arbitrary payload".into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                expected_result: Err(ScheduleError::InvalidTemplate(
+                    TemplateError::UnclosedPlaceholder,
+                )),
+            },
+            TestCase {
+                name: String::from("templated nats subject with timestamp"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT.{{transmitted_at}}".into(),
+                    payload: "arbitrary payload".into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                expected_result: Err(ScheduleError::InvalidTemplate(
+                    TemplateError::TimestampInSubject(String::from("transmitted_at")),
+                )),
+            },
+            TestCase {
+                name: String::from("templated nats subject renders with space"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT. {{transmission_count}}".into(),
+                    payload: "arbitrary payload".into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                expected_result: Err(ScheduleError::NatsInvalidSubject),
+            },
+            TestCase {
+                name: String::from("templated nats payload is not utf-8"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "SUBJECT.arbitrary".into(),
+                    payload: vec![0xff, 0xfe].into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                expected_result: Err(ScheduleError::InvalidTemplate(TemplateError::NotUtf8)),
+            },
+            TestCase {
+                name: String::from("valid mqtt"),
+                message: new_mqtt_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("mqtt empty topic"),
+                message: Message::MqttPublish(MqttPublish::new(
+                    String::new(),
+                    "arbitrary payload".into(),
+                    QualityOfService::AtLeastOnce,
+                    false,
+                )),
+                expected_result: Err(ScheduleError::MqttInvalidTopic),
+            },
+            TestCase {
+                name: String::from("mqtt too large topic"),
+                message: Message::MqttPublish(MqttPublish::new(
+                    "c".repeat((MAX_MQTT_TOPIC_LENGTH + 1) as usize),
+                    "arbitrary payload".into(),
+                    QualityOfService::AtLeastOnce,
+                    false,
+                )),
+                expected_result: Err(ScheduleError::MqttInvalidTopic),
+            },
+            TestCase {
+                name: String::from("mqtt start with $"),
+                message: Message::MqttPublish(MqttPublish::new(
+                    String::from("$SYS/reserved"),
+                    "arbitrary payload".into(),
+                    QualityOfService::AtLeastOnce,
+                    false,
+                )),
+                expected_result: Err(ScheduleError::MqttInvalidTopic),
+            },
+            TestCase {
+                name: String::from("mqtt contains +"),
+                message: Message::MqttPublish(MqttPublish::new(
+                    String::from("has/+/wildcard"),
+                    "arbitrary payload".into(),
+                    QualityOfService::AtLeastOnce,
+                    false,
+                )),
+                expected_result: Err(ScheduleError::MqttInvalidTopic),
+            },
+            TestCase {
+                name: String::from("mqtt contains #"),
+                message: Message::MqttPublish(MqttPublish::new(
+                    String::from("has/#"),
+                    "arbitrary payload".into(),
+                    QualityOfService::AtLeastOnce,
+                    false,
+                )),
+                expected_result: Err(ScheduleError::MqttInvalidTopic),
+            },
+            TestCase {
+                name: String::from("valid amqp"),
+                message: new_amqp_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("amqp default exchange"),
+                message: Message::AmqpPublish(AmqpPublish::new(
+                    String::new(),
+                    String::from("queue"),
+                    AmqpProperties::default(),
+                    "arbitrary body".into(),
+                )),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("amqp exchange contains space"),
+                message: Message::AmqpPublish(AmqpPublish::new(
+                    String::from("has space"),
+                    String::from("routing.key"),
+                    AmqpProperties::default(),
+                    "arbitrary body".into(),
+                )),
+                expected_result: Err(ScheduleError::AmqpInvalidExchange),
+            },
+            TestCase {
+                name: String::from("amqp reserved exchange"),
+                message: Message::AmqpPublish(AmqpPublish::new(
+                    String::from("amq.topic"),
+                    String::from("routing.key"),
+                    AmqpProperties::default(),
+                    "arbitrary body".into(),
+                )),
+                expected_result: Err(ScheduleError::AmqpInvalidExchange),
+            },
+            TestCase {
+                name: String::from("amqp too large routing key"),
+                message: Message::AmqpPublish(AmqpPublish::new(
+                    String::from("exchange"),
+                    "k".repeat((MAX_AMQP_SHORT_STRING_LENGTH + 1) as usize),
+                    AmqpProperties::default(),
+                    "arbitrary body".into(),
+                )),
+                expected_result: Err(ScheduleError::AmqpInvalidRoutingKey),
+            },
+            TestCase {
+                name: String::from("amqp malformed json body by properties content type"),
+                message: Message::AmqpPublish(AmqpPublish::new(
+                    String::from("exchange"),
+                    String::from("routing.key"),
+                    AmqpProperties {
+                        content_type: Some(String::from("application/json")),
+                        ..AmqpProperties::default()
+                    },
+                    "arbitrary body".into(),
+                )),
+                expected_result: Err(ScheduleError::InvalidPayload(SchemaError::InvalidJson(
+                    String::from("expected value at line 1 column 1"),
+                ))),
+            },
+            TestCase {
+                name: String::from("valid redis publish"),
+                message: Message::RedisPublish(RedisPublish::new(
+                    String::from("channel"),
+                    "arbitrary payload".into(),
+                )),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("redis publish empty channel"),
+                message: Message::RedisPublish(RedisPublish::new(
+                    String::new(),
+                    "arbitrary payload".into(),
+                )),
+                expected_result: Err(ScheduleError::RedisInvalidChannel),
+            },
+            TestCase {
+                name: String::from("valid redis stream add"),
+                message: Message::RedisStreamAdd(RedisStreamAdd::new(
+                    String::from("stream"),
+                    BTreeMap::from([(String::from("field"), "value".into())]),
+                    Some(1000),
+                )),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("redis stream add without fields"),
+                message: Message::RedisStreamAdd(RedisStreamAdd::new(
+                    String::from("stream"),
+                    BTreeMap::new(),
+                    None,
+                )),
+                expected_result: Err(ScheduleError::RedisInvalidStream),
+            },
+            TestCase {
+                name: String::from("redis stream add empty stream"),
+                message: Message::RedisStreamAdd(RedisStreamAdd::new(
+                    String::new(),
+                    BTreeMap::from([(String::from("field"), "value".into())]),
+                    None,
+                )),
+                expected_result: Err(ScheduleError::RedisInvalidStream),
+            },
+            TestCase {
+                name: String::from("redis stream add without hash tag slot"),
+                message: Message::RedisStreamAdd(RedisStreamAdd::new(
+                    String::from("{}.stream"),
+                    BTreeMap::from([(String::from("field"), "value".into())]),
+                    None,
+                )),
+                expected_result: Err(ScheduleError::RedisInvalidStream),
+            },
+            TestCase {
+                name: String::from("valid kafka"),
+                message: Message::KafkaRecord(KafkaRecord::new(
+                    String::from("orders.created"),
+                    Some("customer-1".into()),
+                    BTreeMap::from([(String::from("origin"), "transmit".into())]),
+                    "arbitrary value".into(),
+                )),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("kafka topic contains slash"),
+                message: Message::KafkaRecord(KafkaRecord::new(
+                    String::from("orders/created"),
+                    None,
+                    BTreeMap::new(),
+                    "arbitrary value".into(),
+                )),
+                expected_result: Err(ScheduleError::KafkaInvalidTopic),
+            },
+            TestCase {
+                name: String::from("kafka too large topic"),
+                message: Message::KafkaRecord(KafkaRecord::new(
+                    "t".repeat((MAX_KAFKA_TOPIC_LENGTH + 1) as usize),
+                    None,
+                    BTreeMap::new(),
+                    "arbitrary value".into(),
+                )),
+                expected_result: Err(ScheduleError::KafkaInvalidTopic),
+            },
+            TestCase {
+                name: String::from("kafka header contains null"),
+                message: Message::KafkaRecord(KafkaRecord::new(
+                    String::from("orders"),
+                    None,
+                    BTreeMap::from([(String::from("has\0null"), "value".into())]),
+                    "arbitrary value".into(),
+                )),
+                expected_result: Err(ScheduleError::KafkaInvalidHeader),
+            },
+            TestCase {
+                name: String::from("valid grpc call"),
+                message: new_grpc_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("grpc call without scheme"),
+                message: Message::GrpcCall(GrpcCall {
+                    endpoint: String::from("orders:8080"),
+                    ..new_grpc_call()
+                }),
+                expected_result: Err(ScheduleError::GrpcInvalidEndpoint),
+            },
+            TestCase {
+                name: String::from("grpc call unqualified method"),
+                message: Message::GrpcCall(GrpcCall {
+                    method: String::from("Create"),
+                    ..new_grpc_call()
+                }),
+                expected_result: Err(ScheduleError::GrpcInvalidMethod),
+            },
+            TestCase {
+                name: String::from("grpc call method without name"),
+                message: Message::GrpcCall(GrpcCall {
+                    method: String::from("/orders.Orders/"),
+                    ..new_grpc_call()
+                }),
+                expected_result: Err(ScheduleError::GrpcInvalidMethod),
+            },
+            TestCase {
+                name: String::from("grpc call zero deadline"),
+                message: Message::GrpcCall(GrpcCall {
+                    deadline: time::Duration::ZERO,
+                    ..new_grpc_call()
+                }),
+                expected_result: Err(ScheduleError::GrpcInvalidDeadline),
+            },
+            TestCase {
+                name: String::from("grpc call reserved metadata"),
+                message: Message::GrpcCall(GrpcCall {
+                    metadata: BTreeMap::from([(String::from("grpc-timeout"), String::from("1S"))]),
+                    ..new_grpc_call()
+                }),
+                expected_result: Err(ScheduleError::GrpcInvalidMetadata),
+            },
+            TestCase {
+                name: String::from("grpc call invalid metadata key"),
+                message: Message::GrpcCall(GrpcCall {
+                    metadata: BTreeMap::from([(String::from("Has Space"), String::from("value"))]),
+                    ..new_grpc_call()
+                }),
+                expected_result: Err(ScheduleError::GrpcInvalidMetadata),
+            },
+            TestCase {
+                name: String::from("valid exec"),
+                message: new_exec_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("exec empty command"),
+                message: Message::Exec(Exec::new(
+                    String::new(),
+                    vec![],
+                    BTreeMap::new(),
+                    time::Duration::from_secs(1),
+                )),
+                expected_result: Err(ScheduleError::ExecInvalidCommand),
+            },
+            TestCase {
+                name: String::from("exec environment key contains ="),
+                message: Message::Exec(Exec::new(
+                    String::from("/usr/bin/backup"),
+                    vec![],
+                    BTreeMap::from([(String::from("KEY=VALUE"), String::from("value"))]),
+                    time::Duration::from_secs(1),
+                )),
+                expected_result: Err(ScheduleError::ExecInvalidCommand),
+            },
+            TestCase {
+                name: String::from("exec zero timeout"),
+                message: Message::Exec(Exec::new(
+                    String::from("/usr/bin/backup"),
+                    vec![],
+                    BTreeMap::new(),
+                    time::Duration::ZERO,
+                )),
+                expected_result: Err(ScheduleError::ExecInvalidCommand),
+            },
+            TestCase {
+                name: String::from("valid email"),
+                message: new_email_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("email invalid sender"),
+                message: Message::Email(Email {
+                    from: String::from("not an address"),
+                    ..new_email()
+                }),
+                expected_result: Err(ScheduleError::EmailInvalidAddress),
+            },
+            TestCase {
+                name: String::from("email without recipients"),
+                message: Message::Email(Email {
+                    to: vec![],
+                    ..new_email()
+                }),
+                expected_result: Err(ScheduleError::EmailInvalidAddress),
+            },
+            TestCase {
+                name: String::from("email invalid recipient"),
+                message: Message::Email(Email {
+                    to: vec![String::from("someone@example.com"), String::from("@")],
+                    ..new_email()
+                }),
+                expected_result: Err(ScheduleError::EmailInvalidAddress),
+            },
+            TestCase {
+                name: String::from("email without body"),
+                message: Message::Email(Email {
+                    text_body: None,
+                    html_body: None,
+                    ..new_email()
+                }),
+                expected_result: Err(ScheduleError::EmailMissingBody),
+            },
+            TestCase {
+                name: String::from("valid fan out"),
+                message: new_fan_out_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("fan out without destinations"),
+                message: Message::FanOut(FanOut::new(vec![])),
+                expected_result: Err(ScheduleError::FanOutInvalidDestinations),
+            },
+            TestCase {
+                name: String::from("nested fan out"),
+                message: Message::FanOut(FanOut::new(vec![new_fan_out_message()])),
+                expected_result: Err(ScheduleError::FanOutInvalidDestinations),
+            },
+            TestCase {
+                name: String::from("fan out with invalid destination"),
+                message: Message::FanOut(FanOut::new(vec![
+                    new_nats_message(),
+                    Message::NatsEvent(NatsEvent::new(
+                        String::from("HAS space"),
+                        "arbitrary payload".into(),
+                    )),
+                ])),
+                expected_result: Err(ScheduleError::NatsInvalidSubject),
+            },
+            TestCase {
+                name: String::from("fan out with too large payloads combined"),
+                message: Message::FanOut(FanOut::new(vec![
+                    Message::NatsEvent(NatsEvent::new(
+                        "SUBJECT.arbitrary".into(),
+                        "P".repeat(DEFAULT_MAX_PAYLOAD_SIZE / 2 + 1).into(),
+                    )),
+                    Message::NatsEvent(NatsEvent::new(
+                        "SUBJECT.arbitrary".into(),
+                        "P".repeat(DEFAULT_MAX_PAYLOAD_SIZE / 2).into(),
+                    )),
+                ])),
+                expected_result: Err(ScheduleError::PayloadTooLarge {
+                    size: DEFAULT_MAX_PAYLOAD_SIZE + 1,
+                    max_size: DEFAULT_MAX_PAYLOAD_SIZE,
+                }),
+            },
         ];
 
         for test_case in test_cases {
-            let valid = validate_message(&test_case.message);
+            let valid = validate_message(&test_case.message, DEFAULT_MAX_PAYLOAD_SIZE, &schemas);
             match test_case.expected_result {
                 Ok(()) => assert!(
                     valid.is_ok(),
@@ -1298,6 +3240,204 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_validate_exec_allowed() {
+        struct TestCase {
+            name: String,
+            message: Message,
+            expected_result: Result<(), ScheduleError>,
+        }
+
+        let exec_allowlist = vec![String::from("/usr/bin/backup")];
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("allowlisted exec"),
+                message: new_exec_message(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("exec not allowlisted"),
+                message: Message::Exec(Exec::new(
+                    String::from("/bin/rm"),
+                    vec![String::from("-rf"), String::from("/")],
+                    BTreeMap::new(),
+                    time::Duration::from_secs(1),
+                )),
+                expected_result: Err(ScheduleError::ExecCommandNotAllowed),
+            },
+            TestCase {
+                name: String::from("fan out with exec not allowlisted"),
+                message: Message::FanOut(FanOut::new(vec![
+                    new_nats_message(),
+                    Message::Exec(Exec::new(
+                        String::from("/bin/rm"),
+                        vec![],
+                        BTreeMap::new(),
+                        time::Duration::from_secs(1),
+                    )),
+                ])),
+                expected_result: Err(ScheduleError::ExecCommandNotAllowed),
+            },
+            TestCase {
+                name: String::from("other message"),
+                message: new_nats_message(),
+                expected_result: Ok(()),
+            },
+        ];
+
+        for test_case in test_cases {
+            let result = validate_exec_allowed(&test_case.message, &exec_allowlist);
+            assert_eq!(
+                result, test_case.expected_result,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_transport_configured() {
+        struct TestCase {
+            name: String,
+            message: Message,
+            transports: Option<HashSet<Transport>>,
+            expected_result: Result<(), ScheduleError>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("configured transport"),
+                message: new_nats_message(),
+                transports: Some(HashSet::from([Transport::Nats])),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("transport not configured"),
+                message: new_exec_message(),
+                transports: Some(HashSet::from([Transport::Nats])),
+                expected_result: Err(ScheduleError::TransportNotConfigured(Transport::Exec)),
+            },
+            TestCase {
+                name: String::from("fan out with transport not configured"),
+                message: Message::FanOut(FanOut::new(vec![new_nats_message(), new_exec_message()])),
+                transports: Some(HashSet::from([Transport::Nats])),
+                expected_result: Err(ScheduleError::TransportNotConfigured(Transport::Exec)),
+            },
+            TestCase {
+                name: String::from("any transport"),
+                message: new_exec_message(),
+                transports: None,
+                expected_result: Ok(()),
+            },
+        ];
+
+        for test_case in test_cases {
+            let result =
+                validate_transport_configured(&test_case.message, test_case.transports.as_ref());
+            assert_eq!(
+                result, test_case.expected_result,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_subject_allowed() {
+        struct TestCase {
+            name: String,
+            message: Message,
+            principal: Option<Principal>,
+            expected_result: Result<(), ScheduleError>,
+        }
+
+        let subject_acl = SubjectAcl {
+            deny: vec![String::from("$CONTROL.>"), String::from("INTERNAL.>")],
+            clients: BTreeMap::from([(
+                String::from("orders"),
+                ClientSubjectRules {
+                    allow: vec![String::from("ORDERS.>")],
+                    deny: vec![],
+                },
+            )]),
+            ..SubjectAcl::default()
+        };
+        let orders = Some(Principal {
+            name: String::from("orders"),
+        });
+        let nats_message = |subject: &str| {
+            Message::NatsEvent(NatsEvent::new(subject.into(), "arbitrary payload".into()))
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("anonymous onto subject that is not denied"),
+                message: nats_message("BILLING.invoiced"),
+                principal: None,
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("anonymous onto denied subject"),
+                message: nats_message("INTERNAL.control"),
+                principal: None,
+                expected_result: Err(ScheduleError::NatsSubjectNotAllowed),
+            },
+            TestCase {
+                name: String::from("client onto own prefix"),
+                message: nats_message("ORDERS.created"),
+                principal: orders.clone(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("client onto prefix of others"),
+                message: nats_message("BILLING.invoiced"),
+                principal: orders.clone(),
+                expected_result: Err(ScheduleError::NatsSubjectNotAllowed),
+            },
+            TestCase {
+                name: String::from("client onto templated subject of own prefix"),
+                message: Message::NatsEvent(NatsEvent {
+                    subject: "ORDERS.{{transmission_count}}".into(),
+                    payload: "arbitrary payload".into(),
+                    templated: true,
+                    format: PayloadFormat::default(),
+                }),
+                principal: orders.clone(),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("fan out with subject of others"),
+                message: Message::FanOut(FanOut::new(vec![
+                    nats_message("ORDERS.created"),
+                    nats_message("BILLING.invoiced"),
+                ])),
+                principal: orders.clone(),
+                expected_result: Err(ScheduleError::NatsSubjectNotAllowed),
+            },
+            TestCase {
+                name: String::from("other message"),
+                message: new_exec_message(),
+                principal: orders.clone(),
+                expected_result: Ok(()),
+            },
+        ];
+
+        for test_case in test_cases {
+            let result = validate_subject_allowed(
+                &test_case.message,
+                test_case.principal.as_ref(),
+                &subject_acl,
+            );
+            assert_eq!(
+                result, test_case.expected_result,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
     #[test]
     fn test_validate_schedule() {
         let now = DateTime::from_timestamp(1431648000, 0).expect("should be valid timestamp");
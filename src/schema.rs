@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use jsonschema::Validator;
+
+use crate::model::PayloadFormat;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    UnknownSchema(String),
+    InvalidSchema(String),
+    // UnsupportedContentType is returned if a schema is referenced for a payload that is not JSON.
+    UnsupportedContentType(String),
+    InvalidJson(String),
+    Violation {
+        // instance_path points to the offending value in the payload, schema_path to the keyword
+        // of the schema it violates, both as JSON pointers.
+        instance_path: String,
+        schema_path: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::UnknownSchema(name) => write!(f, "unknown schema '{name}'"),
+            SchemaError::InvalidSchema(reason) => write!(f, "invalid schema: {reason}"),
+            SchemaError::UnsupportedContentType(content_type) => write!(
+                f,
+                "content type '{content_type}' cannot be validated against a json schema"
+            ),
+            SchemaError::InvalidJson(reason) => write!(f, "payload is not valid json: {reason}"),
+            SchemaError::Violation {
+                instance_path,
+                schema_path,
+                reason,
+            } => write!(
+                f,
+                "payload at '{instance_path}' violates schema at '{schema_path}': {reason}"
+            ),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+// SchemaRegistry holds the JSON Schemas that payloads may refer to by name. Schemas are compiled
+// once, when registered.
+#[derive(Default)]
+pub struct SchemaRegistry {
+    validators: RwLock<BTreeMap<String, Arc<Validator>>>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> SchemaRegistry {
+        SchemaRegistry::default()
+    }
+
+    // register compiles the schema and stores it by name, replacing a schema of the same name.
+    pub fn register(&self, name: &str, schema: &serde_json::Value) -> Result<(), SchemaError> {
+        if name.is_empty() {
+            return Err(SchemaError::InvalidSchema(String::from(
+                "schema name cannot be empty",
+            )));
+        }
+
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|err| SchemaError::InvalidSchema(err.to_string()))?;
+
+        self.validators
+            .write()
+            .expect("schema registry lock should not be poisoned")
+            .insert(name.to_string(), Arc::new(validator));
+
+        Ok(())
+    }
+
+    // validate asserts that a payload of the given format is well-formed. JSON payloads must parse,
+    // and conform to the referenced schema, if any. Payloads of other content types are not
+    // inspected.
+    pub fn validate(&self, format: &PayloadFormat, payload: &[u8]) -> Result<(), SchemaError> {
+        let content_type_is_json = match &format.content_type {
+            Some(content_type) => is_json(content_type),
+            // A schema reference implies the payload is JSON.
+            None => format.schema.is_some(),
+        };
+        if !content_type_is_json {
+            return match (&format.schema, &format.content_type) {
+                (Some(_), Some(content_type)) => Err(SchemaError::UnsupportedContentType(
+                    content_type.to_string(),
+                )),
+                _ => Ok(()),
+            };
+        }
+
+        let instance: serde_json::Value = serde_json::from_slice(payload)
+            .map_err(|err| SchemaError::InvalidJson(err.to_string()))?;
+
+        let name = match &format.schema {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        let validator = self
+            .validators
+            .read()
+            .expect("schema registry lock should not be poisoned")
+            .get(name)
+            .cloned()
+            .ok_or_else(|| SchemaError::UnknownSchema(name.to_string()))?;
+
+        validator
+            .validate(&instance)
+            .map_err(|err| SchemaError::Violation {
+                instance_path: err.instance_path.to_string(),
+                schema_path: err.schema_path.to_string(),
+                reason: err.to_string(),
+            })
+    }
+}
+
+// is_json matches application/json and the structured syntax suffix +json, e.g.
+// application/cloudevents+json, ignoring parameters like the charset.
+fn is_json(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(content_type: Option<&str>, schema: Option<&str>) -> PayloadFormat {
+        PayloadFormat {
+            content_type: content_type.map(String::from),
+            schema: schema.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        struct TestCase {
+            name: String,
+            format: PayloadFormat,
+            payload: String,
+            expected_result: Result<(), SchemaError>,
+        }
+
+        let registry = SchemaRegistry::new();
+        registry
+            .register(
+                "order.created",
+                &serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "order_id": { "type": "string" },
+                        "lines": {
+                            "type": "array",
+                            "items": { "type": "object", "required": ["quantity"] }
+                        }
+                    },
+                    "required": ["order_id"]
+                }),
+            )
+            .expect("schema should compile");
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("unspecified format"),
+                format: format(None, None),
+                payload: String::from("not json"),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("other content type"),
+                format: format(Some("text/plain"), None),
+                payload: String::from("not json"),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("json without schema"),
+                format: format(Some("application/json; charset=utf-8"), None),
+                payload: String::from(r#"{"arbitrary": true}"#),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("malformed json"),
+                format: format(Some("application/cloudevents+json"), None),
+                payload: String::from(r#"{"arbitrary": "#),
+                expected_result: Err(SchemaError::InvalidJson(String::from(
+                    "EOF while parsing a value at line 1 column 14",
+                ))),
+            },
+            TestCase {
+                name: String::from("conforms to schema"),
+                format: format(None, Some("order.created")),
+                payload: String::from(r#"{"order_id": "a1", "lines": [{"quantity": 2}]}"#),
+                expected_result: Ok(()),
+            },
+            TestCase {
+                name: String::from("violates schema"),
+                format: format(Some("application/json"), Some("order.created")),
+                payload: String::from(r#"{"order_id": "a1", "lines": [{"price": 2}]}"#),
+                expected_result: Err(SchemaError::Violation {
+                    instance_path: String::from("/lines/0"),
+                    schema_path: String::from("/properties/lines/items/required"),
+                    reason: String::from(r#""quantity" is a required property"#),
+                }),
+            },
+            TestCase {
+                name: String::from("unknown schema"),
+                format: format(None, Some("order.cancelled")),
+                payload: String::from(r#"{"order_id": "a1"}"#),
+                expected_result: Err(SchemaError::UnknownSchema(String::from("order.cancelled"))),
+            },
+            TestCase {
+                name: String::from("schema for other content type"),
+                format: format(Some("text/plain"), Some("order.created")),
+                payload: String::from(r#"{"order_id": "a1"}"#),
+                expected_result: Err(SchemaError::UnsupportedContentType(String::from(
+                    "text/plain",
+                ))),
+            },
+        ];
+
+        for test_case in test_cases {
+            let result = registry.validate(&test_case.format, test_case.payload.as_bytes());
+            assert_eq!(
+                result, test_case.expected_result,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_register_invalid_schema() {
+        let registry = SchemaRegistry::new();
+
+        assert!(registry
+            .register("invalid", &serde_json::json!({ "type": "unknown" }))
+            .is_err());
+        assert!(registry
+            .register("", &serde_json::json!({ "type": "object" }))
+            .is_err());
+    }
+}
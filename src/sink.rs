@@ -0,0 +1,22 @@
+use log::info;
+use serde::Deserialize;
+use tokio::fs::{File, OpenOptions};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub path: String,
+}
+
+// open_file opens the file to append to, creating it if it does not exist, such that transmissions
+// of earlier runs are kept.
+pub async fn open_file(config: Config) -> Result<File, Box<dyn std::error::Error>> {
+    info!("Opening sink file {}", config.path);
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config.path)
+        .await?;
+
+    Ok(file)
+}
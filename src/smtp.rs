@@ -0,0 +1,41 @@
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, Tokio1Executor};
+use log::info;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    // starttls requires the connection to be upgraded to TLS, before authenticating.
+    pub starttls: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+pub async fn connect_to_smtp(
+    config: Config,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, Box<dyn std::error::Error>> {
+    info!("Connecting to smtp://{}:{}", config.host, config.port);
+
+    let mut builder = match config.starttls {
+        true => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+        // Without TLS, e.g. for a local relay or mail sink.
+        false => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+    }
+    .port(config.port);
+
+    if let Some(username) = config.username {
+        builder = builder.credentials(Credentials::new(
+            username,
+            config.password.unwrap_or_default(),
+        ));
+    }
+
+    let transport = builder.build();
+
+    // Surface an unreachable server or refused credentials at startup.
+    transport.test_connection().await?;
+
+    Ok(transport)
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use log::info;
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
+
+// BUSY_TIMEOUT is the time to wait for another process to release its lock on the database file.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// connect_to_database opens the database file at path, which is created if it does not exist.
+// Writes are synced to disk before they are acknowledged, such that stored transmissions survive a
+// power loss.
+pub async fn connect_to_database(path: &str) -> Result<SqlitePool, Box<dyn std::error::Error>> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Full)
+        .busy_timeout(BUSY_TIMEOUT);
+
+    info!("Opening sqlite database {path}");
+
+    // SQLite allows a single writer at a time, so a single connection serialises the queries of
+    // the repository rather than failing them on contention.
+    let connection_pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+
+    Ok(connection_pool)
+}
+
+#[cfg(test)]
+pub async fn connect_to_test_database() -> Result<SqlitePool, Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!(
+        "transmit_test_{}.db",
+        uuid::Uuid::new_v4().as_simple()
+    ));
+
+    connect_to_database(&path.to_string_lossy()).await
+}
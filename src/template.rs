@@ -0,0 +1,319 @@
+use std::error::Error;
+use std::fmt;
+
+use chrono::DateTime;
+use uuid::Uuid;
+
+use crate::model::{FanOut, Message, NatsEvent, Occurrence};
+
+const PLACEHOLDER_OPEN: &str = "{{";
+const PLACEHOLDER_CLOSE: &str = "}}";
+// TIMESTAMP_VARIABLES render with fractional seconds only if there are any, separated by a '.', so
+// they cannot be used in subjects, of which the '.' separated tokens must be validated.
+const TIMESTAMP_VARIABLES: [&str; 2] = ["scheduled_at", "transmitted_at"];
+
+// value returns the value of the variable with the given name, if the occurrence has it.
+fn value(occurrence: &Occurrence, name: &str) -> Option<String> {
+    match name {
+        "transmission_id" => Some(occurrence.transmission_id.to_string()),
+        "transmission_count" => Some(occurrence.transmission_count.to_string()),
+        "scheduled_at" => Some(occurrence.scheduled_at.to_rfc3339()),
+        "transmitted_at" => Some(occurrence.transmitted_at.to_rfc3339()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    NotUtf8,
+    UnclosedPlaceholder,
+    UnknownVariable(String),
+    TimestampInSubject(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::NotUtf8 => write!(f, "template is not valid utf-8"),
+            TemplateError::UnclosedPlaceholder => {
+                write!(f, "placeholder is not closed with '{PLACEHOLDER_CLOSE}'")
+            }
+            TemplateError::UnknownVariable(name) => write!(f, "unknown variable '{name}'"),
+            TemplateError::TimestampInSubject(name) => {
+                write!(f, "timestamp variable '{name}' cannot be used in a subject")
+            }
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+// render substitutes each placeholder in the template with the value of the variable it names.
+pub fn render(template: &str, occurrence: &Occurrence) -> Result<String, TemplateError> {
+    render_with(template, |name| {
+        value(occurrence, name).ok_or_else(|| TemplateError::UnknownVariable(name.to_string()))
+    })
+}
+
+fn render_with(
+    template: &str,
+    value: impl Fn(&str) -> Result<String, TemplateError>,
+) -> Result<String, TemplateError> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut remainder = template;
+
+    while let Some(start) = remainder.find(PLACEHOLDER_OPEN) {
+        rendered.push_str(&remainder[..start]);
+
+        let placeholder = &remainder[start + PLACEHOLDER_OPEN.len()..];
+        let end = placeholder
+            .find(PLACEHOLDER_CLOSE)
+            .ok_or(TemplateError::UnclosedPlaceholder)?;
+        let name = placeholder[..end].trim();
+        rendered.push_str(&value(name)?);
+
+        remainder = &placeholder[end + PLACEHOLDER_CLOSE.len()..];
+    }
+    rendered.push_str(remainder);
+
+    Ok(rendered)
+}
+
+// example_occurrence provides values of the greatest length a variable may render to, since no
+// occurrence exists yet: timestamps with nanoseconds and the greatest count.
+fn example_occurrence() -> Occurrence {
+    let timestamp =
+        DateTime::from_timestamp(1431648000, 999_999_999).expect("should be valid timestamp");
+
+    Occurrence {
+        transmission_id: Uuid::nil(),
+        transmission_count: u32::MAX,
+        scheduled_at: timestamp,
+        transmitted_at: timestamp,
+    }
+}
+
+// validate checks that the template only contains closed placeholders of known variables.
+pub fn validate(template: &str) -> Result<(), TemplateError> {
+    render_example(template).map(|_| ())
+}
+
+// validate_bytes checks the template like validate, provided it is valid utf-8.
+pub fn validate_bytes(template: &[u8]) -> Result<(), TemplateError> {
+    let template = std::str::from_utf8(template).map_err(|_| TemplateError::NotUtf8)?;
+
+    validate(template)
+}
+
+// render_example renders the template with example values, such that the result can be validated
+// like any non-templated field.
+pub fn render_example(template: &str) -> Result<String, TemplateError> {
+    render(template, &example_occurrence())
+}
+
+// render_subject_example renders the subject template like render_example, but rejects timestamp
+// variables, since they render to a varying number of tokens.
+pub fn render_subject_example(template: &str) -> Result<String, TemplateError> {
+    render_with(template, |name| {
+        if TIMESTAMP_VARIABLES.contains(&name) {
+            return Err(TemplateError::TimestampInSubject(name.to_string()));
+        }
+
+        value(&example_occurrence(), name)
+            .ok_or_else(|| TemplateError::UnknownVariable(name.to_string()))
+    })
+}
+
+// render_message renders the fields of templated messages. Other messages are returned as they are.
+pub fn render_message(
+    message: &Message,
+    occurrence: &Occurrence,
+) -> Result<Message, TemplateError> {
+    match message {
+        Message::NatsEvent(nats_event) if nats_event.templated => {
+            let payload =
+                std::str::from_utf8(&nats_event.payload).map_err(|_| TemplateError::NotUtf8)?;
+
+            Ok(Message::NatsEvent(NatsEvent {
+                subject: render(&nats_event.subject, occurrence)?.into(),
+                payload: render(payload, occurrence)?.into(),
+                templated: true,
+                format: nats_event.format.clone(),
+            }))
+        }
+        Message::FanOut(fan_out) => {
+            let destinations = fan_out
+                .destinations
+                .iter()
+                .map(|destination| render_message(destination, occurrence))
+                .collect::<Result<Vec<Message>, TemplateError>>()?;
+
+            Ok(Message::FanOut(FanOut::new(destinations)))
+        }
+        message => Ok(message.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::model::{PayloadFormat, RedisStreamAdd};
+
+    fn occurrence() -> Occurrence {
+        Occurrence {
+            transmission_id: uuid::uuid!("a23bfa0f-a906-429a-ab90-66322dfa72e5"),
+            transmission_count: 3,
+            scheduled_at: Utc.with_ymd_and_hms(2015, 5, 15, 0, 0, 0).unwrap(),
+            transmitted_at: Utc.with_ymd_and_hms(2015, 5, 15, 0, 0, 1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        struct TestCase {
+            name: String,
+            template: String,
+            expected_result: Result<String, TemplateError>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("without placeholders"),
+                template: String::from(r#"{"order": {"id": 1}}"#),
+                expected_result: Ok(String::from(r#"{"order": {"id": 1}}"#)),
+            },
+            TestCase {
+                name: String::from("all variables"),
+                template: String::from(
+                    "{{transmission_id}} {{transmission_count}} {{ scheduled_at }} {{transmitted_at}}",
+                ),
+                expected_result: Ok(String::from(
+                    "a23bfa0f-a906-429a-ab90-66322dfa72e5 3 2015-05-15T00:00:00+00:00 2015-05-15T00:00:01+00:00",
+                )),
+            },
+            TestCase {
+                name: String::from("subject"),
+                template: String::from("EVENTS.{{transmission_count}}"),
+                expected_result: Ok(String::from("EVENTS.3")),
+            },
+            TestCase {
+                name: String::from("unclosed placeholder"),
+                template: String::from("EVENTS.{{transmission_count"),
+                expected_result: Err(TemplateError::UnclosedPlaceholder),
+            },
+            TestCase {
+                name: String::from("unknown variable"),
+                template: String::from("EVENTS.{{unknown}}"),
+                expected_result: Err(TemplateError::UnknownVariable(String::from("unknown"))),
+            },
+        ];
+
+        for test_case in test_cases {
+            let result = render(&test_case.template, &occurrence());
+            assert_eq!(
+                result, test_case.expected_result,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_example() {
+        struct TestCase {
+            name: String,
+            template: String,
+            expected_example: Result<String, TemplateError>,
+            expected_subject_example: Result<String, TemplateError>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("identity"),
+                template: String::from("EVENTS.{{transmission_id}}.{{transmission_count}}"),
+                expected_example: Ok(String::from(
+                    "EVENTS.00000000-0000-0000-0000-000000000000.4294967295",
+                )),
+                expected_subject_example: Ok(String::from(
+                    "EVENTS.00000000-0000-0000-0000-000000000000.4294967295",
+                )),
+            },
+            TestCase {
+                name: String::from("timestamp"),
+                template: String::from("EVENTS.{{scheduled_at}}"),
+                expected_example: Ok(String::from("EVENTS.2015-05-15T00:00:00.999999999+00:00")),
+                expected_subject_example: Err(TemplateError::TimestampInSubject(String::from(
+                    "scheduled_at",
+                ))),
+            },
+            TestCase {
+                name: String::from("unknown variable"),
+                template: String::from("EVENTS.{{unknown}}"),
+                expected_example: Err(TemplateError::UnknownVariable(String::from("unknown"))),
+                expected_subject_example: Err(TemplateError::UnknownVariable(String::from(
+                    "unknown",
+                ))),
+            },
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                render_example(&test_case.template),
+                test_case.expected_example,
+                "test case failed: {}",
+                test_case.name
+            );
+            assert_eq!(
+                render_subject_example(&test_case.template),
+                test_case.expected_subject_example,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_message() {
+        let message = Message::FanOut(FanOut::new(vec![
+            Message::NatsEvent(NatsEvent {
+                subject: "EVENTS.templated".into(),
+                payload: "occurrence {{transmission_count}}".into(),
+                templated: true,
+                format: PayloadFormat::default(),
+            }),
+            Message::NatsEvent(NatsEvent::new(
+                "EVENTS.verbatim".into(),
+                "occurrence {{transmission_count}}".into(),
+            )),
+            Message::RedisStreamAdd(RedisStreamAdd::new(
+                "events".into(),
+                std::collections::BTreeMap::from([("payload".into(), "arbitrary".into())]),
+                None,
+            )),
+        ]));
+        let expected_message = Message::FanOut(FanOut::new(vec![
+            Message::NatsEvent(NatsEvent {
+                subject: "EVENTS.templated".into(),
+                payload: "occurrence 3".into(),
+                templated: true,
+                format: PayloadFormat::default(),
+            }),
+            Message::NatsEvent(NatsEvent::new(
+                "EVENTS.verbatim".into(),
+                "occurrence {{transmission_count}}".into(),
+            )),
+            Message::RedisStreamAdd(RedisStreamAdd::new(
+                "events".into(),
+                std::collections::BTreeMap::from([("payload".into(), "arbitrary".into())]),
+                None,
+            )),
+        ]));
+
+        let rendered_message =
+            render_message(&message, &occurrence()).expect("message should render");
+        assert_eq!(rendered_message, expected_message);
+    }
+}
@@ -0,0 +1,333 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use log::{error, info, warn};
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use serde::Deserialize;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::ReceiverStream;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::model::Principal;
+
+// HANDSHAKE_TIMEOUT bounds the time a client may take to complete the handshake, such that
+// stalled clients do not hold on to their connection.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    // cert_path and key_path are the paths to the PEM encoded certificate chain and private key
+    // of the server.
+    pub cert_path: String,
+    pub key_path: String,
+    // client_ca_path is the path to the PEM encoded certificates of the authorities that client
+    // certificates must be issued by. If set, clients are required to present a certificate.
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+}
+
+// Acceptor performs TLS handshakes with the certificates on disk. The files are reloaded once
+// they are modified, such that rotated certificates take effect without a restart.
+#[derive(Clone)]
+pub struct Acceptor {
+    config: Config,
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    modified: Vec<Option<SystemTime>>,
+    server_config: Arc<ServerConfig>,
+}
+
+impl Acceptor {
+    pub fn new(config: Config) -> Result<Acceptor, Box<dyn Error>> {
+        let modified = modified(&config);
+        let server_config = Arc::new(load_server_config(&config)?);
+
+        Ok(Acceptor {
+            config,
+            state: Arc::new(Mutex::new(State {
+                modified,
+                server_config,
+            })),
+        })
+    }
+
+    // current returns the server config of the certificates on disk, reloading them if any of the
+    // files was modified since. If reloading fails, e.g. because the files are being replaced, the
+    // previous certificates remain in use.
+    fn current(&self) -> Arc<ServerConfig> {
+        let mut state = self
+            .state
+            .lock()
+            .expect("tls acceptor lock should not be poisoned");
+
+        let modified = modified(&self.config);
+        if modified != state.modified {
+            match load_server_config(&self.config) {
+                Ok(server_config) => {
+                    info!("Reloaded tls certificates from {}.", self.config.cert_path);
+                    state.server_config = Arc::new(server_config);
+                    state.modified = modified;
+                }
+                Err(err) => error!("Failed to reload tls certificates: {err}"),
+            }
+        }
+
+        state.server_config.clone()
+    }
+
+    pub async fn accept(&self, stream: TcpStream) -> io::Result<TlsStream<TcpStream>> {
+        match tokio::time::timeout(
+            HANDSHAKE_TIMEOUT,
+            TlsAcceptor::from(self.current()).accept(stream),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "tls handshake timed out",
+            )),
+        }
+    }
+}
+
+// incoming accepts connections from the listener and streams them once their handshake
+// completed. Handshakes run concurrently, such that a slow client does not delay the others.
+// Failed handshakes are logged and dropped, rather than ending the stream.
+pub fn incoming(
+    listener: TcpListener,
+    acceptor: Acceptor,
+) -> ReceiverStream<io::Result<TlsStream<TcpStream>>> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(32);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, remote_address) = tokio::select! {
+                // The server stopped listening once it dropped the receiver.
+                _ = sender.closed() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        warn!("Failed to accept connection: {err}");
+                        continue;
+                    }
+                },
+            };
+
+            let acceptor = acceptor.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                match acceptor.accept(stream).await {
+                    Ok(tls_stream) => {
+                        let _ = sender.send(Ok(tls_stream)).await;
+                    }
+                    Err(err) => warn!("Failed tls handshake with {remote_address}: {err}"),
+                }
+            });
+        }
+    });
+
+    ReceiverStream::new(receiver)
+}
+
+fn modified(config: &Config) -> Vec<Option<SystemTime>> {
+    [Some(&config.cert_path), Some(&config.key_path)]
+        .into_iter()
+        .chain([config.client_ca_path.as_ref()])
+        .flatten()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+fn load_server_config(config: &Config) -> Result<ServerConfig, Box<dyn Error>> {
+    let certs = load_certs(&config.cert_path)?;
+    let key = load_key(&config.key_path)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let mut server_config = match &config.client_ca_path {
+        Some(client_ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(client_ca_path)? {
+                roots
+                    .add(&cert)
+                    .map_err(|err| format!("adding client ca from {client_ca_path}: {err}"))?;
+            }
+
+            builder
+                .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+                .with_single_cert(certs, key)?
+        }
+        None => builder.with_no_client_auth().with_single_cert(certs, key)?,
+    };
+    // gRPC is served over HTTP/2 only.
+    server_config.alpn_protocols = vec![b"h2".to_vec()];
+
+    Ok(server_config)
+}
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>, Box<dyn Error>> {
+    let mut reader = BufReader::new(
+        File::open(path).map_err(|err| format!("reading certificates from {path}: {err}"))?,
+    );
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut reader)
+        .map_err(|err| format!("parsing certificates from {path}: {err}"))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    if certs.is_empty() {
+        return Err(format!("{path} contains no certificates").into());
+    }
+
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKey, Box<dyn Error>> {
+    let mut reader = BufReader::new(
+        File::open(path).map_err(|err| format!("reading private key from {path}: {err}"))?,
+    );
+
+    for item in rustls_pemfile::read_all(&mut reader)
+        .map_err(|err| format!("parsing private key from {path}: {err}"))?
+    {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => continue,
+        }
+    }
+
+    Err(format!("{path} contains no private key").into())
+}
+
+// principal identifies the client by the subject of its DER encoded certificate: its common name,
+// or else the distinguished name as a whole.
+pub fn principal(certificate: &[u8]) -> Option<Principal> {
+    let (_, certificate) = X509Certificate::from_der(certificate).ok()?;
+    let subject = certificate.subject();
+
+    let name = match subject
+        .iter_common_name()
+        .next()
+        .and_then(|common_name| common_name.as_str().ok())
+    {
+        Some(common_name) => common_name.to_string(),
+        None => subject.to_string(),
+    };
+
+    match name.is_empty() {
+        true => None,
+        false => Some(Principal { name }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = "./tests/tls";
+
+    fn fixture(name: &str) -> String {
+        format!("{FIXTURES}/{name}")
+    }
+
+    #[test]
+    fn test_principal() {
+        let client_certs = load_certs(&fixture("client.pem")).expect("certificate should load");
+
+        assert_eq!(
+            principal(&client_certs[0].0),
+            Some(Principal {
+                name: String::from("orders")
+            })
+        );
+        assert_eq!(principal(b"not a certificate"), None);
+    }
+
+    #[test]
+    fn test_load_server_config() {
+        let valid = Config {
+            cert_path: fixture("server.pem"),
+            key_path: fixture("server.key"),
+            client_ca_path: Some(fixture("ca.pem")),
+        };
+        assert!(load_server_config(&valid).is_ok());
+
+        let missing_key = Config {
+            key_path: fixture("missing.key"),
+            ..valid.clone()
+        };
+        assert!(load_server_config(&missing_key).is_err());
+
+        let key_as_client_ca = Config {
+            client_ca_path: Some(fixture("server.key")),
+            ..valid
+        };
+        assert!(load_server_config(&key_as_client_ca).is_err());
+    }
+
+    #[test]
+    fn test_reload() {
+        let directory = std::env::temp_dir().join(format!("tls_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&directory).expect("directory should be created");
+        let cert_path = directory.join("tls.crt");
+        let key_path = directory.join("tls.key");
+        std::fs::copy(fixture("server.pem"), &cert_path).expect("certificate should be copied");
+        std::fs::copy(fixture("server.key"), &key_path).expect("key should be copied");
+
+        let acceptor = Acceptor::new(Config {
+            cert_path: cert_path.to_string_lossy().to_string(),
+            key_path: key_path.to_string_lossy().to_string(),
+            client_ca_path: None,
+        })
+        .expect("acceptor should be constructed");
+
+        let loaded = Arc::as_ptr(&acceptor.current());
+        assert_eq!(
+            Arc::as_ptr(&acceptor.current()),
+            loaded,
+            "unmodified files reloaded"
+        );
+
+        // A partially written file fails to load, so the previous certificates remain in use.
+        let later = SystemTime::now() + Duration::from_secs(60);
+        std::fs::write(&cert_path, "").expect("certificate should be truncated");
+        File::options()
+            .write(true)
+            .open(&cert_path)
+            .and_then(|file| file.set_modified(later))
+            .expect("modification time should be set");
+        assert_eq!(
+            Arc::as_ptr(&acceptor.current()),
+            loaded,
+            "invalid files loaded"
+        );
+
+        std::fs::copy(fixture("server.pem"), &cert_path).expect("certificate should be copied");
+        File::options()
+            .write(true)
+            .open(&cert_path)
+            .and_then(|file| file.set_modified(later + Duration::from_secs(60)))
+            .expect("modification time should be set");
+        assert_ne!(
+            Arc::as_ptr(&acceptor.current()),
+            loaded,
+            "modified files not reloaded"
+        );
+
+        std::fs::remove_dir_all(directory).expect("directory should be removed");
+    }
+}
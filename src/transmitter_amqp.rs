@@ -0,0 +1,217 @@
+use std::error::Error;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lapin::options::BasicPublishOptions;
+use lapin::publisher_confirm::Confirmation;
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel};
+use log::info;
+
+use crate::contract::Transmitter;
+use crate::model::{AmqpProperties, Message, Occurrence};
+
+// CONFIRM_TIMEOUT bounds the wait for the broker to confirm a publication. A missing confirm
+// fails the transmission, such that it is rescheduled.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+// DELIVERY_MODE_PERSISTENT is the AMQP delivery mode that stores the message on disk.
+const DELIVERY_MODE_PERSISTENT: u8 = 2;
+
+pub struct AmqpPublisher {
+    channel: Channel,
+}
+
+impl AmqpPublisher {
+    // new expects a channel in confirm mode, as returned by amqp::connect_to_amqp.
+    pub fn new(channel: Channel) -> AmqpPublisher {
+        AmqpPublisher { channel }
+    }
+}
+
+fn basic_properties(properties: &AmqpProperties) -> BasicProperties {
+    let mut basic_properties = BasicProperties::default();
+    if let Some(content_type) = &properties.content_type {
+        basic_properties = basic_properties.with_content_type(content_type.as_str().into());
+    }
+    if let Some(correlation_id) = &properties.correlation_id {
+        basic_properties = basic_properties.with_correlation_id(correlation_id.as_str().into());
+    }
+    if properties.persistent {
+        basic_properties = basic_properties.with_delivery_mode(DELIVERY_MODE_PERSISTENT);
+    }
+    if !properties.headers.is_empty() {
+        let mut headers = FieldTable::default();
+        for (key, value) in &properties.headers {
+            headers.insert(
+                key.as_str().into(),
+                AMQPValue::LongString(value.as_str().into()),
+            );
+        }
+        basic_properties = basic_properties.with_headers(headers);
+    }
+
+    basic_properties
+}
+
+#[async_trait]
+impl Transmitter for AmqpPublisher {
+    async fn transmit(
+        &self,
+        message: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::AmqpPublish(amqp_publish) => {
+                let publisher_confirm = self
+                    .channel
+                    .basic_publish(
+                        &amqp_publish.exchange,
+                        &amqp_publish.routing_key,
+                        // Mandatory makes the broker return the message if it cannot be routed
+                        // to any queue, rather than silently dropping it.
+                        BasicPublishOptions {
+                            mandatory: true,
+                            ..BasicPublishOptions::default()
+                        },
+                        &amqp_publish.body,
+                        basic_properties(&amqp_publish.properties),
+                    )
+                    .await?;
+
+                let confirmation =
+                    match tokio::time::timeout(CONFIRM_TIMEOUT, publisher_confirm).await {
+                        Ok(confirmation) => confirmation?,
+                        Err(_) => return Err("amqp publisher confirm not received in time".into()),
+                    };
+
+                match confirmation {
+                    Confirmation::Ack(None) => {
+                        info!(
+                            "transmitted amqp message to exchange '{}' with routing key '{}'",
+                            amqp_publish.exchange, amqp_publish.routing_key
+                        );
+
+                        Ok(())
+                    }
+                    Confirmation::Ack(Some(_)) => Err(format!(
+                        "amqp message returned as unroutable by exchange '{}' with routing key '{}'",
+                        amqp_publish.exchange, amqp_publish.routing_key
+                    )
+                    .into()),
+                    Confirmation::Nack(_) => Err("amqp message not acknowledged by broker".into()),
+                    Confirmation::NotRequested => {
+                        Err("amqp channel is not in publisher confirm mode".into())
+                    }
+                }
+            }
+            _ => Err("amqp publisher can only transmit amqp messages".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use lapin::options::{BasicGetOptions, QueueDeclareOptions};
+
+    use crate::amqp::{connect_to_amqp, Config};
+    use crate::model::AmqpPublish;
+
+    fn config() -> Config {
+        Config {
+            host: "localhost".to_string(),
+            port: 5672,
+            vhost: "/".to_string(),
+            username: "guest".to_string(),
+            password: "guest".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with rabbitmq, which is expected to run
+    // and be accessible.
+    //
+    // Run with `docker run -p 5672:5672 -ti rabbitmq:3`.
+    async fn test_transmitter_confirmed() {
+        let channel = connect_to_amqp(config())
+            .await
+            .expect("Amqp connection failed. Is rabbitmq running on port 5672?");
+
+        // A server-named, exclusive queue is bound to the default exchange by its name.
+        let queue = channel
+            .queue_declare(
+                "",
+                QueueDeclareOptions {
+                    exclusive: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .expect("queue should be declared");
+
+        let transmitter = AmqpPublisher::new(channel.clone());
+        transmitter
+            .transmit(
+                Message::AmqpPublish(AmqpPublish::new(
+                    String::new(),
+                    queue.name().to_string(),
+                    AmqpProperties {
+                        content_type: Some("application/json".to_string()),
+                        correlation_id: None,
+                        persistent: true,
+                        headers: BTreeMap::from([("origin".to_string(), "transmit".to_string())]),
+                    },
+                    "{\"order\": 1}".into(),
+                )),
+                &Occurrence::default(),
+            )
+            .await
+            .expect("transmission should be confirmed");
+
+        let delivery = channel
+            .basic_get(queue.name().as_str(), BasicGetOptions { no_ack: true })
+            .await
+            .expect("getting from queue should succeed")
+            .expect("queue should contain the transmitted message");
+
+        assert_eq!(delivery.data, b"{\"order\": 1}");
+        assert_eq!(
+            delivery.properties.content_type(),
+            &Some("application/json".into())
+        );
+        assert_eq!(
+            delivery.properties.delivery_mode(),
+            &Some(DELIVERY_MODE_PERSISTENT)
+        );
+    }
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with rabbitmq, which is expected to run
+    // and be accessible.
+    //
+    // Run with `docker run -p 5672:5672 -ti rabbitmq:3`.
+    async fn test_transmitter_unroutable() {
+        let channel = connect_to_amqp(config())
+            .await
+            .expect("Amqp connection failed. Is rabbitmq running on port 5672?");
+
+        let transmitter = AmqpPublisher::new(channel);
+        let result = transmitter
+            .transmit(
+                Message::AmqpPublish(AmqpPublish::new(
+                    String::new(),
+                    "queue.that.does.not.exist".to_string(),
+                    AmqpProperties::default(),
+                    "arbitrary body".into(),
+                )),
+                &Occurrence::default(),
+            )
+            .await;
+
+        assert!(result.is_err(), "unroutable message must fail to transmit");
+    }
+}
@@ -0,0 +1,212 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, MultiPart, SinglePart};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use log::info;
+
+use crate::contract::Transmitter;
+use crate::model::{Email, Message, Occurrence, PermanentTransmitError};
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(transport: AsyncSmtpTransport<Tokio1Executor>) -> SmtpMailer {
+        SmtpMailer { transport }
+    }
+}
+
+fn build_email(email: &Email) -> Result<lettre::Message, Box<dyn Error + Send + Sync>> {
+    let mut builder = lettre::Message::builder()
+        .from(email.from.parse()?)
+        .subject(&email.subject);
+    for to in &email.to {
+        builder = builder.to(to.parse()?);
+    }
+
+    let message = match (&email.text_body, &email.html_body) {
+        (Some(text_body), Some(html_body)) => builder.multipart(
+            MultiPart::alternative_plain_html(text_body.clone(), html_body.clone()),
+        )?,
+        (Some(text_body), None) => builder.singlepart(SinglePart::plain(text_body.clone()))?,
+        (None, Some(html_body)) => builder.singlepart(SinglePart::html(html_body.clone()))?,
+        (None, None) => builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(String::new())?,
+    };
+
+    Ok(message)
+}
+
+#[async_trait]
+impl Transmitter for SmtpMailer {
+    async fn transmit(
+        &self,
+        message: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::Email(email) => {
+                let email_message = build_email(&email)?;
+
+                match self.transport.send(email_message).await {
+                    Ok(_) => {
+                        info!(
+                            "transmitted email to {} recipients with subject '{}'",
+                            email.to.len(),
+                            email.subject
+                        );
+
+                        Ok(())
+                    }
+                    // A 5xx reply, e.g. an unknown recipient, will be repeated on retry. A 4xx
+                    // reply, e.g. a full mailbox or greylisting, may succeed later.
+                    Err(err) if err.is_permanent() => {
+                        Err(Box::new(PermanentTransmitError::new(err.to_string())))
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            _ => Err("smtp mailer can only transmit emails".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    use crate::smtp::{connect_to_smtp, Config};
+
+    fn email(subject: &str) -> Email {
+        Email::new(
+            "Transmit <transmit@example.com>".to_string(),
+            vec!["someone@example.com".to_string()],
+            subject.to_string(),
+            Some("Do not forget.".to_string()),
+            Some("<p>Do not forget.</p>".to_string()),
+        )
+    }
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with mailhog, which is expected to run and
+    // be accessible.
+    //
+    // Run with `docker run -p 1025:1025 -p 8025:8025 -ti mailhog/mailhog`.
+    async fn test_transmitter_delivers() {
+        let subject = format!("Reminder {}", uuid::Uuid::new_v4());
+
+        let transport = connect_to_smtp(Config {
+            host: "localhost".to_string(),
+            port: 1025,
+            starttls: false,
+            username: None,
+            password: None,
+        })
+        .await
+        .expect("Smtp connection failed. Is mailhog running on port 1025?");
+        let mailer = SmtpMailer::new(transport);
+        mailer
+            .transmit(Message::Email(email(&subject)), &Occurrence::default())
+            .await
+            .expect("transmission should succeed");
+
+        let body =
+            reqwest::get("http://localhost:8025/api/v2/search?kind=containing&query=Reminder")
+                .await
+                .expect("mailhog api should respond")
+                .text()
+                .await
+                .expect("mailhog api should respond with a body");
+        let messages: serde_json::Value =
+            serde_json::from_str(&body).expect("mailhog api should respond with json");
+        let delivered = messages["items"]
+            .as_array()
+            .expect("search result should contain items")
+            .iter()
+            .any(|item| item["Content"]["Headers"]["Subject"][0] == subject.as_str());
+        assert!(
+            delivered,
+            "email with subject '{subject}' was not delivered"
+        );
+    }
+
+    // serve_smtp accepts a single session, replying to the recipient with the given reply.
+    async fn serve_smtp(recipient_reply: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("listener should bind");
+        let port = listener
+            .local_addr()
+            .expect("listener should have address")
+            .port();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("should accept");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            writer.write_all(b"220 localhost\r\n").await.unwrap();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let reply = match line.to_uppercase() {
+                    command if command.starts_with("EHLO") => "250 localhost\r\n",
+                    command if command.starts_with("MAIL FROM") => "250 OK\r\n",
+                    command if command.starts_with("RCPT TO") => recipient_reply,
+                    command if command.starts_with("QUIT") => "221 Bye\r\n",
+                    _ => "250 OK\r\n",
+                };
+                writer.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_transmitter_reply_classification() {
+        struct TestCase {
+            name: String,
+            recipient_reply: &'static str,
+            expected_permanent: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("transient"),
+                recipient_reply: "451 Try again later\r\n",
+                expected_permanent: false,
+            },
+            TestCase {
+                name: String::from("permanent"),
+                recipient_reply: "550 No such user\r\n",
+                expected_permanent: true,
+            },
+        ];
+
+        for test_case in test_cases {
+            let port = serve_smtp(test_case.recipient_reply).await;
+            let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(port)
+                .timeout(Some(Duration::from_secs(1)))
+                .build();
+
+            let err = SmtpMailer::new(transport)
+                .transmit(Message::Email(email("Reminder")), &Occurrence::default())
+                .await
+                .expect_err("transmission should fail");
+            assert_eq!(
+                err.is::<PermanentTransmitError>(),
+                test_case.expected_permanent,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::error::Error;
+use std::io;
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use log::info;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+use crate::contract::Transmitter;
+use crate::model::{Exec, Message, Occurrence};
+
+// MAX_OUTPUT_LENGTH is the number of bytes of stdout and stderr that is logged per occurrence.
+const MAX_OUTPUT_LENGTH: usize = 4096;
+
+// ExecRunner runs allowlisted commands as child processes of this program.
+pub struct ExecRunner {
+    allowlist: Vec<String>,
+}
+
+impl ExecRunner {
+    pub fn new(allowlist: Vec<String>) -> ExecRunner {
+        ExecRunner { allowlist }
+    }
+
+    async fn run(&self, exec: &Exec) -> Result<Output, Box<dyn Error + Send + Sync>> {
+        // The allowlist is also validated when scheduling, but it may have shrunk since.
+        if !self.allowlist.contains(&exec.command) {
+            return Err(format!("command '{}' is not allowlisted", exec.command).into());
+        }
+
+        let mut child = Command::new(&exec.command)
+            .args(&exec.arguments)
+            .env_clear()
+            .envs(&exec.environment)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Dropping the child on timeout kills the process.
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdout = child.stdout.take().expect("stdout should be piped");
+        let stderr = child.stderr.take().expect("stderr should be piped");
+
+        let running = async {
+            let (status, stdout, stderr) =
+                tokio::try_join!(child.wait(), capture(stdout), capture(stderr))?;
+
+            Ok::<Output, io::Error>(Output {
+                exit_code: status.code(),
+                stdout,
+                stderr,
+            })
+        };
+
+        match tokio::time::timeout(exec.timeout, running).await {
+            Ok(output) => Ok(output?),
+            Err(_) => Err(format!(
+                "command '{}' killed after timeout of {:?}",
+                exec.command, exec.timeout
+            )
+            .into()),
+        }
+    }
+}
+
+// capture reads at most MAX_OUTPUT_LENGTH bytes of the output, and discards the rest rather than
+// buffering it, such that the process is not blocked on a full pipe.
+async fn capture(mut output: impl AsyncRead + Unpin) -> io::Result<String> {
+    let mut captured = Vec::new();
+    (&mut output)
+        .take(MAX_OUTPUT_LENGTH as u64)
+        .read_to_end(&mut captured)
+        .await?;
+    let discarded = tokio::io::copy(&mut output, &mut tokio::io::sink()).await?;
+
+    let captured = String::from_utf8_lossy(&captured);
+    if discarded > 0 {
+        return Ok(format!("{captured}..."));
+    }
+
+    Ok(captured.into_owned())
+}
+
+// Output is what is captured of a finished process.
+#[derive(Debug)]
+struct Output {
+    // exit_code is None if the process was terminated by a signal.
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+#[async_trait]
+impl Transmitter for ExecRunner {
+    async fn transmit(
+        &self,
+        message: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::Exec(exec) => {
+                let output = self.run(&exec).await?;
+
+                match output.exit_code {
+                    Some(0) => {
+                        info!(
+                            "transmitted exec of command '{}'; stdout: {:?}; stderr: {:?}",
+                            exec.command, output.stdout, output.stderr
+                        );
+
+                        Ok(())
+                    }
+                    exit_code => Err(format!(
+                        "command '{}' exited with code {:?}; stdout: {:?}; stderr: {:?}",
+                        exec.command, exit_code, output.stdout, output.stderr
+                    )
+                    .into()),
+                }
+            }
+            _ => Err("exec runner can only transmit exec messages".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn runner() -> ExecRunner {
+        ExecRunner::new(vec![
+            "/bin/sh".to_string(),
+            "/bin/false".to_string(),
+            "/bin/sleep".to_string(),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_run_captures_output() {
+        let output = runner()
+            .run(&Exec::new(
+                "/bin/sh".to_string(),
+                vec![
+                    "-c".to_string(),
+                    "echo \"$GREETING\"; echo failure >&2".to_string(),
+                ],
+                BTreeMap::from([("GREETING".to_string(), "hello".to_string())]),
+                Duration::from_secs(5),
+            ))
+            .await
+            .expect("command should run");
+
+        assert_eq!(output.exit_code, Some(0));
+        assert_eq!(output.stdout, "hello\n");
+        assert_eq!(output.stderr, "failure\n");
+    }
+
+    #[tokio::test]
+    async fn test_run_truncates_output() {
+        let output = runner()
+            .run(&Exec::new(
+                "/bin/sh".to_string(),
+                vec![
+                    "-c".to_string(),
+                    // The output exceeds the capacity of the pipe, so must be drained.
+                    format!("printf '%0{}d' 0", MAX_OUTPUT_LENGTH * 256),
+                ],
+                BTreeMap::new(),
+                Duration::from_secs(5),
+            ))
+            .await
+            .expect("command should run");
+
+        assert_eq!(output.exit_code, Some(0));
+        assert_eq!(output.stdout.len(), MAX_OUTPUT_LENGTH + "...".len());
+    }
+
+    #[tokio::test]
+    async fn test_transmit() {
+        struct TestCase {
+            name: String,
+            exec: Exec,
+            expected_ok: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("zero exit code"),
+                exec: Exec::new(
+                    "/bin/sh".to_string(),
+                    vec!["-c".to_string(), "exit 0".to_string()],
+                    BTreeMap::new(),
+                    Duration::from_secs(5),
+                ),
+                expected_ok: true,
+            },
+            TestCase {
+                name: String::from("non-zero exit code"),
+                exec: Exec::new(
+                    "/bin/false".to_string(),
+                    vec![],
+                    BTreeMap::new(),
+                    Duration::from_secs(5),
+                ),
+                expected_ok: false,
+            },
+            TestCase {
+                name: String::from("timeout"),
+                exec: Exec::new(
+                    "/bin/sleep".to_string(),
+                    vec!["10".to_string()],
+                    BTreeMap::new(),
+                    Duration::from_millis(100),
+                ),
+                expected_ok: false,
+            },
+            TestCase {
+                name: String::from("not allowlisted"),
+                exec: Exec::new(
+                    "/bin/true".to_string(),
+                    vec![],
+                    BTreeMap::new(),
+                    Duration::from_secs(5),
+                ),
+                expected_ok: false,
+            },
+        ];
+
+        for test_case in test_cases {
+            let result = runner()
+                .transmit(Message::Exec(test_case.exec), &Occurrence::default())
+                .await;
+            assert_eq!(
+                result.is_ok(),
+                test_case.expected_ok,
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use bytes::{Buf, BufMut, Bytes};
+use log::info;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Request, Status};
+
+use crate::contract::Transmitter;
+use crate::model::{GrpcCall, Message, Occurrence};
+
+// GrpcCaller makes unary calls with request bodies that are already protobuf encoded. The
+// channels to endpoints are reused across calls.
+#[derive(Default)]
+pub struct GrpcCaller {
+    channels: Mutex<HashMap<String, Channel>>,
+}
+
+impl GrpcCaller {
+    pub fn new() -> GrpcCaller {
+        GrpcCaller::default()
+    }
+
+    fn channel(&self, endpoint: &str) -> Result<Channel, Box<dyn Error + Send + Sync>> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| "grpc channels lock poisoned")?;
+
+        if let Some(channel) = channels.get(endpoint) {
+            return Ok(channel.clone());
+        }
+
+        let mut channel_endpoint = Endpoint::from_shared(endpoint.to_string())?;
+        if endpoint.starts_with("https://") {
+            channel_endpoint = channel_endpoint.tls_config(ClientTlsConfig::new())?;
+        }
+        // Connecting lazily defers connection errors to the call, such that they fail the
+        // transmission rather than the construction of the channel.
+        let channel = channel_endpoint.connect_lazy();
+        channels.insert(endpoint.to_string(), channel.clone());
+
+        Ok(channel)
+    }
+
+    async fn call(&self, grpc_call: &GrpcCall) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        let path = PathAndQuery::from_str(&grpc_call.method)?;
+
+        let mut request = Request::new(grpc_call.body.clone());
+        request.set_timeout(grpc_call.deadline);
+        for (key, value) in &grpc_call.metadata {
+            request.metadata_mut().insert(
+                AsciiMetadataKey::from_str(key)?,
+                AsciiMetadataValue::from_str(value)?,
+            );
+        }
+
+        let mut client = tonic::client::Grpc::new(self.channel(&grpc_call.endpoint)?);
+        client.ready().await?;
+
+        // A status other than OK is returned as error, which fails the transmission.
+        let response = client.unary(request, path, BytesCodec).await?;
+
+        Ok(response.into_inner())
+    }
+}
+
+#[async_trait]
+impl Transmitter for GrpcCaller {
+    async fn transmit(
+        &self,
+        message: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::GrpcCall(grpc_call) => {
+                let response = self.call(&grpc_call).await?;
+
+                info!(
+                    "transmitted grpc call to '{}{}' with response of {} bytes",
+                    grpc_call.endpoint,
+                    grpc_call.method,
+                    response.len()
+                );
+
+                Ok(())
+            }
+            _ => Err("grpc caller can only transmit grpc calls".into()),
+        }
+    }
+}
+
+// BytesCodec passes encoded messages through unaltered, since the request body is encoded by the
+// scheduling client and the response is not interpreted.
+#[derive(Clone, Copy, Default)]
+struct BytesCodec;
+
+impl Codec for BytesCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
+    type Encoder = BytesCodec;
+    type Decoder = BytesCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesCodec
+    }
+}
+
+impl Encoder for BytesCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        dst.put(item);
+
+        Ok(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use prost::Message as _;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::contract::MockScheduler;
+    use crate::grpc::{self, proto};
+
+    // serve_health starts the gRPC server of this program, which serves the health check that the
+    // calls are made to.
+    async fn serve_health(port: u16) -> CancellationToken {
+        let token = CancellationToken::new();
+        let server = grpc::GrpcServer::new(
+            grpc::Config { port, tls: None },
+            Arc::new(MockScheduler::new()),
+        );
+        let server_token = token.clone();
+        tokio::spawn(async move { server.serve(server_token).await.expect("server must serve") });
+
+        // Allow the server to start listening.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        token
+    }
+
+    #[tokio::test]
+    async fn test_transmitter_call() {
+        let port = 50151;
+        let token = serve_health(port).await;
+
+        let request = proto::HealthCheckRequest {
+            service: "transmit".to_string(),
+        };
+
+        let caller = GrpcCaller::new();
+        let response = caller
+            .call(&GrpcCall::new(
+                format!("http://localhost:{port}"),
+                "/transmit.Health/Check".to_string(),
+                request.encode_to_vec().into(),
+                Duration::from_secs(1),
+                BTreeMap::from([("x-origin".to_string(), "transmit".to_string())]),
+            ))
+            .await
+            .expect("call should succeed");
+
+        let response = proto::HealthCheckResponse::decode(response)
+            .expect("response should be a health check response");
+        assert_eq!(
+            response.status,
+            proto::health_check_response::ServingStatus::Serving as i32
+        );
+
+        token.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_transmitter_non_ok_status() {
+        let port = 50152;
+        let token = serve_health(port).await;
+
+        let caller = GrpcCaller::new();
+        let result = caller
+            .transmit(
+                Message::GrpcCall(GrpcCall::new(
+                    format!("http://localhost:{port}"),
+                    "/transmit.Health/Unknown".to_string(),
+                    Bytes::new(),
+                    Duration::from_secs(1),
+                    BTreeMap::new(),
+                )),
+                &Occurrence::default(),
+            )
+            .await;
+        assert!(
+            result.is_err(),
+            "unimplemented status must fail transmission"
+        );
+
+        token.cancel();
+    }
+}
@@ -0,0 +1,137 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use log::info;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+
+use crate::contract::Transmitter;
+use crate::model::{Message, Occurrence};
+
+pub struct KafkaProducer {
+    producer: FutureProducer,
+}
+
+impl KafkaProducer {
+    pub fn new(producer: FutureProducer) -> KafkaProducer {
+        KafkaProducer { producer }
+    }
+}
+
+#[async_trait]
+impl Transmitter for KafkaProducer {
+    async fn transmit(
+        &self,
+        message: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::KafkaRecord(kafka_record) => {
+                let mut headers = OwnedHeaders::new_with_capacity(kafka_record.headers.len());
+                for (key, value) in &kafka_record.headers {
+                    headers = headers.insert(Header {
+                        key,
+                        value: Some(value.as_ref()),
+                    });
+                }
+
+                let mut record = FutureRecord::to(&kafka_record.topic)
+                    .payload(kafka_record.value.as_ref())
+                    .headers(headers);
+                if let Some(key) = &kafka_record.key {
+                    record = record.key(key.as_ref());
+                }
+
+                // The delivery future resolves once the brokers acknowledged the record, or the
+                // delivery timeout of the producer passed.
+                let (partition, offset) = self
+                    .producer
+                    .send(record, Timeout::Never)
+                    .await
+                    .map_err(|(err, _)| err)?;
+
+                info!(
+                    "transmitted kafka record to topic '{}' partition {partition} offset {offset}",
+                    kafka_record.topic
+                );
+
+                Ok(())
+            }
+            _ => Err("kafka producer can only transmit kafka records".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use rdkafka::config::ClientConfig;
+    use rdkafka::consumer::{Consumer, StreamConsumer};
+    use rdkafka::message::Headers;
+    use rdkafka::Message as _;
+
+    use crate::kafka::{create_producer, Config};
+    use crate::model::KafkaRecord;
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with a single node kafka compatible broker,
+    // which is expected to run and be accessible, and to create topics automatically.
+    //
+    // Run with `docker run -p 9092:9092 -ti redpandadata/redpanda redpanda start --mode
+    // dev-container --kafka-addr 0.0.0.0:9092 --advertise-kafka-addr localhost:9092`.
+    async fn test_transmitter_acknowledged() {
+        let topic = format!("transmit-test-{}", uuid::Uuid::new_v4());
+
+        let producer = create_producer(Config {
+            brokers: vec!["localhost:9092".to_string()],
+            client_id: "transmit-test".to_string(),
+        })
+        .await
+        .expect("Kafka connection failed. Is a broker running on port 9092?");
+        let transmitter = KafkaProducer::new(producer);
+        transmitter
+            .transmit(
+                Message::KafkaRecord(KafkaRecord::new(
+                    topic.clone(),
+                    Some("customer-1".into()),
+                    BTreeMap::from([("origin".to_string(), "transmit".into())]),
+                    "structured bytes containing order information".into(),
+                )),
+                &Occurrence::default(),
+            )
+            .await
+            .expect("transmission should be acknowledged");
+
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", "localhost:9092")
+            .set("group.id", uuid::Uuid::new_v4().to_string())
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .expect("consumer should be created");
+        consumer
+            .subscribe(&[&topic])
+            .expect("subscribing should succeed");
+
+        let record = tokio::time::timeout(Duration::from_secs(10), consumer.recv())
+            .await
+            .expect("consumer did not receive record in time; timeout reached")
+            .expect("consuming should succeed");
+
+        assert_eq!(record.key(), Some("customer-1".as_bytes()));
+        assert_eq!(
+            record.payload(),
+            Some("structured bytes containing order information".as_bytes())
+        );
+        let header = record
+            .headers()
+            .expect("record should contain headers")
+            .get(0);
+        assert_eq!(header.key, "origin");
+        assert_eq!(header.value, Some("transmit".as_bytes()));
+    }
+}
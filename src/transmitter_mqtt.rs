@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::info;
+use rumqttc::QoS;
+use tokio::sync::Mutex;
+
+use crate::contract::Transmitter;
+use crate::model::{Message, Occurrence, QualityOfService};
+use crate::mqtt::Acknowledgements;
+
+// ACKNOWLEDGEMENT_TIMEOUT bounds the time to wait for the broker to acknowledge a publication, after
+// which the transmission fails and is retried.
+const ACKNOWLEDGEMENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct MqttPublisher {
+    client: rumqttc::AsyncClient,
+    acknowledgements: Arc<Acknowledgements>,
+    // queueing serialises queueing publications, such that they are expected in the same order.
+    queueing: Mutex<()>,
+}
+
+impl MqttPublisher {
+    pub fn new(
+        client: rumqttc::AsyncClient,
+        acknowledgements: Arc<Acknowledgements>,
+    ) -> MqttPublisher {
+        MqttPublisher {
+            client,
+            acknowledgements,
+            queueing: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Transmitter for MqttPublisher {
+    async fn transmit(
+        &self,
+        message: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::MqttPublish(mqtt_publish) => {
+                let qos = match mqtt_publish.qos {
+                    QualityOfService::AtMostOnce => QoS::AtMostOnce,
+                    QualityOfService::AtLeastOnce => QoS::AtLeastOnce,
+                    QualityOfService::ExactlyOnce => QoS::ExactlyOnce,
+                };
+
+                // The publication is only queued, so its acknowledgement by the broker is awaited,
+                // which for QoS 0 is when it is sent.
+                let acknowledged = {
+                    let _queueing = self.queueing.lock().await;
+                    let acknowledged = self.acknowledgements.expect();
+                    if let Err(err) = self
+                        .client
+                        .publish(
+                            mqtt_publish.topic.clone(),
+                            qos,
+                            mqtt_publish.retain,
+                            mqtt_publish.payload.to_vec(),
+                        )
+                        .await
+                    {
+                        self.acknowledgements.cancel();
+                        return Err(err.into());
+                    }
+
+                    acknowledged
+                };
+
+                match tokio::time::timeout(ACKNOWLEDGEMENT_TIMEOUT, acknowledged).await {
+                    Ok(Ok(())) => {
+                        info!("transmitted mqtt message to topic '{}'", mqtt_publish.topic);
+
+                        Ok(())
+                    }
+                    Ok(Err(_)) => Err("mqtt event loop stopped before acknowledgement".into()),
+                    Err(_) => Err(format!(
+                        "mqtt publication to topic '{}' not acknowledged within {:?}",
+                        mqtt_publish.topic, ACKNOWLEDGEMENT_TIMEOUT
+                    )
+                    .into()),
+                }
+            }
+            _ => Err("mqtt publisher can only transmit mqtt messages".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use rumqttc::{AsyncClient, Event, MqttOptions, Packet};
+
+    use crate::model::MqttPublish;
+    use crate::mqtt::{connect_to_mqtt, Config};
+
+    fn config(client_id: &str) -> Config {
+        Config {
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: client_id.to_string(),
+            username: None,
+            password: None,
+        }
+    }
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with mosquitto, which is expected to run
+    // and be accessible.
+    //
+    // Run with `docker run -p 1883:1883 -ti eclipse-mosquitto:1.6`.
+    async fn test_transmitter_right_topic() {
+        let topic = "EVENTS/published";
+
+        let mut options = MqttOptions::new("transmit-test-subscriber", "localhost", 1883);
+        options.set_keep_alive(Duration::from_secs(5));
+        let (subscription_client, mut event_loop) = AsyncClient::new(options, 10);
+        subscription_client
+            .subscribe(topic, QoS::AtLeastOnce)
+            .await
+            .expect("subscribing should succeed");
+
+        // Wait for the subscription to be acknowledged, before publishing.
+        tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                let event = event_loop
+                    .poll()
+                    .await
+                    .expect("Mqtt connection failed. Is mosquitto running on port 1883?");
+                if let Event::Incoming(Packet::SubAck(_)) = event {
+                    break;
+                }
+            }
+        })
+        .await
+        .expect("subscription not acknowledged in time");
+
+        let (client, acknowledgements) = connect_to_mqtt(config("transmit-test-publisher"))
+            .await
+            .expect("Mqtt connection failed. Is mosquitto running on port 1883?");
+        let transmitter = MqttPublisher::new(client, acknowledgements);
+        transmitter
+            .transmit(
+                Message::MqttPublish(MqttPublish::new(
+                    topic.to_string(),
+                    "structured bytes containing order information".into(),
+                    QualityOfService::AtLeastOnce,
+                    false,
+                )),
+                &Occurrence::default(),
+            )
+            .await
+            .expect("transmission should succeed");
+
+        // Wait for the message to be received.
+        let publish = tokio::time::timeout(Duration::from_millis(500), async {
+            loop {
+                if let Event::Incoming(Packet::Publish(publish)) =
+                    event_loop.poll().await.expect("polling should succeed")
+                {
+                    return publish;
+                }
+            }
+        })
+        .await
+        .expect("subscribe did not receive message in time; timeout reached");
+
+        assert_eq!(publish.topic, topic);
+        assert_eq!(
+            publish.payload,
+            "structured bytes containing order information"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transmitter_rejects_other_messages() {
+        let (client, _event_loop) = AsyncClient::new(
+            MqttOptions::new("transmit-test-unconnected", "localhost", 1883),
+            10,
+        );
+        let transmitter = MqttPublisher::new(client, Arc::new(Acknowledgements::default()));
+
+        let result = transmitter
+            .transmit(
+                Message::NatsEvent(crate::model::NatsEvent::new(
+                    "SUBJECT.arbitrary".into(),
+                    "arbitrary payload".into(),
+                )),
+                &Occurrence::default(),
+            )
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use log::info;
 
 use crate::contract::Transmitter;
-use crate::model::Message;
+use crate::model::{Message, Occurrence};
 
 pub struct NatsPublisher {
     client: async_nats::Client,
@@ -18,7 +18,11 @@ impl NatsPublisher {
 
 #[async_trait]
 impl Transmitter for NatsPublisher {
-    async fn transmit(&self, event: Message) -> Result<(), Box<dyn Error + Send + Sync>> {
+    async fn transmit(
+        &self,
+        event: Message,
+        _occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         match event {
             Message::NatsEvent(nats_event) => {
                 self.client
@@ -29,6 +33,7 @@ impl Transmitter for NatsPublisher {
 
                 Ok(())
             }
+            _ => Err("nats publisher can only transmit nats events".into()),
         }
     }
 }
@@ -43,8 +48,8 @@ mod test {
     use bytes::Bytes;
     use futures::StreamExt;
 
-    use crate::model::NatsEvent;
-    use crate::nats::Config;
+    use crate::model::{NatsEvent, PayloadFormat};
+    use crate::nats::{Auth, Config};
 
     #[tokio::test]
     // This is a sociable unit test, i.e. it integrates with nats, which is expected to run and be
@@ -55,6 +60,9 @@ mod test {
         let nats_config = Config {
             port: 4222,
             host: "localhost".to_string(),
+            servers: vec![],
+            tls: None,
+            auth: Auth::Anonymous,
         };
         let address = format!("nats://{}:{}", nats_config.host, nats_config.port);
         let client = async_nats::connect(address)
@@ -65,7 +73,9 @@ mod test {
 
         let event = NatsEvent {
             subject,
-            payload: Bytes::from("structured bytes containing order information").into(),
+            payload: Bytes::from("structured bytes containing order information"),
+            templated: false,
+            format: PayloadFormat::default(),
         };
         let subject_clone = event.subject.clone();
 
@@ -91,7 +101,7 @@ mod test {
 
         let transmitter = NatsPublisher::new(client);
         transmitter
-            .transmit(Message::NatsEvent(event))
+            .transmit(Message::NatsEvent(event), &Occurrence::default())
             .await
             .expect("transmission should succeed");
 
@@ -125,7 +135,9 @@ mod test {
 
         let event = NatsEvent {
             subject: subject_publish,
-            payload: Bytes::from("structured bytes containing order information").into(),
+            payload: Bytes::from("structured bytes containing order information"),
+            templated: false,
+            format: PayloadFormat::default(),
         };
         let subject_clone = event.subject.clone();
 
@@ -151,7 +163,7 @@ mod test {
 
         let transmitter = NatsPublisher::new(client);
         transmitter
-            .transmit(Message::NatsEvent(event))
+            .transmit(Message::NatsEvent(event), &Occurrence::default())
             .await
             .expect("transmission should succeed");
 
@@ -0,0 +1,293 @@
+use std::error::Error;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::info;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Script};
+
+use crate::contract::Transmitter;
+use crate::model::{Message, Occurrence, RedisStreamAdd};
+use crate::redis::hash_tag;
+
+// DEDUPLICATION_TTL is how long the entry id of an occurrence is remembered, to recognise its
+// retries. It must exceed the period in which a failed occurrence may be retried.
+const DEDUPLICATION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// STREAM_ADD_SCRIPT appends the entry to the stream, unless the deduplication key shows the
+// occurrence was appended before. It runs atomically, such that concurrent retries cannot both
+// append the entry. Both keys hash to the same slot, as Redis Cluster requires of a script.
+//
+// KEYS: stream, deduplication key.
+// ARGV: deduplication ttl in seconds, max length or empty, followed by the field-value pairs.
+const STREAM_ADD_SCRIPT: &str = r"
+local entry_id = redis.call('GET', KEYS[2])
+if entry_id then
+    return entry_id
+end
+
+local arguments = {KEYS[1]}
+if ARGV[2] ~= '' then
+    table.insert(arguments, 'MAXLEN')
+    table.insert(arguments, '~')
+    table.insert(arguments, ARGV[2])
+end
+table.insert(arguments, '*')
+for index = 3, #ARGV do
+    table.insert(arguments, ARGV[index])
+end
+
+entry_id = redis.call('XADD', unpack(arguments))
+redis.call('SET', KEYS[2], entry_id, 'EX', ARGV[1])
+return entry_id
+";
+
+pub struct RedisPublisher {
+    connection: ConnectionManager,
+    stream_add_script: Script,
+}
+
+impl RedisPublisher {
+    pub fn new(connection: ConnectionManager) -> RedisPublisher {
+        RedisPublisher {
+            connection,
+            stream_add_script: Script::new(STREAM_ADD_SCRIPT),
+        }
+    }
+
+    async fn stream_add(
+        &self,
+        stream_add: &RedisStreamAdd,
+        occurrence: &Occurrence,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection.clone();
+
+        let mut invocation = self.stream_add_script.prepare_invoke();
+        invocation
+            .key(&stream_add.stream)
+            .key(deduplication_key(&stream_add.stream, occurrence))
+            .arg(DEDUPLICATION_TTL.as_secs())
+            .arg(
+                stream_add
+                    .max_len
+                    .map(|max_len| max_len.to_string())
+                    .unwrap_or_default(),
+            );
+        for (field, value) in &stream_add.fields {
+            invocation.arg(field).arg(value.as_ref());
+        }
+
+        Ok(invocation.invoke_async(&mut connection).await?)
+    }
+}
+
+// deduplication_key identifies the occurrence, such that a retried occurrence is not appended twice.
+// It shares the hash tag of the stream, or makes the stream its hash tag if it has none, such that
+// both keys are assigned the same cluster slot.
+fn deduplication_key(stream: &str, occurrence: &Occurrence) -> String {
+    let deduplication_id = format!(
+        "transmit-deduplication:{}:{}",
+        occurrence.transmission_id, occurrence.transmission_count
+    );
+
+    match hash_tag(stream) {
+        Some(_) => format!("{stream}:{deduplication_id}"),
+        None => format!("{{{stream}}}:{deduplication_id}"),
+    }
+}
+
+#[async_trait]
+impl Transmitter for RedisPublisher {
+    async fn transmit(
+        &self,
+        message: Message,
+        occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match message {
+            Message::RedisPublish(redis_publish) => {
+                let mut connection = self.connection.clone();
+                let receivers: u64 = connection
+                    .publish(&redis_publish.channel, redis_publish.payload.as_ref())
+                    .await?;
+
+                info!(
+                    "transmitted redis message to channel '{}' with {receivers} subscribers",
+                    redis_publish.channel
+                );
+
+                Ok(())
+            }
+            Message::RedisStreamAdd(redis_stream_add) => {
+                let entry_id = self.stream_add(&redis_stream_add, occurrence).await?;
+
+                info!(
+                    "transmitted redis entry '{entry_id}' to stream '{}'",
+                    redis_stream_add.stream
+                );
+
+                Ok(())
+            }
+            _ => Err("redis publisher can only transmit redis messages".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use futures_util::StreamExt;
+
+    use crate::model::RedisPublish;
+    use crate::redis::{connect_to_redis, Config};
+
+    fn config() -> Config {
+        Config {
+            host: "localhost".to_string(),
+            port: 6379,
+            db: 0,
+            username: None,
+            password: None,
+        }
+    }
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with redis, which is expected to run and be
+    // accessible.
+    //
+    // Run with `docker run -p 6379:6379 -ti redis:7`.
+    async fn test_transmitter_publish() {
+        let channel = format!("channel-{}", uuid::Uuid::new_v4());
+
+        let client = redis::Client::open("redis://localhost:6379").expect("url should be valid");
+        let mut subscriber = client
+            .get_async_connection()
+            .await
+            .expect("Redis connection failed. Is redis running on port 6379?")
+            .into_pubsub();
+        subscriber
+            .subscribe(&channel)
+            .await
+            .expect("subscribing should succeed");
+
+        let connection = connect_to_redis(config())
+            .await
+            .expect("Redis connection failed. Is redis running on port 6379?");
+        let transmitter = RedisPublisher::new(connection);
+        transmitter
+            .transmit(
+                Message::RedisPublish(RedisPublish::new(
+                    channel.clone(),
+                    "structured bytes containing order information".into(),
+                )),
+                &Occurrence::default(),
+            )
+            .await
+            .expect("transmission should succeed");
+
+        let message =
+            tokio::time::timeout(Duration::from_millis(500), subscriber.on_message().next())
+                .await
+                .expect("subscribe did not receive message in time; timeout reached")
+                .expect("subscription should yield a message");
+
+        assert_eq!(
+            message.get_payload_bytes(),
+            b"structured bytes containing order information"
+        );
+    }
+
+    #[tokio::test]
+    // This is a sociable unit test, i.e. it integrates with redis, which is expected to run and be
+    // accessible.
+    //
+    // Run with `docker run -p 6379:6379 -ti redis:7`.
+    async fn test_transmitter_stream_add_deduplicates() {
+        let stream = format!("stream-{}", uuid::Uuid::new_v4());
+
+        let mut connection = connect_to_redis(config())
+            .await
+            .expect("Redis connection failed. Is redis running on port 6379?");
+        let transmitter = RedisPublisher::new(connection.clone());
+
+        let message = Message::RedisStreamAdd(RedisStreamAdd::new(
+            stream.clone(),
+            BTreeMap::from([("order".to_string(), "1".into())]),
+            Some(1000),
+        ));
+
+        // The retry of the first occurrence must not append another entry.
+        for transmission_count in [0, 0, 1] {
+            let occurrence = Occurrence {
+                transmission_id: uuid::uuid!("a23bfa0f-a906-429a-ab90-66322dfa72e5"),
+                transmission_count,
+                ..Occurrence::default()
+            };
+            transmitter
+                .transmit(message.clone(), &occurrence)
+                .await
+                .expect("transmission should succeed");
+        }
+
+        let length: u64 = connection
+            .xlen(&stream)
+            .await
+            .expect("stream length should be queried");
+        assert_eq!(length, 2);
+    }
+
+    #[test]
+    fn test_deduplication_key() {
+        struct TestCase {
+            name: String,
+            stream: String,
+            expected_key: String,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: String::from("without hash tag"),
+                stream: String::from("orders"),
+                expected_key: String::from(
+                    "{orders}:transmit-deduplication:a23bfa0f-a906-429a-ab90-66322dfa72e5:3",
+                ),
+            },
+            TestCase {
+                name: String::from("with hash tag"),
+                stream: String::from("{tenant}.orders"),
+                expected_key: String::from(
+                    "{tenant}.orders:transmit-deduplication:a23bfa0f-a906-429a-ab90-66322dfa72e5:3",
+                ),
+            },
+            TestCase {
+                name: String::from("with unclosed brace"),
+                stream: String::from("{orders"),
+                expected_key: String::from(
+                    "{{orders}:transmit-deduplication:a23bfa0f-a906-429a-ab90-66322dfa72e5:3",
+                ),
+            },
+        ];
+
+        let occurrence = Occurrence {
+            transmission_id: uuid::uuid!("a23bfa0f-a906-429a-ab90-66322dfa72e5"),
+            transmission_count: 3,
+            ..Occurrence::default()
+        };
+        for test_case in test_cases {
+            let key = deduplication_key(&test_case.stream, &occurrence);
+            assert_eq!(
+                key, test_case.expected_key,
+                "test case failed: {}",
+                test_case.name
+            );
+            assert_eq!(
+                hash_tag(&key),
+                hash_tag(&test_case.stream).or(Some(&test_case.stream)),
+                "test case failed: {}",
+                test_case.name
+            );
+        }
+    }
+}
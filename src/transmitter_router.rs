@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::contract::Transmitter;
+use crate::model::{Message, Occurrence, PermanentTransmitError, Transport};
+
+// TransmitterRouter dispatches each message to the transmitter configured for its variant, such
+// that the scheduler can remain agnostic of the set of configured transmitters. Messages of a
+// variant without a configured transmitter are written to the sinks instead, if any.
+#[derive(Default)]
+pub struct TransmitterRouter {
+    nats: Option<Arc<dyn Transmitter>>,
+    mqtt: Option<Arc<dyn Transmitter>>,
+    amqp: Option<Arc<dyn Transmitter>>,
+    redis: Option<Arc<dyn Transmitter>>,
+    kafka: Option<Arc<dyn Transmitter>>,
+    grpc: Option<Arc<dyn Transmitter>>,
+    exec: Option<Arc<dyn Transmitter>>,
+    email: Option<Arc<dyn Transmitter>>,
+    sinks: Vec<Arc<dyn Transmitter>>,
+}
+
+impl TransmitterRouter {
+    pub fn new() -> TransmitterRouter {
+        TransmitterRouter::default()
+    }
+
+    pub fn with_nats(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            nats: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_mqtt(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            mqtt: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_amqp(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            amqp: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_redis(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            redis: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_kafka(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            kafka: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_grpc(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            grpc: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_exec(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            exec: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_email(self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        TransmitterRouter {
+            email: Some(transmitter),
+            ..self
+        }
+    }
+
+    pub fn with_sink(mut self, transmitter: Arc<dyn Transmitter>) -> TransmitterRouter {
+        self.sinks.push(transmitter);
+        self
+    }
+
+    // transports returns the transports that messages can be routed to, or None if sinks are
+    // configured, which accept messages of any transport.
+    pub fn transports(&self) -> Option<HashSet<Transport>> {
+        if !self.sinks.is_empty() {
+            return None;
+        }
+
+        let transports = [
+            Transport::Nats,
+            Transport::Mqtt,
+            Transport::Amqp,
+            Transport::Redis,
+            Transport::Kafka,
+            Transport::Grpc,
+            Transport::Exec,
+            Transport::Email,
+        ];
+        Some(
+            transports
+                .into_iter()
+                .filter(|transport| self.transmitter(*transport).is_some())
+                .collect(),
+        )
+    }
+
+    fn transmitter(&self, transport: Transport) -> &Option<Arc<dyn Transmitter>> {
+        match transport {
+            Transport::Nats => &self.nats,
+            Transport::Mqtt => &self.mqtt,
+            Transport::Amqp => &self.amqp,
+            Transport::Redis => &self.redis,
+            Transport::Kafka => &self.kafka,
+            Transport::Grpc => &self.grpc,
+            Transport::Exec => &self.exec,
+            Transport::Email => &self.email,
+        }
+    }
+}
+
+#[async_trait]
+impl Transmitter for TransmitterRouter {
+    async fn transmit(
+        &self,
+        message: Message,
+        occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let transmitter = match message.transport() {
+            Some(transport) => self.transmitter(transport),
+            None => {
+                return Err(
+                    "fan out messages must be transmitted to each destination separately".into(),
+                )
+            }
+        };
+
+        if let Some(transmitter) = transmitter {
+            return transmitter.transmit(message, occurrence).await;
+        }
+
+        // The transmitter may be missing from the configuration of this replica, or since the
+        // transmission was scheduled, in which case retrying does not help.
+        if self.sinks.is_empty() {
+            return Err(Box::new(PermanentTransmitError::new(format!(
+                "no transmitter configured for destination '{}'",
+                message.destination()
+            ))));
+        }
+
+        for sink in &self.sinks {
+            sink.transmit(message.clone(), occurrence).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mockall::predicate::*;
+
+    use crate::contract::MockTransmitter;
+    use crate::model::{MqttPublish, NatsEvent, QualityOfService};
+
+    #[tokio::test]
+    async fn test_route_to_configured_transmitter() {
+        let nats_message = Message::NatsEvent(NatsEvent::new(
+            "SUBJECT.arbitrary".into(),
+            "arbitrary payload".into(),
+        ));
+        let mqtt_message = Message::MqttPublish(MqttPublish::new(
+            "topic/arbitrary".into(),
+            "arbitrary payload".into(),
+            QualityOfService::AtLeastOnce,
+            false,
+        ));
+
+        let mut nats_transmitter = MockTransmitter::new();
+        nats_transmitter
+            .expect_transmit()
+            .with(eq(nats_message.clone()), always())
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let router = TransmitterRouter::new().with_nats(Arc::new(nats_transmitter));
+
+        router
+            .transmit(nats_message, &Occurrence::default())
+            .await
+            .expect("nats message should be routed to the nats transmitter");
+        let err = router
+            .transmit(mqtt_message, &Occurrence::default())
+            .await
+            .expect_err("mqtt message should fail without mqtt transmitter");
+        assert!(
+            err.is::<PermanentTransmitError>(),
+            "missing transmitter should fail permanently"
+        );
+        assert_eq!(router.transports(), Some(HashSet::from([Transport::Nats])));
+    }
+
+    #[tokio::test]
+    async fn test_route_to_sinks_without_configured_transmitter() {
+        let nats_message = Message::NatsEvent(NatsEvent::new(
+            "SUBJECT.arbitrary".into(),
+            "arbitrary payload".into(),
+        ));
+        let mqtt_message = Message::MqttPublish(MqttPublish::new(
+            "topic/arbitrary".into(),
+            "arbitrary payload".into(),
+            QualityOfService::AtLeastOnce,
+            false,
+        ));
+
+        let mut nats_transmitter = MockTransmitter::new();
+        nats_transmitter
+            .expect_transmit()
+            .with(eq(nats_message.clone()), always())
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut file_sink = MockTransmitter::new();
+        file_sink
+            .expect_transmit()
+            .with(eq(mqtt_message.clone()), eq(Occurrence::default()))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let mut stdout_sink = MockTransmitter::new();
+        stdout_sink
+            .expect_transmit()
+            .with(eq(mqtt_message.clone()), eq(Occurrence::default()))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let router = TransmitterRouter::new()
+            .with_nats(Arc::new(nats_transmitter))
+            .with_sink(Arc::new(file_sink))
+            .with_sink(Arc::new(stdout_sink));
+
+        router
+            .transmit(nats_message, &Occurrence::default())
+            .await
+            .expect("nats message should be routed to the nats transmitter");
+        router
+            .transmit(mqtt_message, &Occurrence::default())
+            .await
+            .expect("mqtt message should be written to the sinks");
+        assert_eq!(router.transports(), None);
+    }
+}
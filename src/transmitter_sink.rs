@@ -0,0 +1,138 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use log::info;
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::contract::Transmitter;
+use crate::model::{Message, Occurrence};
+
+// SinkWriter writes each message as a JSON line, along with its occurrence, e.g. to a file or
+// stdout. It accepts any message, so transmissions can be observed without the services they are
+// destined for.
+pub struct SinkWriter {
+    writer: Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
+}
+
+impl SinkWriter {
+    pub fn new(writer: impl AsyncWrite + Send + Unpin + 'static) -> SinkWriter {
+        SinkWriter {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    pub fn stdout() -> SinkWriter {
+        SinkWriter::new(tokio::io::stdout())
+    }
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    #[serde(flatten)]
+    occurrence: &'a Occurrence,
+    message: &'a Message,
+}
+
+#[async_trait]
+impl Transmitter for SinkWriter {
+    async fn transmit(
+        &self,
+        message: Message,
+        occurrence: &Occurrence,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut line = serde_json::to_vec(&Line {
+            occurrence,
+            message: &message,
+        })?;
+        line.push(b'\n');
+
+        // The lock is held until flushed, such that concurrent lines are not interleaved.
+        let mut writer = self.writer.lock().await;
+        writer.write_all(&line).await?;
+        writer.flush().await?;
+
+        info!(
+            "transmitted message for destination '{}' to sink",
+            message.destination()
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use chrono::prelude::*;
+
+    use crate::model::{Exec, NatsEvent};
+    use crate::sink::{open_file, Config};
+
+    #[tokio::test]
+    async fn test_transmitter_appends_lines() {
+        let path = std::env::temp_dir().join(format!("transmit-{}.jsonl", uuid::Uuid::new_v4()));
+        let config = Config {
+            path: path.to_string_lossy().to_string(),
+        };
+
+        let messages = vec![
+            Message::NatsEvent(NatsEvent::new(
+                "SUBJECT.arbitrary".into(),
+                "arbitrary payload".into(),
+            )),
+            Message::Exec(Exec::new(
+                "/usr/local/bin/backup".into(),
+                vec!["--full".into()],
+                std::collections::BTreeMap::new(),
+                std::time::Duration::from_secs(1),
+            )),
+        ];
+        let occurrence = Occurrence {
+            transmission_id: uuid::Uuid::new_v4(),
+            transmission_count: 2,
+            scheduled_at: Utc.with_ymd_and_hms(2015, 5, 15, 0, 0, 0).unwrap(),
+            transmitted_at: Utc.with_ymd_and_hms(2015, 5, 15, 0, 0, 1).unwrap(),
+        };
+
+        // Each message is appended, also when the file is opened again.
+        for message in &messages {
+            let file = open_file(config.clone())
+                .await
+                .expect("sink file should open");
+            SinkWriter::new(file)
+                .transmit(message.clone(), &occurrence)
+                .await
+                .expect("transmission should succeed");
+        }
+
+        let contents = tokio::fs::read_to_string(&path)
+            .await
+            .expect("sink file should be readable");
+        tokio::fs::remove_file(&path)
+            .await
+            .expect("sink file should be removed");
+
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("line should be json"))
+            .collect();
+        assert_eq!(lines.len(), messages.len());
+        for (line, message) in lines.iter().zip(&messages) {
+            assert_eq!(
+                line["transmission_id"],
+                occurrence.transmission_id.to_string()
+            );
+            assert_eq!(line["transmission_count"], 2);
+            assert_eq!(line["scheduled_at"], "2015-05-15T00:00:00Z");
+            assert_eq!(line["transmitted_at"], "2015-05-15T00:00:01Z");
+            assert_eq!(
+                serde_json::from_value::<Message>(line["message"].clone())
+                    .expect("message should deserialize"),
+                *message
+            );
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+// DEFAULT_SNAPSHOT_THRESHOLD is the number of log records after which the state is snapshotted.
+pub const DEFAULT_SNAPSHOT_THRESHOLD: u64 = 10_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    // directory contains the log and snapshot files, and is created if it does not exist.
+    pub directory: String,
+    #[serde(default)]
+    pub fsync: FsyncPolicy,
+    // snapshot_threshold is the number of log records after which the state is written to a
+    // snapshot and the log is truncated, which bounds the time to recover.
+    #[serde(default = "default_snapshot_threshold")]
+    pub snapshot_threshold: u64,
+}
+
+fn default_snapshot_threshold() -> u64 {
+    DEFAULT_SNAPSHOT_THRESHOLD
+}
+
+// FsyncPolicy determines when log records are synced to disk, i.e. which writes survive a power
+// loss. Writes survive a crash of the process regardless, since they are handed to the operating
+// system before they are acknowledged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum FsyncPolicy {
+    // Always syncs each record before the write is acknowledged.
+    #[default]
+    Always,
+    // Interval syncs the records written since the last sync periodically, such that at most the
+    // writes of the last interval are lost.
+    Interval(Duration),
+    // Never leaves syncing to the operating system.
+    Never,
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.directory.is_empty() {
+            return Err(String::from("wal directory cannot be empty"));
+        }
+        if self.snapshot_threshold == 0 {
+            return Err(String::from("wal snapshot threshold cannot be zero"));
+        }
+        if self.fsync == FsyncPolicy::Interval(Duration::ZERO) {
+            return Err(String::from("wal fsync interval cannot be zero"));
+        }
+
+        Ok(())
+    }
+}
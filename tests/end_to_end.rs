@@ -22,6 +22,9 @@ mod tests {
             // host is overridden, because the config file contains how the program itself
             // can find nats, which is through Docker's DNS.
             host: "0.0.0.0".to_string(),
+            servers: vec![],
+            tls: None,
+            auth: nats::Auth::Anonymous,
         })
         .await
         .expect("could not connect to nats on port 4222");
@@ -38,10 +41,9 @@ mod tests {
         let mut health_grpc_client =
             grpc::proto::health_client::HealthClient::connect(address.clone())
                 .await
-                .expect(&format!(
-                    "failed to connect to grpc server on address {}",
-                    &address
-                ));
+                .unwrap_or_else(|_| {
+                    panic!("failed to connect to grpc server on address {}", &address)
+                });
         let service = "transmit".to_string();
         let health_check_request = tonic::Request::new(grpc::proto::HealthCheckRequest { service });
         let response = health_grpc_client
@@ -57,10 +59,9 @@ mod tests {
         let mut grpc_client =
             grpc::proto::transmit_client::TransmitClient::connect(address.clone())
                 .await
-                .expect(&format!(
-                    "failed to connect to grpc server on address {}",
-                    &address
-                ));
+                .unwrap_or_else(|_| {
+                    panic!("failed to connect to grpc server on address {}", &address)
+                });
 
         // Construct the grpc request, containing a schedule and message.
         let message_schedule_request =
@@ -78,7 +79,7 @@ mod tests {
         // Invoke poll-transmit (second: ten_seconds_later), assert nothing happened.
         let received_flag_after_10s = Arc::new(Mutex::new(false));
         let _handle_cannot_be_joined = listen_for_transmission(
-            &mut nats_connection.clone(),
+            &nats_connection.clone(),
             subject.into(),
             received_flag_after_10s.clone(),
         )
@@ -97,7 +98,7 @@ mod tests {
         // Invoke another poll-transmit (third: one_minute_later), assert the transmission.
         let received_flag_after_60s = Arc::new(Mutex::new(false));
         let _handle = listen_for_transmission(
-            &mut nats_connection.clone(),
+            &nats_connection.clone(),
             subject.into(),
             received_flag_after_60s.clone(),
         )
@@ -117,7 +118,7 @@ mod tests {
         // Invoke another poll-transmit (fourth: two_minutes_later), assert happened.
         let received_flag_after_120s = Arc::new(Mutex::new(false));
         let _handle_cannot_be_joined = listen_for_transmission(
-            &mut nats_connection.clone(),
+            &nats_connection.clone(),
             subject.into(),
             received_flag_after_120s.clone(),
         )
@@ -144,14 +145,15 @@ mod tests {
         let nats_event = grpc::proto::NatsEvent {
             subject: subject.to_string(),
             payload: "Integration test payload.".into(),
+            templated: false,
+            format: None,
         };
         let message = grpc::proto::schedule_transmission_request::Message::NatsEvent(nats_event);
-        let schedule_transmission_request = grpc::proto::ScheduleTransmissionRequest {
+
+        grpc::proto::ScheduleTransmissionRequest {
             schedule: Some(schedule),
             message: Some(message),
-        };
-
-        schedule_transmission_request
+        }
     }
 
     async fn listen_for_transmission(